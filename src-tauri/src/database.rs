@@ -1,15 +1,250 @@
 use rusqlite::{Connection, Result};
-use std::path::PathBuf;
-use chrono::{DateTime, Utc, Local};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info, warn};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Utc};
+
+/// Version of the app writing new screenshot rows
+const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How long a connection waits for another one to release the database
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Every migration `run_migrations` applies, in order
+const MIGRATIONS: &[&str] = &[
+    "split_timestamps",
+    "add_error_logs",
+    "add_window_context",
+    "add_app_version",
+    "add_time_indexes",
+    "add_display_info",
+    "add_utc_offset",
+    "add_scale_factor",
+    "add_thumbnails",
+    "add_day_labels",
+    "add_change_score",
+    "add_frame_timings",
+];
+
+/// Screenshot metadata including the foreground app at capture time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenshotContext {
+    pub frame_number: u32,
+    pub created_at: String,
+    pub local_time: String,
+    pub app_name: Option<String>,
+    pub window_title: Option<String>,
+    /// Version of the app that captured the frame, `None` for frames captured
+    /// before versions were recorded
+    pub app_version: Option<String>,
+    /// The display the frame was captured from, `None` for frames captured
+    /// before displays were recorded
+    pub display: Option<FrameDisplay>,
+}
+
+/// The timestamps of one frame, as loaded in bulk for a day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameMeta {
+    pub frame_number: u32,
+    pub created_at: String,
+    pub local_time: String,
+}
+
+/// How much one frame changed from the frame saved before it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeScore {
+    pub frame_number: u32,
+    pub local_time: String,
+    /// Normalized RMSE against the previous frame, from 0 (identical) to 1
+    pub score: f64,
+}
+
+/// Identity and resolution of the display a frame was captured from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FrameDisplay {
+    pub id: u32,
+    /// Logical width in points
+    pub width: u32,
+    /// Logical height in points
+    pub height: u32,
+    /// Physical pixels per point, e.g. 2.0 on Retina displays
+    pub scale_factor: f32,
+}
+
+/// A saved frame waiting to be recorded by the `ScreenshotWriter`
+#[derive(Debug, Clone)]
+pub struct ScreenshotRecord {
+    pub frame_number: u32,
+    pub created_at: DateTime<Utc>,
+    pub local_time: DateTime<Local>,
+    pub app_name: Option<String>,
+    pub window_title: Option<String>,
+    pub display: Option<FrameDisplay>,
+    /// Encoded thumbnail, when thumbnail generation is on
+    pub thumbnail: Option<Vec<u8>>,
+    /// Difference from the previous frame, when change scoring is on
+    pub change_score: Option<f64>,
+    /// How long each stage of the capture took, when profiling is on
+    pub stage_timings: Vec<(&'static str, Duration)>,
+}
 
 pub struct ScreenshotDatabase {
     conn: Connection,
 }
 
+/// Columns `context_from_row` reads, in order
+const CONTEXT_COLUMNS: &str = "frame_number, created_at, local_time, app_name, window_title,
+    app_version, display_id, display_width, display_height, scale_factor";
+
+fn context_from_row(row: &rusqlite::Row) -> Result<ScreenshotContext> {
+    let display = match (row.get(6)?, row.get(7)?, row.get(8)?) {
+        (Some(id), Some(width), Some(height)) => Some(FrameDisplay {
+            id,
+            width,
+            height,
+            scale_factor: row.get::<_, Option<f32>>(9)?.unwrap_or(1.0),
+        }),
+        _ => None,
+    };
+    Ok(ScreenshotContext {
+        frame_number: row.get(0)?,
+        created_at: row.get(1)?,
+        local_time: row.get(2)?,
+        app_name: row.get(3)?,
+        window_title: row.get(4)?,
+        app_version: row.get(5)?,
+        display,
+    })
+}
+
+/// Condition on `local_hour` for a `[?1, ?2)` hour range, wrapping past midnight
+/// when the start is after the end and covering the whole day when they're equal
+fn local_hour_filter(start_hour: u8, end_hour: u8) -> &'static str {
+    match start_hour.cmp(&end_hour) {
+        std::cmp::Ordering::Less => "AND local_hour >= ?1 AND local_hour < ?2",
+        std::cmp::Ordering::Greater => "AND (local_hour >= ?1 OR local_hour < ?2)",
+        std::cmp::Ordering::Equal => "AND ?1 = ?2",
+    }
+}
+
 impl ScreenshotDatabase {
-    /// Create a new database connection and initialize the schema
+    /// Create a new database connection and initialize the schema.
+    /// A database that fails its integrity check is moved aside and replaced
+    /// with a fresh one, so a corrupted file doesn't stop capture entirely.
+    /// An existing database is backed up before any pending migration runs.
     pub fn new(db_path: PathBuf) -> Result<Self> {
+        let existed = db_path.metadata().is_ok_and(|metadata| metadata.len() > 0);
+        let conn = Connection::open(&db_path)?;
+
+        if !Self::passes_integrity_check(&conn)? {
+            drop(conn);
+
+            let quarantine_path = Self::quarantine(&db_path)?;
+            warn!(quarantine = ?quarantine_path, "Database failed integrity check, moved aside");
+
+            let db = Self::initialize(Connection::open(&db_path)?, None)?;
+            db.insert_error(
+                Utc::now(),
+                &format!(
+                    "Database was corrupted and has been moved to {}",
+                    quarantine_path.display()
+                ),
+            )?;
+            return Ok(db);
+        }
+
+        Self::initialize(conn, existed.then_some(db_path.as_path()))
+    }
+
+    /// A database that only lives in memory, for when nothing should touch the file
+    pub fn in_memory() -> Result<Self> {
+        Self::initialize(Connection::open_in_memory()?, None)
+    }
+
+    /// Write a consistent copy of the database open on `conn` next to `db_path`, as
+    /// `<name>.bak-<timestamp>`, returning the copy's path. SQLite writes the copy,
+    /// so it includes anything still in the journal, and an existing backup is
+    /// never overwritten.
+    fn back_up(conn: &Connection, db_path: &Path) -> Result<PathBuf> {
+        let timestamp = Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+        let mut base = db_path.as_os_str().to_owned();
+        base.push(format!(".bak-{}", timestamp));
+
+        let mut backup_path = PathBuf::from(&base);
+        let mut copies = 1;
+        while backup_path.exists() {
+            let mut numbered = base.clone();
+            numbered.push(format!("-{}", copies));
+            backup_path = PathBuf::from(numbered);
+            copies += 1;
+        }
+
+        conn.execute("VACUUM INTO ?1", [backup_path.to_string_lossy()])?;
+        Ok(backup_path)
+    }
+
+    /// Run `PRAGMA integrity_check`. A file SQLite reports as corrupt or not a
+    /// database at all fails the check; any other error (e.g. the file is
+    /// locked or unreadable) is returned, since moving it aside wouldn't help
+    fn passes_integrity_check(conn: &Connection) -> Result<bool> {
+        match conn.query_row("PRAGMA integrity_check", [], |row| row.get::<_, String>(0)) {
+            Ok(result) => Ok(result == "ok"),
+            Err(rusqlite::Error::SqliteFailure(error, _))
+                if matches!(
+                    error.code,
+                    rusqlite::ErrorCode::DatabaseCorrupt | rusqlite::ErrorCode::NotADatabase
+                ) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Rename a corrupted database (and any journal files) to
+    /// `<name>.corrupt.<timestamp>`, returning the new database path
+    fn quarantine(db_path: &Path) -> Result<PathBuf> {
+        let timestamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+        let mut quarantine_path = db_path.as_os_str().to_owned();
+        quarantine_path.push(format!(".corrupt.{}", timestamp));
+        let quarantine_path = PathBuf::from(quarantine_path);
+
+        std::fs::rename(db_path, &quarantine_path).map_err(|e| {
+            rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
+                Some(format!("Failed to move corrupted database aside: {}", e)),
+            )
+        })?;
+
+        // Stale journals would otherwise be replayed into the fresh database
+        for suffix in ["-journal", "-wal", "-shm"] {
+            let mut journal_path = db_path.as_os_str().to_owned();
+            journal_path.push(suffix);
+            let journal_path = PathBuf::from(journal_path);
+            if journal_path.exists() {
+                let mut quarantined_journal = quarantine_path.as_os_str().to_owned();
+                quarantined_journal.push(suffix);
+                let _ = std::fs::rename(&journal_path, PathBuf::from(quarantined_journal));
+            }
+        }
+
+        Ok(quarantine_path)
+    }
+
+    /// Open another connection to a database `new` has already set up
+    pub fn open_existing(db_path: &Path) -> Result<Self> {
         let conn = Connection::open(db_path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(ScreenshotDatabase { conn })
+    }
+
+    /// Create the base tables and bring the schema up to date. When `backup_from`
+    /// is given and migrations are pending, that file is backed up first.
+    fn initialize(conn: Connection, backup_from: Option<&Path>) -> Result<Self> {
+        conn.busy_timeout(BUSY_TIMEOUT)?;
 
         // Create migrations table if it doesn't exist
         conn.execute(
@@ -32,8 +267,33 @@ impl ScreenshotDatabase {
             [],
         )?;
 
+        // Back up before touching the schema, so a migration that fails partway
+        // can't lose data
+        let backup_path = match backup_from {
+            Some(db_path) if Self::has_pending_migrations(&conn)? => {
+                let backup_path = Self::back_up(&conn, db_path).inspect_err(|e| {
+                    error!(error = %e, "Failed to back up database before migrating");
+                })?;
+                info!(backup = ?backup_path, "Backed up database before migrating");
+                Some(backup_path)
+            }
+            _ => None,
+        };
+
         // Run migrations
-        Self::run_migrations(&conn)?;
+        if let Err(e) = Self::run_migrations(&conn) {
+            let Some(backup_path) = backup_path else {
+                return Err(e);
+            };
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_ERROR),
+                Some(format!(
+                    "Database migration failed: {}. A backup from before the migration is at {}",
+                    e,
+                    backup_path.display()
+                )),
+            ));
+        }
 
         Ok(Self { conn })
     }
@@ -58,7 +318,7 @@ impl ScreenshotDatabase {
                 })?;
 
             if has_old_schema && !has_new_schema {
-                println!("Migrating database: splitting creation_date into created_at and local_time");
+                info!("Migrating database: splitting creation_date into created_at and local_time");
 
                 // Rename the old table
                 conn.execute("ALTER TABLE screenshots RENAME TO screenshots_old", [])?;
@@ -84,16 +344,197 @@ impl ScreenshotDatabase {
                 // Drop old table
                 conn.execute("DROP TABLE screenshots_old", [])?;
 
-                println!("Database migration completed successfully");
+                info!("Database migration completed successfully");
             }
 
             // Record migration as applied
+            Self::record_migration(conn, "split_timestamps")?;
+        }
+
+        // Migration 2: Persist capture error logs across restarts
+        if !Self::migration_applied(conn, "add_error_logs")? {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS error_logs (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    timestamp TEXT NOT NULL,
+                    error_message TEXT NOT NULL
+                )",
+                [],
+            )?;
+
+            Self::record_migration(conn, "add_error_logs")?;
+        }
+
+        // Migration 3: Record the foreground app and window title for each frame
+        if !Self::migration_applied(conn, "add_window_context")? {
+            if !Self::column_exists(conn, "screenshots", "app_name")? {
+                conn.execute("ALTER TABLE screenshots ADD COLUMN app_name TEXT", [])?;
+            }
+            if !Self::column_exists(conn, "screenshots", "window_title")? {
+                conn.execute("ALTER TABLE screenshots ADD COLUMN window_title TEXT", [])?;
+            }
+
+            Self::record_migration(conn, "add_window_context")?;
+        }
+
+        // Migration 4: Record which app version captured each frame
+        if !Self::migration_applied(conn, "add_app_version")? {
+            if !Self::column_exists(conn, "screenshots", "app_version")? {
+                conn.execute("ALTER TABLE screenshots ADD COLUMN app_version TEXT", [])?;
+            }
+
+            Self::record_migration(conn, "add_app_version")?;
+        }
+
+        // Migration 5: Index the columns range and frame lookups filter on
+        if !Self::migration_applied(conn, "add_time_indexes")? {
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_screenshots_created_at ON screenshots(created_at)",
+                [],
+            )?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_screenshots_frame_number ON screenshots(frame_number)",
+                [],
+            )?;
+
+            Self::record_migration(conn, "add_time_indexes")?;
+        }
+
+        // Migration 6: Record which display each frame came from, and its resolution
+        if !Self::migration_applied(conn, "add_display_info")? {
+            for column in ["display_id", "display_width", "display_height"] {
+                if !Self::column_exists(conn, "screenshots", column)? {
+                    conn.execute(
+                        &format!("ALTER TABLE screenshots ADD COLUMN {} INTEGER", column),
+                        [],
+                    )?;
+                }
+            }
+
+            Self::record_migration(conn, "add_display_info")?;
+        }
+
+        // Migration 7: Record each frame's UTC offset so local hours survive DST and travel
+        if !Self::migration_applied(conn, "add_utc_offset")? {
+            if !Self::column_exists(conn, "screenshots", "utc_offset")? {
+                conn.execute("ALTER TABLE screenshots ADD COLUMN utc_offset INTEGER", [])?;
+            }
+            Self::backfill_utc_offsets(conn)?;
+
+            Self::record_migration(conn, "add_utc_offset")?;
+        }
+
+        // Migration 8: Record each display's scale factor alongside its logical size
+        if !Self::migration_applied(conn, "add_scale_factor")? {
+            if !Self::column_exists(conn, "screenshots", "scale_factor")? {
+                conn.execute("ALTER TABLE screenshots ADD COLUMN scale_factor REAL", [])?;
+            }
+
+            Self::record_migration(conn, "add_scale_factor")?;
+        }
+
+        // Migration 9: Small previews of each frame for browsing a day's grid
+        if !Self::migration_applied(conn, "add_thumbnails")? {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS thumbnails (
+                    date TEXT NOT NULL,
+                    frame_number INTEGER NOT NULL,
+                    data BLOB NOT NULL,
+                    PRIMARY KEY (date, frame_number)
+                )",
+                [],
+            )?;
+
+            Self::record_migration(conn, "add_thumbnails")?;
+        }
+
+        // Migration 10: User labels for capture days, kept out of the directory names
+        if !Self::migration_applied(conn, "add_day_labels")? {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS day_labels (
+                    date TEXT PRIMARY KEY,
+                    label TEXT NOT NULL
+                )",
+                [],
+            )?;
+
+            Self::record_migration(conn, "add_day_labels")?;
+        }
+
+        // Migration 11: How much each frame changed from the one before it
+        if !Self::migration_applied(conn, "add_change_score")? {
+            if !Self::column_exists(conn, "screenshots", "change_score")? {
+                conn.execute("ALTER TABLE screenshots ADD COLUMN change_score REAL", [])?;
+            }
+
+            Self::record_migration(conn, "add_change_score")?;
+        }
+
+        // Migration 12: Per-stage capture timings, for diagnosing a loop that falls behind
+        if !Self::migration_applied(conn, "add_frame_timings")? {
             conn.execute(
-                "INSERT INTO migrations (migration_name, applied_at) VALUES (?1, ?2)",
-                rusqlite::params!["split_timestamps", Utc::now().to_rfc3339()],
+                "CREATE TABLE IF NOT EXISTS frame_timings (
+                    date TEXT NOT NULL,
+                    frame_number INTEGER NOT NULL,
+                    stage TEXT NOT NULL,
+                    duration_ms REAL NOT NULL
+                )",
+                [],
+            )?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_frame_timings_date ON frame_timings(date)",
+                [],
             )?;
+
+            Self::record_migration(conn, "add_frame_timings")?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether any of `MIGRATIONS` hasn't been applied yet
+    fn has_pending_migrations(conn: &Connection) -> Result<bool> {
+        for name in MIGRATIONS {
+            if !Self::migration_applied(conn, name)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Fill in `utc_offset` for existing rows from the offset in their `local_time`.
+    /// Rows whose `local_time` doesn't parse are left without one.
+    fn backfill_utc_offsets(conn: &Connection) -> Result<()> {
+        let rows: Vec<(i64, String)> = conn
+            .prepare("SELECT id, local_time FROM screenshots WHERE utc_offset IS NULL")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_>>()?;
+
+        let mut update = conn.prepare("UPDATE screenshots SET utc_offset = ?1 WHERE id = ?2")?;
+        for (id, local_time) in rows {
+            if let Ok(local_time) = DateTime::parse_from_rfc3339(&local_time) {
+                update.execute(rusqlite::params![local_time.offset().local_minus_utc(), id])?;
+            }
         }
+        Ok(())
+    }
+
+    /// Check if a table has a column with the given name
+    fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+        let count: i32 = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+            [table, column],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
 
+    /// Record a migration as applied
+    fn record_migration(conn: &Connection, name: &str) -> Result<()> {
+        conn.execute(
+            "INSERT INTO migrations (migration_name, applied_at) VALUES (?1, ?2)",
+            rusqlite::params![name, Utc::now().to_rfc3339()],
+        )?;
         Ok(())
     }
 
@@ -107,24 +548,212 @@ impl ScreenshotDatabase {
         Ok(count > 0)
     }
 
-    /// Insert a new screenshot record
+    /// Insert a new screenshot record without a thumbnail, change score or timings.
+    /// Outside of tests, frames arrive as whole records through `insert_record`.
+    #[cfg(test)]
     pub fn insert_screenshot(
         &self,
         frame_number: u32,
         created_at: DateTime<Utc>,
         local_time: DateTime<Local>,
+        app_name: Option<&str>,
+        window_title: Option<&str>,
+        display: Option<FrameDisplay>,
     ) -> Result<()> {
+        self.insert_record(&ScreenshotRecord {
+            frame_number,
+            created_at,
+            local_time,
+            app_name: app_name.map(str::to_string),
+            window_title: window_title.map(str::to_string),
+            display,
+            thumbnail: None,
+            change_score: None,
+            stage_timings: Vec::new(),
+        })
+    }
+
+    /// Insert a screenshot record sent by the capture loop, along with its thumbnail
+    pub fn insert_record(&self, record: &ScreenshotRecord) -> Result<()> {
+        let display = record.display;
         self.conn.execute(
-            "INSERT INTO screenshots (frame_number, created_at, local_time) VALUES (?1, ?2, ?3)",
+            "INSERT INTO screenshots (frame_number, created_at, local_time, utc_offset, app_name,
+                                      window_title, app_version, display_id, display_width,
+                                      display_height, scale_factor, change_score)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
             rusqlite::params![
-                frame_number,
-                created_at.to_rfc3339(),
-                local_time.to_rfc3339()
+                record.frame_number,
+                record.created_at.to_rfc3339(),
+                record.local_time.to_rfc3339(),
+                record.local_time.offset().local_minus_utc(),
+                record.app_name,
+                record.window_title,
+                APP_VERSION,
+                display.map(|display| display.id),
+                display.map(|display| display.width),
+                display.map(|display| display.height),
+                display.map(|display| display.scale_factor),
+                record.change_score
             ],
         )?;
+        if let Some(thumbnail) = &record.thumbnail {
+            self.insert_thumbnail(record.local_time.date_naive(), record.frame_number, thumbnail)?;
+        }
+        if !record.stage_timings.is_empty() {
+            self.insert_frame_timings(
+                record.local_time.date_naive(),
+                record.frame_number,
+                &record.stage_timings,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Store how long each stage of frame `frame_number` of local day `date` took
+    pub fn insert_frame_timings(
+        &self,
+        date: NaiveDate,
+        frame_number: u32,
+        stage_timings: &[(&str, Duration)],
+    ) -> Result<()> {
+        let date = date.format("%Y-%m-%d").to_string();
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT INTO frame_timings (date, frame_number, stage, duration_ms)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (stage, duration) in stage_timings {
+            stmt.execute(rusqlite::params![
+                date,
+                frame_number,
+                stage,
+                duration.as_secs_f64() * 1000.0
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Every recorded `(stage, duration_ms)` of local day `date`, ordered by stage
+    pub fn get_frame_timings(&self, date: NaiveDate) -> Result<Vec<(String, f64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT stage, duration_ms FROM frame_timings WHERE date = ?1 ORDER BY stage",
+        )?;
+        let rows = stmt.query_map([date.format("%Y-%m-%d").to_string()], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+
+        rows.collect()
+    }
+
+    /// Delete the frame timings of every local day before `date`, returning how many were removed
+    pub fn delete_frame_timings_before(&self, date: NaiveDate) -> Result<usize> {
+        self.conn.execute(
+            "DELETE FROM frame_timings WHERE date < ?1",
+            [date.format("%Y-%m-%d").to_string()],
+        )
+    }
+
+    /// Delete the frame timings of local day `date`, returning how many were removed
+    pub fn delete_frame_timings_for(&self, date: NaiveDate) -> Result<usize> {
+        self.conn.execute(
+            "DELETE FROM frame_timings WHERE date = ?1",
+            [date.format("%Y-%m-%d").to_string()],
+        )
+    }
+
+    /// Store the thumbnail for frame `frame_number` of local day `date`, replacing any existing one
+    pub fn insert_thumbnail(&self, date: NaiveDate, frame_number: u32, data: &[u8]) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO thumbnails (date, frame_number, data) VALUES (?1, ?2, ?3)",
+            rusqlite::params![date.format("%Y-%m-%d").to_string(), frame_number, data],
+        )?;
+        Ok(())
+    }
+
+    /// The thumbnail for frame `frame_number` of local day `date`, if one was generated
+    pub fn get_thumbnail(&self, date: NaiveDate, frame_number: u32) -> Result<Option<Vec<u8>>> {
+        let result = self.conn.query_row(
+            "SELECT data FROM thumbnails WHERE date = ?1 AND frame_number = ?2",
+            rusqlite::params![date.format("%Y-%m-%d").to_string(), frame_number],
+            |row| row.get(0),
+        );
+
+        match result {
+            Ok(data) => Ok(Some(data)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Frame numbers of local day `date` that already have thumbnails, in ascending order
+    pub fn get_thumbnail_frame_numbers(&self, date: NaiveDate) -> Result<Vec<u32>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT frame_number FROM thumbnails WHERE date = ?1 ORDER BY frame_number")?;
+        let rows = stmt.query_map([date.format("%Y-%m-%d").to_string()], |row| row.get(0))?;
+
+        rows.collect()
+    }
+
+    /// Label local day `date`, or remove its label when `label` is `None`
+    pub fn set_day_label(&self, date: NaiveDate, label: Option<&str>) -> Result<()> {
+        let date = date.format("%Y-%m-%d").to_string();
+        match label {
+            Some(label) => self.conn.execute(
+                "INSERT OR REPLACE INTO day_labels (date, label) VALUES (?1, ?2)",
+                [&date, label],
+            )?,
+            None => self.conn.execute("DELETE FROM day_labels WHERE date = ?1", [&date])?,
+        };
         Ok(())
     }
 
+    /// Every day label as `(date, label)`, ordered by date
+    pub fn get_day_labels(&self) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare("SELECT date, label FROM day_labels ORDER BY date")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        rows.collect()
+    }
+
+    /// Delete the thumbnails of every local day before `date`, returning how many were removed
+    pub fn delete_thumbnails_before(&self, date: NaiveDate) -> Result<usize> {
+        self.conn.execute(
+            "DELETE FROM thumbnails WHERE date < ?1",
+            [date.format("%Y-%m-%d").to_string()],
+        )
+    }
+
+    /// Delete the thumbnails of local day `date`, returning how many were removed
+    pub fn delete_thumbnails_for(&self, date: NaiveDate) -> Result<usize> {
+        self.conn.execute(
+            "DELETE FROM thumbnails WHERE date = ?1",
+            [date.format("%Y-%m-%d").to_string()],
+        )
+    }
+
+    /// Delete the thumbnail of frame `frame_number` on local day `date`, if it has one
+    pub fn delete_thumbnail(&self, date: NaiveDate, frame_number: u32) -> Result<usize> {
+        self.conn.execute(
+            "DELETE FROM thumbnails WHERE date = ?1 AND frame_number = ?2",
+            rusqlite::params![date.format("%Y-%m-%d").to_string(), frame_number],
+        )
+    }
+
+    /// One past the highest frame number recorded on local day `date`, or 1 for a
+    /// new day. Nothing is reserved, and frames queued for the writer aren't
+    /// counted yet, so callers also check what's on disk.
+    pub fn next_frame_number(&self, date: NaiveDate) -> Result<u32> {
+        let (start, end) = crate::timelapse::local_day_bounds(date);
+
+        let max: Option<u32> = self.conn.query_row(
+            "SELECT MAX(frame_number) FROM screenshots WHERE created_at >= ?1 AND created_at < ?2",
+            rusqlite::params![start.to_rfc3339(), end.to_rfc3339()],
+            |row| row.get(0),
+        )?;
+
+        Ok(max.unwrap_or(0) + 1)
+    }
+
     /// Get screenshot metadata by frame number
     pub fn get_screenshot_by_frame(&self, frame_number: u32) -> Result<Option<(String, String)>> {
         let result = self.conn.query_row(
@@ -143,56 +772,642 @@ impl ScreenshotDatabase {
             Err(e) => Err(e),
         }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
-
-    #[test]
-    fn test_database_creation() {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test.db");
 
-        let db = ScreenshotDatabase::new(db_path.clone());
-        assert!(db.is_ok());
+    /// Get screenshot metadata by frame number, including the foreground app context
+    pub fn get_screenshot_context_by_frame(
+        &self,
+        frame_number: u32,
+    ) -> Result<Option<ScreenshotContext>> {
+        let result = self.conn.query_row(
+            &format!("SELECT {} FROM screenshots WHERE frame_number = ?1", CONTEXT_COLUMNS),
+            [frame_number],
+            context_from_row,
+        );
 
-        // Verify the database file was created
-        assert!(db_path.exists());
+        match result {
+            Ok(data) => Ok(Some(data)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 
-    #[test]
-    fn test_insert_screenshot() {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test.db");
-
-        let db = ScreenshotDatabase::new(db_path).unwrap();
+    /// Metadata for every screenshot captured in `[start, end)`, ordered by frame number
+    pub fn get_screenshot_contexts_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ScreenshotContext>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM screenshots WHERE created_at >= ?1 AND created_at < ?2
+             ORDER BY frame_number",
+            CONTEXT_COLUMNS
+        ))?;
+        let rows = stmt.query_map(
+            rusqlite::params![start.to_rfc3339(), end.to_rfc3339()],
+            context_from_row,
+        )?;
+        rows.collect()
+    }
 
-        // Insert a screenshot record
-        let frame_number = 1;
-        let created_at = Utc::now();
-        let local_time = Local::now();
-        let result = db.insert_screenshot(frame_number, created_at, local_time);
-        assert!(result.is_ok());
+    /// Get (frame_number, created_at, local_time) for screenshots captured between
+    /// `start` and `end` inclusive, ordered by frame number
+    pub fn get_screenshots_in_range(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(u32, String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT frame_number, created_at, local_time FROM screenshots
+             WHERE created_at BETWEEN ?1 AND ?2
+             ORDER BY frame_number",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![start.to_rfc3339(), end.to_rfc3339()],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
 
-        // Verify the record was inserted
-        let count: i32 = db.conn
-            .query_row("SELECT COUNT(*) FROM screenshots", [], |row| row.get(0))
-            .unwrap();
-        assert_eq!(count, 1);
+        rows.collect()
     }
 
-    #[test]
-    fn test_insert_multiple_screenshots() {
-        let temp_dir = TempDir::new().unwrap();
+    /// Timestamps of every screenshot captured in `[start, end)`, ordered by frame number
+    pub fn get_frame_metas_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<FrameMeta>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT frame_number, created_at, local_time FROM screenshots
+             WHERE created_at >= ?1 AND created_at < ?2
+             ORDER BY frame_number",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![start.to_rfc3339(), end.to_rfc3339()],
+            |row| {
+                Ok(FrameMeta {
+                    frame_number: row.get(0)?,
+                    created_at: row.get(1)?,
+                    local_time: row.get(2)?,
+                })
+            },
+        )?;
+
+        rows.collect()
+    }
+
+    /// Change scores of the frames captured in `[start, end)` that have one, ordered
+    /// by capture time
+    pub fn get_change_scores(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ChangeScore>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT frame_number, local_time, change_score FROM screenshots
+             WHERE change_score IS NOT NULL AND created_at >= ?1 AND created_at < ?2
+             ORDER BY created_at",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![start.to_rfc3339(), end.to_rfc3339()],
+            |row| {
+                Ok(ChangeScore {
+                    frame_number: row.get(0)?,
+                    local_time: row.get(1)?,
+                    score: row.get(2)?,
+                })
+            },
+        )?;
+
+        rows.collect()
+    }
+
+    /// Frames captured in `[start, end)` while `app_name` was in front, ignoring case,
+    /// ordered by capture time
+    pub fn get_frames_by_app(
+        &self,
+        app_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<FrameMeta>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT frame_number, created_at, local_time FROM screenshots
+             WHERE app_name = ?1 COLLATE NOCASE AND created_at >= ?2 AND created_at < ?3
+             ORDER BY created_at",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![app_name, start.to_rfc3339(), end.to_rfc3339()],
+            |row| {
+                Ok(FrameMeta {
+                    frame_number: row.get(0)?,
+                    created_at: row.get(1)?,
+                    local_time: row.get(2)?,
+                })
+            },
+        )?;
+
+        rows.collect()
+    }
+
+    /// Get (frame_number, created_at, local_time) for screenshots whose local hour of
+    /// capture is in `[start_hour, end_hour)`, ordered by capture time. Local time is
+    /// rebuilt from `created_at` and the offset recorded at capture, so it reflects
+    /// the timezone the frame was actually taken in. A range whose start is after its
+    /// end wraps past midnight, and one whose start equals its end covers the whole
+    /// day. Frames captured before offsets were recorded are skipped.
+    pub fn get_screenshots_by_local_hour(
+        &self,
+        start_hour: u8,
+        end_hour: u8,
+    ) -> Result<Vec<(u32, String, String)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT frame_number, created_at, utc_offset FROM (
+                 SELECT frame_number, created_at, utc_offset,
+                        CAST(strftime('%H', created_at, utc_offset || ' seconds') AS INTEGER)
+                            AS local_hour
+                 FROM screenshots WHERE utc_offset IS NOT NULL
+             )
+             WHERE local_hour IS NOT NULL {}
+             ORDER BY created_at",
+            local_hour_filter(start_hour, end_hour)
+        ))?;
+        let rows = stmt.query_map(rusqlite::params![start_hour, end_hour], |row| {
+            let frame_number: u32 = row.get(0)?;
+            let created_at: String = row.get(1)?;
+            let utc_offset: i32 = row.get(2)?;
+            Ok((frame_number, created_at, utc_offset))
+        })?;
+
+        let mut frames = Vec::new();
+        for row in rows {
+            let (frame_number, created_at, utc_offset) = row?;
+            let local_time = DateTime::parse_from_rfc3339(&created_at)
+                .ok()
+                .zip(FixedOffset::east_opt(utc_offset))
+                .map(|(created_at, offset)| created_at.with_timezone(&offset).to_rfc3339());
+            if let Some(local_time) = local_time {
+                frames.push((frame_number, created_at, local_time));
+            }
+        }
+        Ok(frames)
+    }
+
+    /// Frame numbers of the screenshots captured in `[start, end)` whose local hour of
+    /// capture is in `[start_hour, end_hour)`, ordered by capture time. The hour range
+    /// is read like in `get_screenshots_by_local_hour`.
+    pub fn get_frame_numbers_by_local_hour(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        start_hour: u8,
+        end_hour: u8,
+    ) -> Result<Vec<u32>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT frame_number FROM (
+                 SELECT frame_number, created_at,
+                        CAST(strftime('%H', created_at, utc_offset || ' seconds') AS INTEGER)
+                            AS local_hour
+                 FROM screenshots
+                 WHERE utc_offset IS NOT NULL AND created_at >= ?3 AND created_at < ?4
+             )
+             WHERE local_hour IS NOT NULL {}
+             ORDER BY created_at",
+            local_hour_filter(start_hour, end_hour)
+        ))?;
+        let rows = stmt.query_map(
+            rusqlite::params![start_hour, end_hour, start.to_rfc3339(), end.to_rfc3339()],
+            |row| row.get(0),
+        )?;
+
+        rows.collect()
+    }
+
+    /// Visit every screenshot as (frame_number, created_at, local_time) in insertion
+    /// order without loading them all into memory. Returns the number of rows visited.
+    pub fn get_all_screenshots<F, E>(&self, mut visit: F) -> std::result::Result<usize, E>
+    where
+        F: FnMut(u32, &str, &str) -> std::result::Result<(), E>,
+        E: From<rusqlite::Error>,
+    {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT frame_number, created_at, local_time FROM screenshots ORDER BY id")?;
+        let mut rows = stmt.query([])?;
+
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            let frame_number: u32 = row.get(0)?;
+            let created_at: String = row.get(1)?;
+            let local_time: String = row.get(2)?;
+            visit(frame_number, &created_at, &local_time)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Count screenshots captured in `[start, end)`, along with the first and last capture times
+    pub fn count_screenshots_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(u32, Option<String>, Option<String>)> {
+        self.conn.query_row(
+            "SELECT COUNT(*), MIN(created_at), MAX(created_at) FROM screenshots
+             WHERE created_at >= ?1 AND created_at < ?2",
+            rusqlite::params![start.to_rfc3339(), end.to_rfc3339()],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+    }
+
+    /// The first and last capture times across every screenshot, and how many there
+    /// are. Both times are `None` when there are no screenshots.
+    pub fn get_capture_bounds(&self) -> Result<(Option<String>, Option<String>, u32)> {
+        self.conn.query_row(
+            "SELECT MIN(created_at), MAX(created_at), COUNT(*) FROM screenshots",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+    }
+
+    /// The (frame_number, created_at, local_time) of the screenshot in `[start, end)`
+    /// captured closest to `target`, or `None` if there are none. Targets outside
+    /// the range get the first or last frame in it. Ties go to the earlier frame.
+    pub fn get_nearest_screenshot(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        target: DateTime<Utc>,
+    ) -> Result<Option<(u32, String, String)>> {
+        let params = [start.to_rfc3339(), end.to_rfc3339(), target.to_rfc3339()];
+        let nearest = |query: &str| {
+            let result = self.conn.query_row(
+                query,
+                rusqlite::params_from_iter(&params),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            );
+            match result {
+                Ok(row) => Ok(Some(row)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(e),
+            }
+        };
+        let before: Option<(u32, String, String)> = nearest(
+            "SELECT frame_number, created_at, local_time FROM screenshots
+             WHERE created_at >= ?1 AND created_at < ?2 AND created_at <= ?3
+             ORDER BY created_at DESC LIMIT 1",
+        )?;
+        let after: Option<(u32, String, String)> = nearest(
+            "SELECT frame_number, created_at, local_time FROM screenshots
+             WHERE created_at >= ?1 AND created_at < ?2 AND created_at > ?3
+             ORDER BY created_at ASC LIMIT 1",
+        )?;
+
+        let distance = |created_at: &str| {
+            DateTime::parse_from_rfc3339(created_at)
+                .map(|created_at| (created_at.with_timezone(&Utc) - target).abs())
+                .unwrap_or(chrono::TimeDelta::MAX)
+        };
+        Ok(match (before, after) {
+            (Some(before), Some(after)) if distance(&after.1) < distance(&before.1) => Some(after),
+            (Some(before), _) => Some(before),
+            (None, after) => after,
+        })
+    }
+
+    /// Count screenshots captured in `[start, end)` grouped by local (weekday, hour), with
+    /// weekday 0 being Sunday. Buckets come from the wall clock part of `local_time`, so a
+    /// frame counts towards the hour it was taken in wherever that was.
+    pub fn count_screenshots_by_weekday_hour(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(u8, u8, u32)>> {
+        // Without the offset stripped, strftime would normalize local_time back to UTC
+        let mut stmt = self.conn.prepare(
+            "SELECT weekday, hour, COUNT(*) FROM (
+                 SELECT CAST(strftime('%w', substr(local_time, 1, 19)) AS INTEGER) AS weekday,
+                        CAST(strftime('%H', substr(local_time, 1, 19)) AS INTEGER) AS hour
+                 FROM screenshots WHERE created_at >= ?1 AND created_at < ?2
+             )
+             WHERE weekday IS NOT NULL AND hour IS NOT NULL
+             GROUP BY weekday, hour",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![start.to_rfc3339(), end.to_rfc3339()],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        rows.collect()
+    }
+
+    /// Delete screenshots captured before `cutoff`, keeping any taken exactly at it.
+    /// Returns how many rows were removed.
+    pub fn delete_screenshots_before(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        self.conn.execute(
+            "DELETE FROM screenshots WHERE created_at < ?1",
+            [cutoff.to_rfc3339()],
+        )
+    }
+
+    /// Delete screenshots captured in `[start, end)`, returning how many rows were removed
+    pub fn delete_screenshots_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<usize> {
+        self.conn.execute(
+            "DELETE FROM screenshots WHERE created_at >= ?1 AND created_at < ?2",
+            rusqlite::params![start.to_rfc3339(), end.to_rfc3339()],
+        )
+    }
+
+    /// Replace every screenshot row with `(frame_number, created_at)` pairs recovered
+    /// from disk, in a single transaction. Window context and app version are unknown
+    /// for recovered rows and left empty. Returns the number of rows inserted.
+    pub fn replace_screenshots(&mut self, frames: &[(u32, DateTime<Utc>)]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM screenshots", [])?;
+        Self::insert_recovered(&tx, frames)?;
+        tx.commit()?;
+        Ok(frames.len())
+    }
+
+    /// Add rows for `(frame_number, created_at)` pairs recovered from disk, in a
+    /// single transaction, leaving the existing rows alone
+    pub fn insert_recovered_screenshots(
+        &mut self,
+        frames: &[(u32, DateTime<Utc>)],
+    ) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        Self::insert_recovered(&tx, frames)?;
+        tx.commit()?;
+        Ok(frames.len())
+    }
+
+    fn insert_recovered(conn: &Connection, frames: &[(u32, DateTime<Utc>)]) -> Result<()> {
+        let mut stmt = conn.prepare(
+            "INSERT INTO screenshots (frame_number, created_at, local_time, utc_offset)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (frame_number, created_at) in frames {
+            let local_time = created_at.with_timezone(&Local);
+            stmt.execute(rusqlite::params![
+                frame_number,
+                created_at.to_rfc3339(),
+                local_time.to_rfc3339(),
+                local_time.offset().local_minus_utc()
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Frame numbers of the screenshots captured in `[start, end)`, in ascending order
+    pub fn get_frame_numbers_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<u32>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT frame_number FROM screenshots
+             WHERE created_at >= ?1 AND created_at < ?2
+             ORDER BY frame_number",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![start.to_rfc3339(), end.to_rfc3339()],
+            |row| row.get(0),
+        )?;
+
+        rows.collect()
+    }
+
+    /// Delete the rows for `frame_numbers` captured in `[start, end)`, in a single
+    /// transaction, returning how many rows were removed
+    pub fn delete_frames_between(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        frame_numbers: &[u32],
+    ) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut removed = 0;
+        {
+            let mut stmt = tx.prepare(
+                "DELETE FROM screenshots
+                 WHERE frame_number = ?1 AND created_at >= ?2 AND created_at < ?3",
+            )?;
+            for frame_number in frame_numbers {
+                removed += stmt.execute(rusqlite::params![
+                    frame_number,
+                    start.to_rfc3339(),
+                    end.to_rfc3339()
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Rebuild the database file to reclaim space left by deleted rows, then let
+    /// SQLite refresh its query planner statistics. Must not be called inside a
+    /// transaction.
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM; PRAGMA optimize;")
+    }
+
+    /// Insert a new error log record
+    pub fn insert_error(&self, timestamp: DateTime<Utc>, error_message: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO error_logs (timestamp, error_message) VALUES (?1, ?2)",
+            rusqlite::params![timestamp.to_rfc3339(), error_message],
+        )?;
+        Ok(())
+    }
+
+    /// Get the most recent `limit` error logs, oldest first
+    pub fn get_recent_errors(&self, limit: usize) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, error_message FROM error_logs ORDER BY id DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map([limit as i64], |row| {
+            let timestamp: String = row.get(0)?;
+            let error_message: String = row.get(1)?;
+            Ok((timestamp, error_message))
+        })?;
+
+        let mut errors = rows.collect::<Result<Vec<_>>>()?;
+        errors.reverse();
+        Ok(errors)
+    }
+
+    /// Get one page of error records, newest first, along with the total number
+    /// of records
+    pub fn get_errors_page(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<(String, String)>, usize)> {
+        let total: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM error_logs", [], |row| row.get(0))?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, error_message FROM error_logs ORDER BY id DESC LIMIT ?1 OFFSET ?2",
+        )?;
+        let rows = stmt.query_map([limit as i64, offset as i64], |row| {
+            let timestamp: String = row.get(0)?;
+            let error_message: String = row.get(1)?;
+            Ok((timestamp, error_message))
+        })?;
+
+        Ok((rows.collect::<Result<Vec<_>>>()?, total as usize))
+    }
+
+    /// Delete all error log records
+    pub fn clear_errors(&self) -> Result<()> {
+        self.conn.execute("DELETE FROM error_logs", [])?;
+        Ok(())
+    }
+}
+
+enum WriterMessage {
+    Insert(ScreenshotRecord),
+    /// Called once every record queued before it has been written
+    Flush(Box<dyn FnOnce() + Send>),
+}
+
+/// Records screenshots on a dedicated thread with its own connection, so the
+/// capture loop never waits on queries from the frontend. Records still queued
+/// when the last handle is dropped are written before the thread exits.
+#[derive(Clone)]
+pub struct ScreenshotWriter {
+    sender: mpsc::UnboundedSender<WriterMessage>,
+    /// Why records couldn't be written, until `take_failures` collects them
+    failures: Arc<Mutex<Vec<String>>>,
+}
+
+impl ScreenshotWriter {
+    /// Start the writer thread on `db`
+    pub fn spawn(db: ScreenshotDatabase) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let failures = Arc::new(Mutex::new(Vec::new()));
+
+        let thread_failures = Arc::clone(&failures);
+        std::thread::spawn(move || {
+            while let Some(message) = receiver.blocking_recv() {
+                match message {
+                    WriterMessage::Insert(record) => {
+                        if let Err(e) = db.insert_record(&record) {
+                            error!(
+                                frame_number = record.frame_number,
+                                error = %e,
+                                "Failed to record frame"
+                            );
+                            let failure =
+                                format!("Failed to record frame {}: {}", record.frame_number, e);
+                            push_failure(&thread_failures, failure);
+                        }
+                    }
+                    WriterMessage::Flush(done) => done(),
+                }
+            }
+        });
+
+        ScreenshotWriter { sender, failures }
+    }
+
+    /// Queue `record` to be written, without waiting for it
+    pub fn insert(&self, record: ScreenshotRecord) {
+        let frame_number = record.frame_number;
+        if self.sender.send(WriterMessage::Insert(record)).is_err() {
+            error!(frame_number, "Database writer has stopped, dropping screenshot record");
+            let failure =
+                format!("Database writer has stopped, frame {} not recorded", frame_number);
+            push_failure(&self.failures, failure);
+        }
+    }
+
+    /// Failures to record frames since the last call, oldest first, for the
+    /// caller to report
+    pub fn take_failures(&self) -> Vec<String> {
+        self.failures.lock().map(|mut failures| std::mem::take(&mut *failures)).unwrap_or_default()
+    }
+
+    /// Wait until every record queued so far has been written
+    pub async fn flush(&self) {
+        let (done, flushed) = oneshot::channel();
+        let done = Box::new(move || {
+            let _ = done.send(());
+        });
+        if self.sender.send(WriterMessage::Flush(done)).is_ok() {
+            let _ = flushed.await;
+        }
+    }
+
+    /// `flush` for synchronous callers. Unlike waiting on a tokio channel, this is
+    /// fine on an async runtime thread, which it blocks until the writer catches up.
+    pub fn blocking_flush(&self) {
+        let (done, flushed) = std::sync::mpsc::sync_channel(1);
+        let done = Box::new(move || {
+            let _ = done.send(());
+        });
+        if self.sender.send(WriterMessage::Flush(done)).is_ok() {
+            let _ = flushed.recv();
+        }
+    }
+}
+
+fn push_failure(failures: &Mutex<Vec<String>>, failure: String) {
+    if let Ok(mut failures) = failures.lock() {
+        failures.push(failure);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_database_creation() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path.clone());
+        assert!(db.is_ok());
+
+        // Verify the database file was created
+        assert!(db_path.exists());
+    }
+
+    #[test]
+    fn test_insert_screenshot() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+
+        // Insert a screenshot record
+        let frame_number = 1;
+        let created_at = Utc::now();
+        let local_time = Local::now();
+        let result = db.insert_screenshot(frame_number, created_at, local_time, None, None, None);
+        assert!(result.is_ok());
+
+        // Verify the record was inserted
+        let count: i32 = db.conn
+            .query_row("SELECT COUNT(*) FROM screenshots", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_insert_multiple_screenshots() {
+        let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().join("test.db");
 
         let db = ScreenshotDatabase::new(db_path).unwrap();
 
         // Insert multiple screenshot records
         for i in 1..=5 {
-            let result = db.insert_screenshot(i, Utc::now(), Local::now());
+            let result = db.insert_screenshot(i, Utc::now(), Local::now(), None, None, None);
             assert!(result.is_ok());
         }
 
@@ -262,6 +1477,10 @@ mod tests {
             .unwrap();
         assert_eq!(frame_1_local_time, "2024-01-01T12:00:00Z");
 
+        // Frames captured before versions were recorded have none
+        let context = db.get_screenshot_context_by_frame(1).unwrap().unwrap();
+        assert!(context.app_version.is_none());
+
         // Verify migration was recorded
         let migration_count: i32 = db.conn
             .query_row(
@@ -290,4 +1509,940 @@ mod tests {
             .unwrap();
         assert_eq!(table_exists, 1);
     }
+
+    #[test]
+    fn test_time_indexes_created() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+
+        for index in ["idx_screenshots_created_at", "idx_screenshots_frame_number"] {
+            let index_exists: i32 = db
+                .conn
+                .query_row(
+                    "SELECT COUNT(*) FROM sqlite_master
+                     WHERE type='index' AND tbl_name='screenshots' AND name=?1",
+                    [index],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(index_exists, 1, "missing index {}", index);
+        }
+    }
+
+    #[test]
+    fn test_get_screenshot_context_by_frame() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+        db.insert_screenshot(1, Utc::now(), Local::now(), Some("Code"), Some("main.rs"), None)
+            .unwrap();
+        db.insert_screenshot(2, Utc::now(), Local::now(), None, None, None).unwrap();
+
+        let context = db.get_screenshot_context_by_frame(1).unwrap().unwrap();
+        assert_eq!(context.frame_number, 1);
+        assert_eq!(context.app_name.as_deref(), Some("Code"));
+        assert_eq!(context.window_title.as_deref(), Some("main.rs"));
+        assert_eq!(context.app_version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+
+        let context = db.get_screenshot_context_by_frame(2).unwrap().unwrap();
+        assert!(context.app_name.is_none());
+        assert!(context.window_title.is_none());
+
+        assert!(db.get_screenshot_context_by_frame(3).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_screenshot_display() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+        let display = FrameDisplay { id: 2, width: 1440, height: 900, scale_factor: 2.0 };
+        db.insert_screenshot(1, Utc::now(), Local::now(), None, None, Some(display)).unwrap();
+        db.insert_screenshot(2, Utc::now(), Local::now(), None, None, None).unwrap();
+
+        let context = db.get_screenshot_context_by_frame(1).unwrap().unwrap();
+        assert_eq!(context.display, Some(display));
+        let context = db.get_screenshot_context_by_frame(2).unwrap().unwrap();
+        assert_eq!(context.display, None);
+    }
+
+    #[test]
+    fn test_window_context_migration_on_existing_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        // A database created before the window context columns existed
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute(
+                "CREATE TABLE screenshots (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    frame_number INTEGER NOT NULL,
+                    created_at TEXT NOT NULL,
+                    local_time TEXT NOT NULL
+                )",
+                [],
+            ).unwrap();
+            conn.execute(
+                "INSERT INTO screenshots (frame_number, created_at, local_time) VALUES (?1, ?2, ?3)",
+                rusqlite::params![1, "2024-01-01T12:00:00Z", "2024-01-01T12:00:00Z"],
+            ).unwrap();
+        }
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+
+        // Existing rows keep their data with empty context
+        let context = db.get_screenshot_context_by_frame(1).unwrap().unwrap();
+        assert_eq!(context.created_at, "2024-01-01T12:00:00Z");
+        assert!(context.app_name.is_none());
+    }
+
+    #[test]
+    fn test_get_screenshots_in_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+
+        let base = Utc::now() - chrono::Duration::hours(1);
+        // Insert out of order to check the result is sorted by frame number
+        for i in [3, 1, 2, 4] {
+            let created_at = base + chrono::Duration::minutes(i);
+            db.insert_screenshot(
+                i as u32,
+                created_at,
+                created_at.with_timezone(&Local),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        }
+
+        let frames = db
+            .get_screenshots_in_range(
+                base + chrono::Duration::minutes(2),
+                base + chrono::Duration::minutes(4),
+            )
+            .unwrap();
+        let frame_numbers: Vec<u32> = frames.iter().map(|(frame, _, _)| *frame).collect();
+        assert_eq!(frame_numbers, vec![2, 3, 4]);
+        assert_eq!(frames[0].1, (base + chrono::Duration::minutes(2)).to_rfc3339());
+    }
+
+    #[test]
+    fn test_get_screenshots_by_local_hour() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+        // All captured at 12:00 UTC, in New York, London and Tokyo
+        for (frame_number, utc_offset) in [(1, -5 * 3600), (2, 0), (3, 9 * 3600)] {
+            db.conn
+                .execute(
+                    "INSERT INTO screenshots (frame_number, created_at, local_time, utc_offset)
+                     VALUES (?1, '2024-01-01T12:00:00+00:00', '', ?2)",
+                    rusqlite::params![frame_number, utc_offset],
+                )
+                .unwrap();
+        }
+
+        let frame_numbers = |start_hour, end_hour| -> Vec<u32> {
+            db.get_screenshots_by_local_hour(start_hour, end_hour)
+                .unwrap()
+                .iter()
+                .map(|(frame_number, _, _)| *frame_number)
+                .collect()
+        };
+        assert_eq!(frame_numbers(9, 17), vec![2]);
+        assert_eq!(frame_numbers(7, 8), vec![1]);
+        assert_eq!(frame_numbers(20, 8), vec![1, 3]);
+        assert_eq!(frame_numbers(0, 0), vec![1, 2, 3]);
+
+        let frames = db.get_screenshots_by_local_hour(21, 22).unwrap();
+        assert_eq!(frames[0].2, "2024-01-01T21:00:00+09:00");
+    }
+
+    #[test]
+    fn test_get_frame_numbers_by_local_hour() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+        // Noon in London on two days, and 21:00 in Tokyo on the first
+        for (frame_number, created_at, utc_offset) in [
+            (1, "2024-01-01T12:00:00+00:00", 0),
+            (2, "2024-01-01T12:00:00+00:00", 9 * 3600),
+            (3, "2024-01-02T12:00:00+00:00", 0),
+        ] {
+            db.conn
+                .execute(
+                    "INSERT INTO screenshots (frame_number, created_at, local_time, utc_offset)
+                     VALUES (?1, ?2, '', ?3)",
+                    rusqlite::params![frame_number, created_at, utc_offset],
+                )
+                .unwrap();
+        }
+
+        let start = "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let end = "2024-01-02T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(db.get_frame_numbers_by_local_hour(start, end, 9, 17).unwrap(), vec![1]);
+        assert_eq!(db.get_frame_numbers_by_local_hour(start, end, 20, 8).unwrap(), vec![2]);
+        assert_eq!(db.get_frame_numbers_by_local_hour(start, end, 0, 0).unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_utc_offset_backfilled_on_existing_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        // A database created before offsets were recorded
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute(
+                "CREATE TABLE screenshots (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    frame_number INTEGER NOT NULL,
+                    created_at TEXT NOT NULL,
+                    local_time TEXT NOT NULL
+                )",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO screenshots (frame_number, created_at, local_time)
+                 VALUES (1, '2024-01-01T12:00:00+00:00', '2024-01-01T14:00:00+02:00')",
+                [],
+            )
+            .unwrap();
+        }
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+        let utc_offset: i32 = db
+            .conn
+            .query_row("SELECT utc_offset FROM screenshots WHERE frame_number = 1", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(utc_offset, 7200);
+        assert_eq!(db.get_screenshots_by_local_hour(14, 15).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_get_screenshots_in_range_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+
+        let now = Utc::now();
+        let frames = db
+            .get_screenshots_in_range(now - chrono::Duration::hours(1), now)
+            .unwrap();
+        assert!(frames.is_empty());
+    }
+
+    #[test]
+    fn test_get_all_screenshots() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+        for i in 1..=3 {
+            db.insert_screenshot(i, Utc::now(), Local::now(), None, None, None).unwrap();
+        }
+
+        let mut frames = Vec::new();
+        let count = db
+            .get_all_screenshots(|frame_number, _, _| {
+                frames.push(frame_number);
+                Ok::<(), rusqlite::Error>(())
+            })
+            .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(frames, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_count_screenshots_between() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+
+        let base = Utc::now() - chrono::Duration::hours(2);
+        for i in 0..4 {
+            let created_at = base + chrono::Duration::minutes(i * 10);
+            db.insert_screenshot(
+                i as u32 + 1,
+                created_at,
+                created_at.with_timezone(&Local),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        }
+
+        // Only the middle two frames fall inside the range
+        let start = base + chrono::Duration::minutes(5);
+        let end = base + chrono::Duration::minutes(25);
+        let (count, first, last) = db.count_screenshots_between(start, end).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(first.unwrap(), (base + chrono::Duration::minutes(10)).to_rfc3339());
+        assert_eq!(last.unwrap(), (base + chrono::Duration::minutes(20)).to_rfc3339());
+
+        // An empty range has no first/last frame
+        let later = end + chrono::Duration::hours(1);
+        let (count, first, last) = db
+            .count_screenshots_between(later, later + chrono::Duration::hours(1))
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(first.is_none());
+        assert!(last.is_none());
+    }
+
+    #[test]
+    fn test_get_capture_bounds() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = ScreenshotDatabase::new(temp_dir.path().join("test.db")).unwrap();
+
+        assert_eq!(db.get_capture_bounds().unwrap(), (None, None, 0));
+
+        let base = Utc::now() - chrono::Duration::days(30);
+        for i in 0..3 {
+            let created_at = base + chrono::Duration::days(i * 10);
+            let local_time = created_at.with_timezone(&Local);
+            db.insert_screenshot(1, created_at, local_time, None, None, None).unwrap();
+        }
+
+        let (first, last, count) = db.get_capture_bounds().unwrap();
+        assert_eq!(first.unwrap(), base.to_rfc3339());
+        assert_eq!(last.unwrap(), (base + chrono::Duration::days(20)).to_rfc3339());
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_get_frame_metas_between() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+        let base = Utc::now() - chrono::Duration::hours(2);
+        // Inserted out of order, as a rebuilt database may be
+        for (frame_number, minutes) in [(3, 20), (1, 0), (2, 10), (4, 40)] {
+            let created_at = base + chrono::Duration::minutes(minutes);
+            let local_time = created_at.with_timezone(&Local);
+            db.insert_screenshot(frame_number, created_at, local_time, None, None, None)
+                .unwrap();
+        }
+
+        let end = base + chrono::Duration::minutes(30);
+        let frames = db.get_frame_metas_between(base, end).unwrap();
+        let frame_numbers: Vec<u32> = frames.iter().map(|frame| frame.frame_number).collect();
+        assert_eq!(frame_numbers, vec![1, 2, 3]);
+        assert_eq!(frames[0].created_at, base.to_rfc3339());
+        assert_eq!(frames[0].local_time, base.with_timezone(&Local).to_rfc3339());
+    }
+
+    #[test]
+    fn test_get_frames_by_app() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = ScreenshotDatabase::new(temp_dir.path().join("test.db")).unwrap();
+
+        let base = Utc::now() - chrono::Duration::hours(2);
+        for (frame_number, minutes, app_name) in
+            [(1, 0, Some("Code")), (2, 10, Some("Safari")), (3, 20, Some("code")), (4, 30, None)]
+        {
+            let created_at = base + chrono::Duration::minutes(minutes);
+            let local_time = created_at.with_timezone(&Local);
+            db.insert_screenshot(frame_number, created_at, local_time, app_name, None, None)
+                .unwrap();
+        }
+
+        let end = base + chrono::Duration::hours(1);
+        let frames = db.get_frames_by_app("CODE", base, end).unwrap();
+        let frame_numbers: Vec<u32> = frames.iter().map(|frame| frame.frame_number).collect();
+        assert_eq!(frame_numbers, vec![1, 3]);
+
+        let before_third = base + chrono::Duration::minutes(15);
+        assert_eq!(db.get_frames_by_app("Code", base, before_third).unwrap().len(), 1);
+        assert!(db.get_frames_by_app("Terminal", base, end).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_count_screenshots_by_weekday_hour() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+        // Monday 23:30 in New York is already Tuesday in UTC
+        for (frame_number, created_at, local_time) in [
+            (1, "2024-01-02T04:30:00+00:00", "2024-01-01T23:30:00-05:00"),
+            (2, "2024-01-02T04:40:00+00:00", "2024-01-01T23:40:00-05:00"),
+            (3, "2024-01-07T10:00:00+00:00", "2024-01-07T10:00:00+00:00"),
+            (4, "2024-01-09T10:00:00+00:00", "2024-01-09T10:00:00+00:00"),
+        ] {
+            db.conn
+                .execute(
+                    "INSERT INTO screenshots (frame_number, created_at, local_time)
+                     VALUES (?1, ?2, ?3)",
+                    rusqlite::params![frame_number, created_at, local_time],
+                )
+                .unwrap();
+        }
+
+        let start = "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let end = "2024-01-08T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let mut buckets = db.count_screenshots_by_weekday_hour(start, end).unwrap();
+        buckets.sort();
+        assert_eq!(buckets, vec![(0, 10, 1), (1, 23, 2)]);
+    }
+
+    #[test]
+    fn test_delete_screenshots_before() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+
+        let cutoff = Utc::now();
+        for (frame_number, created_at) in [
+            (1, cutoff - chrono::Duration::seconds(1)),
+            (2, cutoff),
+            (3, cutoff + chrono::Duration::seconds(1)),
+        ] {
+            db.insert_screenshot(
+                frame_number,
+                created_at,
+                created_at.with_timezone(&Local),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        }
+
+        // A frame taken exactly at the cutoff is kept
+        assert_eq!(db.delete_screenshots_before(cutoff).unwrap(), 1);
+        assert!(db.get_screenshot_by_frame(1).unwrap().is_none());
+        assert!(db.get_screenshot_by_frame(2).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_delete_screenshots_between() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+
+        let base = Utc::now() - chrono::Duration::days(2);
+        for i in 0..4 {
+            let created_at = base + chrono::Duration::days(i);
+            db.insert_screenshot(
+                i as u32 + 1,
+                created_at,
+                created_at.with_timezone(&Local),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        }
+
+        // Remove only the second day
+        let start = base + chrono::Duration::hours(12);
+        let end = base + chrono::Duration::hours(36);
+        assert_eq!(db.delete_screenshots_between(start, end).unwrap(), 1);
+
+        let count: i32 = db.conn
+            .query_row("SELECT COUNT(*) FROM screenshots", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 3);
+        assert!(db.get_screenshot_by_frame(2).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_next_frame_number() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = ScreenshotDatabase::new(temp_dir.path().join("test.db")).unwrap();
+
+        let today = Local::now().date_naive();
+        assert_eq!(db.next_frame_number(today).unwrap(), 1);
+
+        let now = Utc::now();
+        for frame_number in [1, 2, 5] {
+            db.insert_screenshot(frame_number, now, now.with_timezone(&Local), None, None, None)
+                .unwrap();
+        }
+        assert_eq!(db.next_frame_number(today).unwrap(), 6);
+
+        // Other days number independently
+        let tomorrow = today.succ_opt().unwrap();
+        assert_eq!(db.next_frame_number(tomorrow).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_replace_screenshots() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = ScreenshotDatabase::new(temp_dir.path().join("test.db")).unwrap();
+
+        let now = Utc::now();
+        db.insert_screenshot(7, now, now.with_timezone(&Local), Some("Finder"), None, None)
+            .unwrap();
+
+        let frames = vec![(1, now), (2, now + chrono::Duration::seconds(1))];
+        assert_eq!(db.replace_screenshots(&frames).unwrap(), 2);
+
+        assert!(db.get_screenshot_by_frame(7).unwrap().is_none());
+        let context = db.get_screenshot_context_by_frame(2).unwrap().unwrap();
+        assert_eq!(context.created_at, frames[1].1.to_rfc3339());
+        assert_eq!(context.app_name, None);
+        assert_eq!(context.app_version, None);
+    }
+
+    #[test]
+    fn test_frames_between_day_bounds() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = ScreenshotDatabase::new(temp_dir.path().join("test.db")).unwrap();
+
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let end = start + chrono::Duration::hours(2);
+        db.insert_recovered_screenshots(&[(1, start), (2, start), (3, end)]).unwrap();
+        assert_eq!(db.get_frame_numbers_between(start, end).unwrap(), vec![1, 2]);
+
+        // Frame 3 falls outside the range and is kept
+        assert_eq!(db.delete_frames_between(start, end, &[2, 3]).unwrap(), 1);
+        assert_eq!(db.get_frame_numbers_between(start, end).unwrap(), vec![1]);
+        assert!(db.get_screenshot_by_frame(3).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_vacuum_after_deleting_rows() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = ScreenshotDatabase::new(temp_dir.path().join("test.db")).unwrap();
+
+        let now = Utc::now();
+        for frame_number in 1..=500 {
+            db.insert_screenshot(
+                frame_number,
+                now,
+                now.with_timezone(&Local),
+                Some("Terminal"),
+                Some("a window title long enough to take up some space"),
+                None,
+            )
+            .unwrap();
+        }
+        let start = now - chrono::Duration::seconds(1);
+        let end = now + chrono::Duration::seconds(1);
+        assert_eq!(db.delete_screenshots_between(start, end).unwrap(), 500);
+
+        db.vacuum().unwrap();
+        assert_eq!(db.count_screenshots_between(start, end).unwrap().0, 0);
+    }
+
+    #[test]
+    fn test_get_errors_page() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+
+        for i in 0..5 {
+            db.insert_error(Utc::now(), &format!("Error {}", i)).unwrap();
+        }
+
+        let (page, total) = db.get_errors_page(0, 2).unwrap();
+        assert_eq!(total, 5);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].1, "Error 4");
+        assert_eq!(page[1].1, "Error 3");
+
+        let (page, total) = db.get_errors_page(4, 2).unwrap();
+        assert_eq!(total, 5);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].1, "Error 0");
+
+        let (page, _) = db.get_errors_page(10, 2).unwrap();
+        assert!(page.is_empty());
+    }
+
+    #[test]
+    fn test_corrupted_database_is_quarantined() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("screenshots.db");
+
+        std::fs::write(&db_path, b"this is definitely not a sqlite database, just garbage bytes")
+            .unwrap();
+
+        let db = ScreenshotDatabase::new(db_path.clone()).unwrap();
+
+        // The fresh database is usable
+        db.insert_screenshot(1, Utc::now(), Local::now(), None, None, None).unwrap();
+        assert!(db.get_screenshot_by_frame(1).unwrap().is_some());
+
+        // The corrupted file was kept for inspection
+        let quarantined: Vec<_> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("screenshots.db.corrupt."))
+            .collect();
+        assert_eq!(quarantined.len(), 1);
+
+        // The recovery was recorded in the error log
+        let errors = db.get_recent_errors(10).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].1.contains("corrupted"));
+    }
+
+    #[test]
+    fn test_locked_database_is_not_quarantined() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("screenshots.db");
+        drop(ScreenshotDatabase::new(db_path.clone()).unwrap());
+
+        // Another process holding the database is an error, not corruption
+        let holder = Connection::open(&db_path).unwrap();
+        holder.execute_batch("BEGIN EXCLUSIVE").unwrap();
+        assert!(ScreenshotDatabase::new(db_path.clone()).is_err());
+        holder.execute_batch("COMMIT").unwrap();
+
+        assert!(db_path.exists());
+        let corrupt_files = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().contains(".corrupt."))
+            .count();
+        assert_eq!(corrupt_files, 0);
+    }
+
+    fn backup_files(dir: &Path) -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("screenshots.db.bak-"))
+            .map(|entry| entry.path())
+            .collect()
+    }
+
+    #[test]
+    fn test_fresh_database_applies_every_migration_in_order() {
+        let db = ScreenshotDatabase::in_memory().unwrap();
+        let applied: Vec<String> = db
+            .conn
+            .prepare("SELECT migration_name FROM migrations ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(applied, MIGRATIONS);
+    }
+
+    #[test]
+    fn test_back_up_never_overwrites() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("screenshots.db");
+        let db = ScreenshotDatabase::new(db_path.clone()).unwrap();
+        db.insert_screenshot(1, Utc::now(), Local::now(), None, None, None).unwrap();
+
+        let first = ScreenshotDatabase::back_up(&db.conn, &db_path).unwrap();
+        let second = ScreenshotDatabase::back_up(&db.conn, &db_path).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(backup_files(temp_dir.path()).len(), 2);
+
+        let backup = Connection::open(&second).unwrap();
+        let frames: u32 = backup
+            .query_row("SELECT COUNT(*) FROM screenshots", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(frames, 1);
+    }
+
+    #[test]
+    fn test_backup_before_pending_migration() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("screenshots.db");
+
+        // A new database and one that's already up to date aren't backed up
+        {
+            let db = ScreenshotDatabase::new(db_path.clone()).unwrap();
+            db.insert_screenshot(1, Utc::now(), Local::now(), None, None, None).unwrap();
+        }
+        drop(ScreenshotDatabase::new(db_path.clone()).unwrap());
+        assert!(backup_files(temp_dir.path()).is_empty());
+
+        // Simulate a database from before the latest migration
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute("DROP TABLE frame_timings", []).unwrap();
+            conn.execute(
+                "DELETE FROM migrations WHERE migration_name = 'add_frame_timings'",
+                [],
+            )
+            .unwrap();
+        }
+
+        let db = ScreenshotDatabase::new(db_path.clone()).unwrap();
+        assert!(db.get_screenshot_by_frame(1).unwrap().is_some());
+        assert!(ScreenshotDatabase::migration_applied(&db.conn, "add_frame_timings").unwrap());
+
+        let backups = backup_files(temp_dir.path());
+        assert_eq!(backups.len(), 1);
+        let backup = Connection::open(&backups[0]).unwrap();
+        assert!(!ScreenshotDatabase::migration_applied(&backup, "add_frame_timings").unwrap());
+        let frames: u32 = backup
+            .query_row("SELECT COUNT(*) FROM screenshots", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(frames, 1);
+    }
+
+    #[test]
+    fn test_healthy_database_is_not_quarantined() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("screenshots.db");
+
+        {
+            let db = ScreenshotDatabase::new(db_path.clone()).unwrap();
+            db.insert_screenshot(1, Utc::now(), Local::now(), None, None, None).unwrap();
+        }
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+        assert!(db.get_screenshot_by_frame(1).unwrap().is_some());
+        let corrupt_files = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().contains(".corrupt."))
+            .count();
+        assert_eq!(corrupt_files, 0);
+    }
+
+    #[test]
+    fn test_insert_and_get_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+
+        for i in 0..5 {
+            db.insert_error(Utc::now(), &format!("Error {}", i)).unwrap();
+        }
+
+        // Only the last 3 should be returned, oldest first
+        let errors = db.get_recent_errors(3).unwrap();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].1, "Error 2");
+        assert_eq!(errors[2].1, "Error 4");
+
+        db.clear_errors().unwrap();
+        assert_eq!(db.get_recent_errors(10).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_error_logs_survive_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        {
+            let db = ScreenshotDatabase::new(db_path.clone()).unwrap();
+            db.insert_error(Utc::now(), "Persisted error").unwrap();
+        }
+
+        let db = ScreenshotDatabase::new(db_path).unwrap();
+        let errors = db.get_recent_errors(10).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].1, "Persisted error");
+
+        let migration_count: i32 = db.conn
+            .query_row(
+                "SELECT COUNT(*) FROM migrations WHERE migration_name = 'add_error_logs'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(migration_count, 1);
+    }
+
+    fn record(frame_number: u32) -> ScreenshotRecord {
+        ScreenshotRecord {
+            frame_number,
+            created_at: Utc::now(),
+            local_time: Local::now(),
+            app_name: Some("Code".to_string()),
+            window_title: None,
+            display: None,
+            thumbnail: None,
+            change_score: None,
+            stage_timings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_thumbnails() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = ScreenshotDatabase::new(temp_dir.path().join("test.db")).unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let next_day = day.succ_opt().unwrap();
+
+        db.insert_thumbnail(day, 2, b"second").unwrap();
+        db.insert_thumbnail(day, 1, b"first").unwrap();
+        db.insert_thumbnail(day, 1, b"replaced").unwrap();
+        db.insert_thumbnail(next_day, 1, b"next day").unwrap();
+
+        assert_eq!(db.get_thumbnail(day, 1).unwrap(), Some(b"replaced".to_vec()));
+        assert_eq!(db.get_thumbnail(day, 3).unwrap(), None);
+        assert_eq!(db.get_thumbnail_frame_numbers(day).unwrap(), vec![1, 2]);
+
+        assert_eq!(db.delete_thumbnails_before(next_day).unwrap(), 2);
+        assert!(db.get_thumbnail_frame_numbers(day).unwrap().is_empty());
+        assert_eq!(db.delete_thumbnails_for(next_day).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_day_labels() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = ScreenshotDatabase::new(temp_dir.path().join("test.db")).unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let earlier_day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+
+        db.set_day_label(day, Some("draft")).unwrap();
+        db.set_day_label(day, Some("client demo")).unwrap();
+        db.set_day_label(earlier_day, Some("kickoff")).unwrap();
+        assert_eq!(
+            db.get_day_labels().unwrap(),
+            vec![
+                ("2024-03-01".to_string(), "kickoff".to_string()),
+                ("2024-03-15".to_string(), "client demo".to_string()),
+            ]
+        );
+
+        db.set_day_label(earlier_day, None).unwrap();
+        assert_eq!(db.get_day_labels().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_insert_record_with_thumbnail() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = ScreenshotDatabase::new(temp_dir.path().join("test.db")).unwrap();
+
+        let record = ScreenshotRecord { thumbnail: Some(b"thumb".to_vec()), ..record(4) };
+        db.insert_record(&record).unwrap();
+        let date = record.local_time.date_naive();
+        assert_eq!(db.get_thumbnail(date, 4).unwrap(), Some(b"thumb".to_vec()));
+    }
+
+    #[test]
+    fn test_change_scores() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = ScreenshotDatabase::new(temp_dir.path().join("test.db")).unwrap();
+
+        db.insert_record(&record(1)).unwrap();
+        db.insert_record(&ScreenshotRecord { change_score: Some(0.25), ..record(2) }).unwrap();
+        db.insert_record(&ScreenshotRecord { change_score: Some(0.0), ..record(3) }).unwrap();
+
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let end = Utc::now() + chrono::Duration::hours(1);
+        let scores = db.get_change_scores(start, end).unwrap();
+        let scores: Vec<(u32, f64)> = scores.iter().map(|s| (s.frame_number, s.score)).collect();
+        assert_eq!(scores, vec![(2, 0.25), (3, 0.0)]);
+
+        assert!(db.get_change_scores(end, end + chrono::Duration::hours(1)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_frame_timings() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = ScreenshotDatabase::new(temp_dir.path().join("test.db")).unwrap();
+
+        let profiled = ScreenshotRecord {
+            stage_timings: vec![
+                ("render", Duration::from_millis(40)),
+                ("encode", Duration::from_millis(120)),
+            ],
+            ..record(1)
+        };
+        db.insert_record(&profiled).unwrap();
+        db.insert_record(&record(2)).unwrap();
+
+        let date = profiled.local_time.date_naive();
+        assert_eq!(
+            db.get_frame_timings(date).unwrap(),
+            vec![("encode".to_string(), 120.0), ("render".to_string(), 40.0)]
+        );
+
+        assert_eq!(db.delete_frame_timings_before(date).unwrap(), 0);
+        assert_eq!(db.delete_frame_timings_for(date).unwrap(), 2);
+        assert!(db.get_frame_timings(date).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_screenshot_writer_flush() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path.clone()).unwrap();
+        let writer = ScreenshotWriter::spawn(ScreenshotDatabase::open_existing(&db_path).unwrap());
+        for frame_number in 1..=3 {
+            writer.insert(record(frame_number));
+        }
+        writer.blocking_flush();
+
+        // Rows written on the writer's connection are visible on the other one
+        let context = db.get_screenshot_context_by_frame(3).unwrap().unwrap();
+        assert_eq!(context.app_name.as_deref(), Some("Code"));
+        assert_eq!(db.get_all_screenshots(|_, _, _| Ok::<_, rusqlite::Error>(())).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_screenshot_writer_reports_failures() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path.clone()).unwrap();
+        let writer = ScreenshotWriter::spawn(ScreenshotDatabase::open_existing(&db_path).unwrap());
+        writer.insert(record(1));
+        writer.blocking_flush();
+        assert!(writer.take_failures().is_empty());
+
+        db.conn.execute("DROP TABLE screenshots", []).unwrap();
+        writer.insert(record(2));
+        writer.blocking_flush();
+        let failures = writer.take_failures();
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].contains("frame 2"), "{}", failures[0]);
+        assert!(writer.take_failures().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_screenshot_writer_blocking_flush_on_runtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path.clone()).unwrap();
+        let writer = ScreenshotWriter::spawn(ScreenshotDatabase::open_existing(&db_path).unwrap());
+        writer.insert(record(1));
+        writer.blocking_flush();
+        assert!(db.get_screenshot_context_by_frame(1).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_screenshot_writer_clones_share_queue() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ScreenshotDatabase::new(db_path.clone()).unwrap();
+        let writer = ScreenshotWriter::spawn(ScreenshotDatabase::open_existing(&db_path).unwrap());
+        let other_handle = writer.clone();
+        writer.insert(record(1));
+        drop(writer);
+
+        other_handle.insert(record(2));
+        other_handle.blocking_flush();
+        assert!(db.get_screenshot_context_by_frame(1).unwrap().is_some());
+        assert!(db.get_screenshot_context_by_frame(2).unwrap().is_some());
+    }
 }
@@ -1,10 +1,17 @@
 mod timelapse;
 mod database;
 
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::{Arc, Mutex};
-use tauri::{Manager, State};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_opener::OpenerExt;
 use timelapse::Photographer;
+use tracing::{error, info, warn};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
 
 // Shared state to manage the timelapse photographer
 type PhotographerState = Arc<Mutex<Option<Photographer>>>;
@@ -16,11 +23,25 @@ fn greet(name: &str) -> String {
 }
 
 #[tauri::command]
-async fn start_timelapse(state: State<'_, PhotographerState>) -> Result<String, String> {
+async fn start_timelapse(
+    app: AppHandle,
+    state: State<'_, PhotographerState>,
+) -> Result<String, String> {
+    start_photographer(state, Some(app))
+}
+
+/// Start capturing, emitting events through `app_handle` when one is given
+fn start_photographer(
+    state: State<'_, PhotographerState>,
+    app_handle: Option<AppHandle>,
+) -> Result<String, String> {
     let mut photographer_guard = state.lock().map_err(|e| e.to_string())?;
 
     if photographer_guard.is_none() {
-        let photographer = Photographer::new().map_err(|e| e.to_string())?;
+        let mut photographer = Photographer::new().map_err(|e| e.to_string())?;
+        if let Some(app_handle) = app_handle {
+            photographer = photographer.with_app_handle(app_handle);
+        }
         photographer.start();
         *photographer_guard = Some(photographer);
         Ok("Timelapse started successfully".to_string())
@@ -30,135 +51,1888 @@ async fn start_timelapse(state: State<'_, PhotographerState>) -> Result<String,
 }
 
 #[tauri::command]
-async fn stop_timelapse(state: State<'_, PhotographerState>) -> Result<String, String> {
-    let mut photographer_guard = state.lock().map_err(|e| e.to_string())?;
+async fn stop_timelapse(state: State<'_, PhotographerState>) -> Result<String, String> {
+    // Release the state lock before waiting for the capture task to exit
+    let photographer = state.lock().map_err(|e| e.to_string())?.take();
+
+    if let Some(photographer) = photographer {
+        photographer.stop_and_join().await;
+        Ok("Timelapse stopped successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+/// Replace the running photographer with a new one, picking up settings that are
+/// only read at launch. Returns whether capture is running afterwards.
+#[tauri::command]
+async fn restart_timelapse(
+    app: AppHandle,
+    state: State<'_, PhotographerState>,
+) -> Result<bool, String> {
+    // The old photographer has to be gone before the new one opens the database:
+    // opening runs the integrity check and migrations, which mustn't race the old
+    // loop and writer
+    let previous = state.lock().map_err(|e| e.to_string())?.take();
+    if let Some(previous) = previous {
+        previous.stop_and_join().await;
+    }
+
+    let mut photographer_guard = state.lock().map_err(|e| e.to_string())?;
+    // Started again while the old loop was exiting
+    if photographer_guard.is_some() {
+        return Ok(true);
+    }
+    let photographer = Photographer::new()
+        .map_err(|e| e.to_string())?
+        .with_app_handle(app);
+    photographer.start();
+    *photographer_guard = Some(photographer);
+    Ok(true)
+}
+
+#[tauri::command]
+async fn is_timelapse_running(state: State<'_, PhotographerState>) -> Result<bool, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+    Ok(photographer_guard.is_some())
+}
+
+#[tauri::command]
+async fn pause_timelapse(state: State<'_, PhotographerState>) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.pause();
+        Ok("Timelapse paused successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn resume_timelapse(state: State<'_, PhotographerState>) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.resume();
+        Ok("Timelapse resumed successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn is_timelapse_paused(state: State<'_, PhotographerState>) -> Result<bool, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+    Ok(photographer_guard
+        .as_ref()
+        .map(|photographer| photographer.is_paused())
+        .unwrap_or(false))
+}
+
+#[tauri::command]
+async fn capture_single_frame(
+    state: State<'_, PhotographerState>,
+) -> Result<timelapse::ScreenshotOutcome, String> {
+    let capture = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => photographer.capture_single_frame(),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    capture.await.map_err(|e| e.to_string())
+}
+
+/// Render a frame with the current settings for the settings screen, without
+/// adding it to the timelapse
+#[tauri::command]
+async fn preview_capture(
+    state: State<'_, PhotographerState>,
+) -> Result<Option<timelapse::FrameImage>, String> {
+    let preview = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => photographer.preview_capture(),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    preview.await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn rebuild_database(
+    app: AppHandle,
+    state: State<'_, PhotographerState>,
+) -> Result<usize, String> {
+    let rebuild = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        let on_progress = move |progress: timelapse::RebuildProgress| {
+            if let Err(e) = app.emit(timelapse::REBUILD_PROGRESS_EVENT, progress) {
+                warn!(
+                    event = timelapse::REBUILD_PROGRESS_EVENT,
+                    error = %e,
+                    "Failed to emit event"
+                );
+            }
+        };
+        match &*photographer_guard {
+            Some(photographer) => photographer.rebuild_database(on_progress),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    tokio::task::spawn_blocking(rebuild)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn verify_integrity(
+    state: State<'_, PhotographerState>,
+    date: String,
+    fix: bool,
+) -> Result<timelapse::IntegrityReport, String> {
+    let parsed_date = parse_date(&date)?;
+    let verify = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => photographer.verify_integrity(parsed_date, fix),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    tokio::task::spawn_blocking(verify)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn optimize_database(
+    state: State<'_, PhotographerState>,
+) -> Result<timelapse::DatabaseOptimizeReport, String> {
+    let optimize = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => photographer.optimize_database(),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    tokio::task::spawn_blocking(optimize)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_error_logs(
+    state: State<'_, PhotographerState>,
+) -> Result<Vec<timelapse::ErrorLogEntry>, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        Ok(photographer.get_error_logs())
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+#[tauri::command]
+async fn get_error_logs_grouped(
+    state: State<'_, PhotographerState>,
+) -> Result<Vec<timelapse::ErrorLogGroup>, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        Ok(photographer.get_error_logs_grouped())
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+#[tauri::command]
+async fn get_capture_health(
+    state: State<'_, PhotographerState>,
+) -> Result<timelapse::CaptureHealth, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        Ok(photographer.get_capture_health())
+    } else {
+        Ok(timelapse::CaptureHealth::default())
+    }
+}
+
+#[tauri::command]
+async fn set_max_error_logs(
+    state: State<'_, PhotographerState>,
+    max_error_logs: usize,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_max_error_logs(max_error_logs).map_err(|e| e.to_string())?;
+        Ok("Error log size updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_error_logs_paginated(
+    state: State<'_, PhotographerState>,
+    offset: usize,
+    limit: usize,
+) -> Result<timelapse::ErrorLogPage, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        Ok(photographer.get_error_logs_paginated(offset, limit))
+    } else {
+        Ok(timelapse::ErrorLogPage {
+            entries: Vec::new(),
+            total: 0,
+        })
+    }
+}
+
+#[tauri::command]
+async fn clear_error_logs(state: State<'_, PhotographerState>) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.clear_error_logs();
+        Ok("Error logs cleared successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_black_threshold(
+    state: State<'_, PhotographerState>,
+    threshold: f64,
+) -> Result<f64, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .set_black_threshold(threshold)
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_black_backoff(
+    state: State<'_, PhotographerState>,
+    backoff_secs: u64,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_black_backoff(backoff_secs).map_err(|e| e.to_string())?;
+        Ok("Black frame backoff updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_min_free_space(
+    state: State<'_, PhotographerState>,
+    min_free_bytes: u64,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_min_free_space(min_free_bytes).map_err(|e| e.to_string())?;
+        Ok("Minimum free space updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_png_compression_level(
+    state: State<'_, PhotographerState>,
+    level: u8,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_png_compression_level(level).map_err(|e| e.to_string())?;
+        Ok("PNG compression level updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_filename_width(
+    state: State<'_, PhotographerState>,
+    width: usize,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_filename_width(width).map_err(|e| e.to_string())?;
+        Ok("Filename width updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_black_sample_target(
+    state: State<'_, PhotographerState>,
+    target: usize,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .set_black_sample_target(target)
+            .map_err(|e| e.to_string())?;
+        Ok("Black sample target updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_similarity_threshold(
+    state: State<'_, PhotographerState>,
+    threshold: f64,
+) -> Result<f64, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .set_similarity_threshold(threshold)
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_output_size(
+    state: State<'_, PhotographerState>,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> Result<String, String> {
+    // Both dimensions reset to the screen's native resolution when omitted
+    let size = match (width, height) {
+        (Some(width), Some(height)) => Some((width, height)),
+        (None, None) => None,
+        _ => return Err("Both width and height must be provided".to_string()),
+    };
+
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_output_size(size).map_err(|e| e.to_string())?;
+        Ok("Output size updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_blocked_apps(
+    state: State<'_, PhotographerState>,
+    apps: Vec<String>,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_blocked_apps(apps).map_err(|e| e.to_string())?;
+        Ok("Blocked apps updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_schedule(
+    state: State<'_, PhotographerState>,
+    schedule: Vec<(u8, u8)>,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_schedule(schedule).map_err(|e| e.to_string())?;
+        Ok("Schedule updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_interval_schedule(
+    state: State<'_, PhotographerState>,
+    schedule: Vec<(u8, u8, u64)>,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .set_interval_schedule(schedule)
+            .map_err(|e| e.to_string())?;
+        Ok("Interval schedule updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_capture_cursor(
+    state: State<'_, PhotographerState>,
+    enabled: bool,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_capture_cursor(enabled).map_err(|e| e.to_string())?;
+        Ok("Cursor capture updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_auto_compile(
+    state: State<'_, PhotographerState>,
+    enabled: bool,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_auto_compile(enabled).map_err(|e| e.to_string())?;
+        Ok("Auto-compile updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn clear_schedule(state: State<'_, PhotographerState>) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.clear_schedule().map_err(|e| e.to_string())?;
+        Ok("Schedule cleared successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_capture_logical_resolution(
+    state: State<'_, PhotographerState>,
+    enabled: bool,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_capture_logical_resolution(enabled).map_err(|e| e.to_string())?;
+        Ok("Capture resolution updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_privacy_masks(
+    state: State<'_, PhotographerState>,
+    masks: Vec<timelapse::PrivacyMask>,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_privacy_masks(masks).map_err(|e| e.to_string())?;
+        Ok("Privacy masks updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn clear_privacy_masks(state: State<'_, PhotographerState>) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.clear_privacy_masks().map_err(|e| e.to_string())?;
+        Ok("Privacy masks cleared successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_battery_saver(
+    state: State<'_, PhotographerState>,
+    enabled: bool,
+    interval_secs: u64,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .set_battery_saver(enabled, interval_secs)
+            .map_err(|e| e.to_string())?;
+        Ok("Battery saver updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_aspect_ratio(
+    state: State<'_, PhotographerState>,
+    ratio: Option<(u32, u32)>,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_aspect_ratio(ratio).map_err(|e| e.to_string())?;
+        Ok("Aspect ratio updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_idle_pause(
+    state: State<'_, PhotographerState>,
+    enabled: bool,
+    threshold_secs: u64,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .set_idle_pause(enabled, threshold_secs)
+            .map_err(|e| e.to_string())?;
+        Ok("Idle pause updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_timestamp_overlay(
+    state: State<'_, PhotographerState>,
+    enabled: bool,
+    format: String,
+    corner: timelapse::OverlayCorner,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .set_timestamp_overlay(enabled, format, corner)
+            .map_err(|e| e.to_string())?;
+        Ok("Timestamp overlay updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_output_format(
+    state: State<'_, PhotographerState>,
+    format: timelapse::OutputFormat,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .set_output_format(format)
+            .map_err(|e| e.to_string())?;
+        Ok("Output format updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_display_output_format(
+    state: State<'_, PhotographerState>,
+    display_id: u32,
+    format: Option<timelapse::OutputFormat>,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .set_display_output_format(display_id, format)
+            .map_err(|e| e.to_string())?;
+        Ok("Display output format updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_capture_mode(
+    state: State<'_, PhotographerState>,
+    mode: timelapse::CaptureMode,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_capture_mode(mode).map_err(|e| e.to_string())?;
+        Ok("Capture mode updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_capture_display(
+    state: State<'_, PhotographerState>,
+    display_id: Option<u32>,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_capture_display(display_id).map_err(|e| e.to_string())?;
+        Ok("Capture display updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn list_displays() -> Result<Vec<timelapse::DisplaySummary>, String> {
+    timelapse::list_displays().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_capture_crop(
+    state: State<'_, PhotographerState>,
+    top: u32,
+    bottom: u32,
+    left: u32,
+    right: u32,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .set_capture_crop(timelapse::EdgeCrop { top, bottom, left, right })
+            .map_err(|e| e.to_string())?;
+        Ok("Capture crop updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_dir_layout(
+    state: State<'_, PhotographerState>,
+    layout: timelapse::DirLayout,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_dir_layout(layout).map_err(|e| e.to_string())?;
+        Ok("Directory layout updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_config(
+    state: State<'_, PhotographerState>,
+) -> Result<timelapse::TimelapseConfig, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    match &*photographer_guard {
+        Some(photographer) => Ok(photographer.get_config()),
+        // Read the saved config so settings can be shown before capture starts
+        None => {
+            let root = timelapse::default_root_path().map_err(|e| e.to_string())?;
+            Ok(timelapse::load_config(&root))
+        }
+    }
+}
+
+#[tauri::command]
+async fn set_config(
+    state: State<'_, PhotographerState>,
+    config: timelapse::TimelapseConfig,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    match &*photographer_guard {
+        Some(photographer) => photographer.set_config(config),
+        // Save it for the next start
+        None => timelapse::default_root_path()
+            .and_then(|root| timelapse::replace_saved_config(&root, &config)),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok("Configuration updated successfully".to_string())
+}
+
+#[tauri::command]
+async fn set_resize_filter(
+    state: State<'_, PhotographerState>,
+    filter: String,
+) -> Result<String, String> {
+    let filter = filter
+        .parse::<timelapse::ResizeFilter>()
+        .map_err(|e| e.to_string())?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_resize_filter(filter).map_err(|e| e.to_string())?;
+        Ok("Resize filter updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+/// Returned when ffmpeg can't be run, so the frontend can prompt the user to install it
+#[derive(Debug, Clone, Serialize, thiserror::Error)]
+#[error("{reason}. {install_hint}")]
+struct FfmpegUnavailable {
+    reason: String,
+    install_hint: String,
+}
+
+impl FfmpegUnavailable {
+    fn new(reason: String) -> Self {
+        FfmpegUnavailable {
+            reason,
+            install_hint: "Install ffmpeg (e.g. `brew install ffmpeg`) and make sure it is in PATH"
+                .to_string(),
+        }
+    }
+}
+
+/// Run `ffmpeg -version` and return the reported version
+fn ffmpeg_version() -> Result<String, FfmpegUnavailable> {
+    let output = Command::new("ffmpeg")
+        .arg("-version")
+        .output()
+        .map_err(|e| FfmpegUnavailable::new(format!("ffmpeg could not be executed: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(FfmpegUnavailable::new(format!(
+            "ffmpeg -version exited with {}",
+            output.status
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_ffmpeg_version(&stdout)
+        .ok_or_else(|| FfmpegUnavailable::new("ffmpeg reported an unrecognized version".to_string()))
+}
+
+/// Extract the version from the first line of `ffmpeg -version`,
+/// e.g. "ffmpeg version 6.1.1 Copyright (c) 2000-2023" -> "6.1.1"
+fn parse_ffmpeg_version(output: &str) -> Option<String> {
+    output
+        .lines()
+        .next()?
+        .strip_prefix("ffmpeg version ")?
+        .split_whitespace()
+        .next()
+        .map(|version| version.to_string())
+}
+
+#[tauri::command]
+async fn check_ffmpeg_available() -> Result<String, FfmpegUnavailable> {
+    ffmpeg_version()
+}
+
+// Name pattern of frames extracted from a video into the cache
+const EXTRACTED_FRAME_PATTERN: &str = "frame%06d.jpg";
+
+// Frame rates accepted by extract_video_frames
+const MIN_EXTRACTION_FPS: u32 = 1;
+const MAX_EXTRACTION_FPS: u32 = 120;
+
+// JPEG quality of extracted frames on ffmpeg's -q:v scale
+const MIN_EXTRACTION_QUALITY: u8 = 1;
+const MAX_EXTRACTION_QUALITY: u8 = 31;
+const DEFAULT_EXTRACTION_QUALITY: u8 = 2;
+
+/// Frames extracted from a video, as returned to the frontend
+#[derive(Debug, Clone, Serialize)]
+struct VideoFrames {
+    /// Folder under `~/Timelapse/.cache` holding the frames
+    cache_folder: String,
+    frame_count: usize,
+    /// printf-style pattern the frames are named with, numbered from 1
+    frame_pattern: String,
+}
+
+/// Extract a compiled video's frames into the cache as JPEGs.
+///
+/// `quality` is passed to ffmpeg as `-q:v` and runs the opposite way to what
+/// the name suggests: 1 is the best quality and largest files, 31 the worst and
+/// smallest. It defaults to 2 when omitted.
+#[tauri::command]
+async fn extract_video_frames(
+    video_filename: String,
+    fps: u32,
+    quality: Option<u8>,
+) -> Result<VideoFrames, String> {
+    let video_stem = check_video_filename(&video_filename)?;
+
+    if !(MIN_EXTRACTION_FPS..=MAX_EXTRACTION_FPS).contains(&fps) {
+        return Err(format!(
+            "Invalid fps {}, expected {} to {}",
+            fps, MIN_EXTRACTION_FPS, MAX_EXTRACTION_FPS
+        ));
+    }
+
+    let quality = quality.unwrap_or(DEFAULT_EXTRACTION_QUALITY);
+    if !(MIN_EXTRACTION_QUALITY..=MAX_EXTRACTION_QUALITY).contains(&quality) {
+        return Err(format!(
+            "Invalid quality {}, expected {} to {}",
+            quality, MIN_EXTRACTION_QUALITY, MAX_EXTRACTION_QUALITY
+        ));
+    }
+
+    ffmpeg_version().map_err(|e| e.to_string())?;
+
+    let home_dir = dirs::home_dir().ok_or("Unable to find home directory")?;
+    let source_path = home_dir.join("Timelapse").join(&video_filename);
+
+    // Create cache directory if it doesn't exist
+    let cache_dir = home_dir.join("Timelapse").join(".cache");
+    std::fs::create_dir_all(&cache_dir).map_err(|e| format!("Failed to create cache directory: {}", e))?;
+
+    // Generate cache folder name (remove .mov extension), keyed on fps and quality
+    // so extractions with different settings don't collide
+    let cache_folder_name = format!(
+        "{}_fps{}_q{}",
+        video_stem,
+        fps,
+        quality
+    );
+    let cache_folder_path = cache_dir.join(&cache_folder_name);
+
+    let video_frames = |frame_count| VideoFrames {
+        cache_folder: cache_folder_name.clone(),
+        frame_count,
+        frame_pattern: EXTRACTED_FRAME_PATTERN.to_string(),
+    };
+
+    // Check if frame sequence already exists
+    if cache_folder_path.exists() && cache_folder_path.is_dir() {
+        let frame_count = count_extracted_frames(&cache_folder_path)?;
+        if frame_count > 0 {
+            info!(cache = ?cache_folder_path, "Using cached frame sequence");
+            return Ok(video_frames(frame_count));
+        }
+    }
+
+    // Create the cache folder for this video
+    std::fs::create_dir_all(&cache_folder_path)
+        .map_err(|e| format!("Failed to create cache folder: {}", e))?;
+
+    info!(source = ?source_path, cache = ?cache_folder_path, "Extracting frames from video");
+
+    // Run ffmpeg to extract frames as JPEG images
+    // frame%06d.jpg creates frame000001.jpg, frame000002.jpg, etc.
+    let output_pattern = cache_folder_path.join(EXTRACTED_FRAME_PATTERN);
+    let output = Command::new("ffmpeg")
+        .arg("-i")
+        .arg(&source_path)
+        .arg("-vf")
+        .arg(format!("fps={}", fps))
+        .arg("-q:v")
+        .arg(quality.to_string())
+        .arg("-y")
+        .arg(&output_pattern)
+        .output()
+        .map_err(|e| format!("Failed to execute ffmpeg: {}. Make sure ffmpeg is installed and in PATH.", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("ffmpeg failed: {}", stderr));
+    }
+
+    let frame_count = count_extracted_frames(&cache_folder_path)?;
+    info!(frame_count, cache = ?cache_folder_path, "Frame extraction complete");
+
+    Ok(video_frames(frame_count))
+}
+
+/// Count the `frameNNNNNN.jpg` files ffmpeg wrote into `cache_folder`
+fn count_extracted_frames(cache_folder: &Path) -> Result<usize, String> {
+    let entries = std::fs::read_dir(cache_folder)
+        .map_err(|e| format!("Failed to read cache directory: {}", e))?;
+
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_prefix("frame")
+                .and_then(|rest| rest.strip_suffix(".jpg"))
+                .is_some_and(|number| {
+                    !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+                })
+        })
+        .count())
+}
+
+#[tauri::command]
+async fn compile_day_video(
+    date: String,
+    fps: u32,
+    codec: Option<String>,
+    crf: Option<u8>,
+) -> Result<timelapse::CompiledVideo, String> {
+    let parsed_date = parse_date(&date)?;
+    let encoding = timelapse::VideoEncoding::new(codec, crf).map_err(|e| e.to_string())?;
+
+    let home_dir = dirs::home_dir().ok_or("Unable to find home directory")?;
+    let timelapse_dir = home_dir.join("Timelapse");
+    if timelapse::existing_day_dirs(&timelapse_dir, parsed_date).is_empty() {
+        return Err(format!("No captures found for {}", date));
+    }
+
+    ffmpeg_version().map_err(|e| e.to_string())?;
+
+    let fps = if fps == 0 { timelapse::DEFAULT_VIDEO_FPS } else { fps };
+    timelapse::compile_day_video(&timelapse_dir, parsed_date, fps, &encoding)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn estimate_video(date: String, fps: u32) -> Result<timelapse::VideoEstimate, String> {
+    let parsed_date = parse_date(&date)?;
+
+    let home_dir = dirs::home_dir().ok_or("Unable to find home directory")?;
+    let fps = if fps == 0 { timelapse::DEFAULT_VIDEO_FPS } else { fps };
+    timelapse::estimate_video(&home_dir.join("Timelapse"), parsed_date, fps)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn generate_day_gif(date: String, max_width: u32, frame_skip: u32) -> Result<String, String> {
+    let parsed_date = parse_date(&date)?;
+
+    let home_dir = dirs::home_dir().ok_or("Unable to find home directory")?;
+    let timelapse_dir = home_dir.join("Timelapse");
+    if timelapse::existing_day_dirs(&timelapse_dir, parsed_date).is_empty() {
+        return Err(format!("No captures found for {}", date));
+    }
+
+    ffmpeg_version().map_err(|e| e.to_string())?;
+
+    let output_path =
+        timelapse::generate_day_gif(&timelapse_dir, parsed_date, max_width, frame_skip)
+            .map_err(|e| e.to_string())?;
+
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+/// Open the Timelapse folder, or one day's frames when `date` is given, in the
+/// system file manager
+#[tauri::command]
+async fn reveal_timelapse_folder(app: AppHandle, date: Option<String>) -> Result<(), String> {
+    let home_dir = dirs::home_dir().ok_or("Unable to find home directory")?;
+    let folder = resolve_reveal_folder(&home_dir.join("Timelapse"), date.as_deref())?;
+
+    app.opener()
+        .open_path(folder.to_string_lossy().to_string(), None::<&str>)
+        .map_err(|e| format!("Failed to open {:?}: {}", folder, e))
+}
+
+/// The existing folder `reveal_timelapse_folder` should open
+fn resolve_reveal_folder(timelapse_dir: &Path, date: Option<&str>) -> Result<PathBuf, String> {
+    let Some(date) = date else {
+        if !timelapse_dir.is_dir() {
+            return Err(format!("Timelapse folder {:?} does not exist", timelapse_dir));
+        }
+        return Ok(timelapse_dir.to_path_buf());
+    };
+
+    let parsed_date = parse_date(date)?;
+    timelapse::existing_day_dirs(timelapse_dir, parsed_date)
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("No captures found for {}", date))
+}
+
+/// Ensure a date coming from the frontend is a plain YYYY-MM-DD string before
+/// it gets joined onto a filesystem path
+fn parse_date(date: &str) -> Result<chrono::NaiveDate, String> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .filter(|_| date.len() == 10)
+        .ok_or_else(|| format!("Invalid date '{}', expected YYYY-MM-DD", date))
+}
+
+/// Check `video_filename` names a compiled day video, `YYYY-MM-DD.mov`, directly in
+/// the Timelapse folder, returning its date part
+fn check_video_filename(video_filename: &str) -> Result<&str, String> {
+    if video_filename.contains(['/', '\\']) || video_filename.contains("..") {
+        return Err(format!(
+            "Invalid video filename '{}', it must not contain path separators or '..'",
+            video_filename
+        ));
+    }
+
+    video_filename
+        .strip_suffix(".mov")
+        .filter(|stem| parse_date(stem).is_ok())
+        .ok_or_else(|| {
+            format!("Invalid video filename '{}', expected YYYY-MM-DD.mov", video_filename)
+        })
+}
+
+fn parse_time(time: &str) -> Result<chrono::NaiveTime, String> {
+    chrono::NaiveTime::parse_from_str(time, "%H:%M:%S")
+        .or_else(|_| chrono::NaiveTime::parse_from_str(time, "%H:%M"))
+        .map_err(|_| format!("Invalid time '{}', expected HH:MM or HH:MM:SS", time))
+}
+
+fn parse_rfc3339(timestamp: &str) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|timestamp| timestamp.with_timezone(&chrono::Utc))
+        .map_err(|e| format!("Invalid timestamp '{}': {}", timestamp, e))
+}
+
+#[tauri::command]
+async fn get_screenshot_metadata(
+    state: State<'_, PhotographerState>,
+    frame_number: u32,
+) -> Result<Option<(String, String)>, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .get_screenshot_metadata(frame_number)
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_screenshot_context(
+    state: State<'_, PhotographerState>,
+    frame_number: u32,
+) -> Result<Option<database::ScreenshotContext>, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .get_screenshot_context(frame_number)
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_frames_between(
+    state: State<'_, PhotographerState>,
+    start: String,
+    end: String,
+) -> Result<Vec<(u32, String, String)>, String> {
+    let start = parse_rfc3339(&start)?;
+    let end = parse_rfc3339(&end)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .get_frames_between(start, end)
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_day_frames(
+    state: State<'_, PhotographerState>,
+    date: String,
+) -> Result<Vec<database::FrameMeta>, String> {
+    let date = parse_date(&date)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.get_day_frames(date).map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_frames_by_local_hour(
+    state: State<'_, PhotographerState>,
+    start_hour: u8,
+    end_hour: u8,
+) -> Result<Vec<(u32, String, String)>, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .get_frames_by_local_hour(start_hour, end_hour)
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn list_capture_days(
+    state: State<'_, PhotographerState>,
+) -> Result<Vec<timelapse::DaySummary>, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.list_capture_days().map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn label_day(
+    state: State<'_, PhotographerState>,
+    date: String,
+    label: String,
+) -> Result<String, String> {
+    let date = parse_date(&date)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.label_day(date, &label).map_err(|e| e.to_string())?;
+        Ok("Day label updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_day_labels(
+    state: State<'_, PhotographerState>,
+) -> Result<Vec<timelapse::DayLabel>, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.get_day_labels().map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_retention_days(
+    state: State<'_, PhotographerState>,
+    days: u32,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_retention_days(days).map_err(|e| e.to_string())?;
+        Ok("Retention updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn evict_old_screenshots(
+    state: State<'_, PhotographerState>,
+) -> Result<timelapse::ScreenshotEvictionReport, String> {
+    let evict = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => photographer.evict_old_screenshots(),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    tokio::task::spawn_blocking(evict)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_frame_image(
+    state: State<'_, PhotographerState>,
+    date: String,
+    frame_number: u32,
+) -> Result<timelapse::FrameImage, String> {
+    let date = parse_date(&date)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .get_frame_image(date, frame_number)
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_thumbnail(
+    state: State<'_, PhotographerState>,
+    date: String,
+    frame_number: u32,
+) -> Result<timelapse::FrameImage, String> {
+    let date = parse_date(&date)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .get_thumbnail(date, frame_number)
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_database_enabled(
+    state: State<'_, PhotographerState>,
+    enabled: bool,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_database_enabled(enabled).map_err(|e| e.to_string())?;
+        Ok("Database setting updated, restart the app to apply it".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_generate_thumbnails(
+    state: State<'_, PhotographerState>,
+    enabled: bool,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_generate_thumbnails(enabled).map_err(|e| e.to_string())?;
+        Ok("Thumbnail generation updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_keep_originals(
+    state: State<'_, PhotographerState>,
+    enabled: bool,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_keep_originals(enabled).map_err(|e| e.to_string())?;
+        Ok("Original retention updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+/// Render a frame again from its kept original, returning whether there was one
+#[tauri::command]
+async fn reprocess_frame(
+    state: State<'_, PhotographerState>,
+    date: String,
+    frame_number: u32,
+) -> Result<bool, String> {
+    let date = parse_date(&date)?;
+    let reprocess = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => photographer.reprocess_frame(date, frame_number),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    tokio::task::spawn_blocking(reprocess)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn backfill_thumbnails(state: State<'_, PhotographerState>) -> Result<usize, String> {
+    let backfill = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => photographer.backfill_thumbnails(),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    tokio::task::spawn_blocking(backfill)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn analyze_frame(
+    state: State<'_, PhotographerState>,
+    date: String,
+    frame_number: u32,
+) -> Result<timelapse::FrameAnalysis, String> {
+    let date = parse_date(&date)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .analyze_frame(date, frame_number)
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_total_storage(
+    state: State<'_, PhotographerState>,
+) -> Result<timelapse::StorageBreakdown, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.get_total_storage().map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_day_stats(
+    state: State<'_, PhotographerState>,
+    date: String,
+) -> Result<timelapse::DayStats, String> {
+    let date = parse_date(&date)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.get_day_stats(date).map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_capture_bounds(
+    state: State<'_, PhotographerState>,
+) -> Result<timelapse::CaptureBounds, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.get_capture_bounds().map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_frames_by_app(
+    state: State<'_, PhotographerState>,
+    app_name: String,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<database::FrameMeta>, String> {
+    let start_date = parse_date(&start_date)?;
+    let end_date = parse_date(&end_date)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .get_frames_by_app(&app_name, start_date, end_date)
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_record_change_scores(
+    state: State<'_, PhotographerState>,
+    enabled: bool,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_record_change_scores(enabled).map_err(|e| e.to_string())?;
+        Ok("Change scoring updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_change_scores(
+    state: State<'_, PhotographerState>,
+    date: String,
+) -> Result<Vec<database::ChangeScore>, String> {
+    let date = parse_date(&date)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.get_change_scores(date).map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn set_profiling_enabled(
+    state: State<'_, PhotographerState>,
+    enabled: bool,
+) -> Result<String, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.set_profiling_enabled(enabled).map_err(|e| e.to_string())?;
+        Ok("Profiling updated successfully".to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_timing_summary(
+    state: State<'_, PhotographerState>,
+    date: String,
+) -> Result<Vec<timelapse::StageTimingSummary>, String> {
+    let date = parse_date(&date)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.get_timing_summary(date).map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_keyframes(
+    state: State<'_, PhotographerState>,
+    date: String,
+    min_change: f64,
+) -> Result<Vec<u32>, String> {
+    let date = parse_date(&date)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.get_keyframes(date, min_change).map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn compile_keyframes_video(
+    state: State<'_, PhotographerState>,
+    date: String,
+    min_change: f64,
+    fps: u32,
+    codec: Option<String>,
+    crf: Option<u8>,
+) -> Result<timelapse::CompiledVideo, String> {
+    let date = parse_date(&date)?;
+    let encoding = timelapse::VideoEncoding::new(codec, crf).map_err(|e| e.to_string())?;
+    let fps = if fps == 0 { timelapse::DEFAULT_VIDEO_FPS } else { fps };
+    ffmpeg_version().map_err(|e| e.to_string())?;
+
+    let compile = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => {
+                photographer.compile_keyframes_video(date, min_change, fps, encoding)
+            }
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    tokio::task::spawn_blocking(compile)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_activity_heatmap(
+    state: State<'_, PhotographerState>,
+    start_date: String,
+    end_date: String,
+) -> Result<timelapse::ActivityHeatmap, String> {
+    let start_date = parse_date(&start_date)?;
+    let end_date = parse_date(&end_date)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .get_activity_heatmap(start_date, end_date)
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn frame_at_time(
+    state: State<'_, PhotographerState>,
+    date: String,
+    target_time: String,
+) -> Result<Option<(u32, String)>, String> {
+    let date = parse_date(&date)?;
+    let target_time = parse_time(&target_time)?;
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(photographer) = &*photographer_guard {
+        photographer.frame_at_time(date, target_time).map_err(|e| e.to_string())
+    } else {
+        Err("Timelapse is not running".to_string())
+    }
+}
+
+#[tauri::command]
+async fn delete_day(
+    state: State<'_, PhotographerState>,
+    date: String,
+) -> Result<timelapse::DeletionSummary, String> {
+    let date = parse_date(&date)?;
+    let delete = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => photographer.delete_day(date),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    tokio::task::spawn_blocking(delete)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn purge_frames_between(
+    state: State<'_, PhotographerState>,
+    date: String,
+    start_hour: u8,
+    end_hour: u8,
+) -> Result<timelapse::DeletionSummary, String> {
+    let date = parse_date(&date)?;
+    let purge = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => photographer.purge_frames_between(date, start_hour, end_hour),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    tokio::task::spawn_blocking(purge)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_frame(
+    state: State<'_, PhotographerState>,
+    date: String,
+    frame_number: u32,
+) -> Result<bool, String> {
+    let date = parse_date(&date)?;
+    let delete = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => photographer.delete_frame(date, frame_number),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    tokio::task::spawn_blocking(delete)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_frames(
+    state: State<'_, PhotographerState>,
+    date: String,
+    frame_numbers: Vec<u32>,
+) -> Result<usize, String> {
+    let date = parse_date(&date)?;
+    let delete = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => photographer.delete_frames(date, frame_numbers),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    tokio::task::spawn_blocking(delete)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+// Log files are named `timelapse.<date>.log` in the logs folder, one per day,
+// keeping this many days
+const LOG_FILE_PREFIX: &str = "timelapse";
+const MAX_LOG_FILES: usize = 14;
+
+// Extracted frame folders older than this are removed on startup
+const CACHE_MAX_AGE: Duration = Duration::from_secs(15 * 24 * 60 * 60);
+
+// Size limit applied to the extracted frame cache on startup
+const DEFAULT_MAX_CACHE_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+struct CacheEvictionReport {
+    removed_by_age: usize,
+    removed_by_size: usize,
+    remaining_bytes: u64,
+}
+
+/// Why a cache folder is due for eviction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+enum EvictionReason {
+    /// Older than `CACHE_MAX_AGE`
+    Age,
+    /// Among the oldest folders while the cache is over its size limit
+    Size,
+}
+
+/// A cache folder that eviction would remove
+#[derive(Debug, Clone, Serialize)]
+struct CacheEvictionCandidate {
+    /// Folder name under `~/Timelapse/.cache`
+    folder: String,
+    #[serde(skip)]
+    path: PathBuf,
+    age_days: u64,
+    size_bytes: u64,
+    reason: EvictionReason,
+}
+
+/// What cache eviction would remove, and how much would be left
+#[derive(Debug, Clone, Serialize)]
+struct CacheEvictionPlan {
+    folders: Vec<CacheEvictionCandidate>,
+    remaining_bytes: u64,
+}
+
+#[tauri::command]
+async fn export_database_csv(
+    state: State<'_, PhotographerState>,
+    output_path: String,
+) -> Result<usize, String> {
+    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
 
-    if let Some(photographer) = photographer_guard.take() {
-        photographer.stop();
-        Ok("Timelapse stopped successfully".to_string())
+    if let Some(photographer) = &*photographer_guard {
+        photographer
+            .export_database_csv(Path::new(&output_path))
+            .map_err(|e| e.to_string())
     } else {
         Err("Timelapse is not running".to_string())
     }
 }
 
 #[tauri::command]
-async fn is_timelapse_running(state: State<'_, PhotographerState>) -> Result<bool, String> {
-    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
-    Ok(photographer_guard.is_some())
+async fn export_day_zip(
+    state: State<'_, PhotographerState>,
+    date: String,
+    output_path: String,
+) -> Result<timelapse::ZipExportSummary, String> {
+    let date = parse_date(&date)?;
+    let export = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => photographer.export_day_zip(date, PathBuf::from(output_path)),
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
+
+    tokio::task::spawn_blocking(export)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn get_error_logs(
+async fn export_renumbered_sequence(
     state: State<'_, PhotographerState>,
-) -> Result<Vec<timelapse::ErrorLogEntry>, String> {
-    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+    date: String,
+    output_dir: String,
+) -> Result<timelapse::SequenceExport, String> {
+    let date = parse_date(&date)?;
+    let export = {
+        let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+
+        match &*photographer_guard {
+            Some(photographer) => {
+                photographer.export_renumbered_sequence(date, PathBuf::from(output_dir))
+            }
+            None => return Err("Timelapse is not running".to_string()),
+        }
+    };
 
-    if let Some(photographer) = &*photographer_guard {
-        Ok(photographer.get_error_logs())
-    } else {
-        Ok(Vec::new())
-    }
+    tokio::task::spawn_blocking(export)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn clear_error_logs(state: State<'_, PhotographerState>) -> Result<String, String> {
-    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+async fn evict_old_cache(max_cache_bytes: Option<u64>) -> Result<CacheEvictionReport, String> {
+    let home_dir = dirs::home_dir().ok_or("Unable to find home directory")?;
+    let cache_dir = home_dir.join("Timelapse").join(".cache");
 
-    if let Some(photographer) = &*photographer_guard {
-        photographer.clear_error_logs();
-        Ok("Error logs cleared successfully".to_string())
-    } else {
-        Err("Timelapse is not running".to_string())
-    }
+    evict_cache_folders(&cache_dir, CACHE_MAX_AGE, max_cache_bytes)
 }
 
+/// List the cache folders `evict_old_cache` would remove, without removing them
 #[tauri::command]
-async fn extract_video_frames(video_filename: String) -> Result<String, String> {
+async fn preview_cache_eviction(
+    max_cache_bytes: Option<u64>,
+) -> Result<CacheEvictionPlan, String> {
     let home_dir = dirs::home_dir().ok_or("Unable to find home directory")?;
-    let source_path = home_dir.join("Timelapse").join(&video_filename);
-
-    // Create cache directory if it doesn't exist
     let cache_dir = home_dir.join("Timelapse").join(".cache");
-    std::fs::create_dir_all(&cache_dir).map_err(|e| format!("Failed to create cache directory: {}", e))?;
 
-    // Generate cache folder name (remove .mov extension)
-    let cache_folder_name = video_filename.trim_end_matches(".mov");
-    let cache_folder_path = cache_dir.join(cache_folder_name);
+    plan_cache_eviction(&cache_dir, CACHE_MAX_AGE, max_cache_bytes)
+}
 
-    // Check if frame sequence already exists
-    if cache_folder_path.exists() && cache_folder_path.is_dir() {
-        let entries = std::fs::read_dir(&cache_folder_path)
-            .map_err(|e| format!("Failed to read cache directory: {}", e))?;
-        let has_frames = entries.count() > 0;
-        if has_frames {
-            println!("Using cached frame sequence: {:?}", cache_folder_path);
-            return Ok(cache_folder_name.to_string());
+/// Remove the folders picked by `plan_cache_eviction`
+fn evict_cache_folders(
+    cache_dir: &Path,
+    max_age: Duration,
+    max_cache_bytes: Option<u64>,
+) -> Result<CacheEvictionReport, String> {
+    let plan = plan_cache_eviction(cache_dir, max_age, max_cache_bytes)?;
+    let mut report = CacheEvictionReport {
+        removed_by_age: 0,
+        removed_by_size: 0,
+        remaining_bytes: plan.remaining_bytes,
+    };
+
+    for candidate in plan.folders {
+        let path = &candidate.path;
+        match candidate.reason {
+            EvictionReason::Age => {
+                info!(?path, age_days = candidate.age_days, "Removing old cache folder");
+                report.removed_by_age += 1;
+            }
+            EvictionReason::Size => {
+                info!(
+                    ?path,
+                    size_bytes = candidate.size_bytes,
+                    "Removing cache folder to free space"
+                );
+                report.removed_by_size += 1;
+            }
         }
+        std::fs::remove_dir_all(path)
+            .map_err(|e| format!("Failed to remove directory {:?}: {}", path, e))?;
     }
 
-    // Create the cache folder for this video
-    std::fs::create_dir_all(&cache_folder_path)
-        .map_err(|e| format!("Failed to create cache folder: {}", e))?;
-
-    println!("Extracting frames from video: {:?} -> {:?}", source_path, cache_folder_path);
+    Ok(report)
+}
 
-    // Run ffmpeg to extract frames as JPEG images
-    // frame%06d.jpg creates frame000001.jpg, frame000002.jpg, etc.
-    let output_pattern = cache_folder_path.join("frame%06d.jpg");
-    let output = Command::new("ffmpeg")
-        .arg("-i")
-        .arg(&source_path)
-        .arg("-vf")
-        .arg("fps=30") // Extract at 30 fps (adjust as needed)
-        .arg("-q:v")
-        .arg("2") // High quality JPEG (1-31, lower is better)
-        .arg("-y")
-        .arg(&output_pattern)
-        .output()
-        .map_err(|e| format!("Failed to execute ffmpeg: {}. Make sure ffmpeg is installed and in PATH.", e))?;
+/// Pick cache folders older than `max_age`, then, if the remaining folders
+/// still exceed `max_cache_bytes`, the oldest ones until they fit
+fn plan_cache_eviction(
+    cache_dir: &Path,
+    max_age: Duration,
+    max_cache_bytes: Option<u64>,
+) -> Result<CacheEvictionPlan, String> {
+    let mut plan = CacheEvictionPlan {
+        folders: Vec::new(),
+        remaining_bytes: 0,
+    };
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("ffmpeg failed: {}", stderr));
+    if !cache_dir.exists() {
+        return Ok(plan);
     }
 
-    println!("Frame extraction complete: {:?}", cache_folder_path);
-
-    Ok(cache_folder_name.to_string())
-}
+    let now = SystemTime::now();
+    let mut remaining = Vec::new();
+
+    for (path, modified, size) in scan_cache_folders(cache_dir)? {
+        let age = now.duration_since(modified).unwrap_or_default();
+        let candidate = |reason| CacheEvictionCandidate {
+            folder: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.clone(),
+            age_days: age.as_secs() / 86400,
+            size_bytes: size,
+            reason,
+        };
+
+        // Check if older than the maximum age
+        if age > max_age {
+            plan.folders.push(candidate(EvictionReason::Age));
+        } else {
+            remaining.push((modified, candidate(EvictionReason::Size)));
+        }
+    }
 
-#[tauri::command]
-async fn get_screenshot_metadata(
-    state: State<'_, PhotographerState>,
-    frame_number: u32,
-) -> Result<Option<(String, String)>, String> {
-    let photographer_guard = state.lock().map_err(|e| e.to_string())?;
+    plan.remaining_bytes = remaining.iter().map(|(_, candidate)| candidate.size_bytes).sum();
 
-    if let Some(photographer) = &*photographer_guard {
-        photographer
-            .get_screenshot_metadata(frame_number)
-            .map_err(|e| e.to_string())
-    } else {
-        Err("Timelapse is not running".to_string())
-    }
-}
+    if let Some(max_cache_bytes) = max_cache_bytes {
+        // Oldest first
+        remaining.sort_by_key(|(modified, _)| *modified);
 
-#[tauri::command]
-async fn evict_old_cache() -> Result<String, String> {
-    let home_dir = dirs::home_dir().ok_or("Unable to find home directory")?;
-    let cache_dir = home_dir.join("Timelapse").join(".cache");
+        for (_, candidate) in remaining {
+            if plan.remaining_bytes <= max_cache_bytes {
+                break;
+            }
 
-    if !cache_dir.exists() {
-        return Ok("Cache directory does not exist".to_string());
+            plan.remaining_bytes -= candidate.size_bytes;
+            plan.folders.push(candidate);
+        }
     }
 
-    let now = std::time::SystemTime::now();
-    let fifteen_days = std::time::Duration::from_secs(15 * 24 * 60 * 60);
+    Ok(plan)
+}
 
-    let entries = std::fs::read_dir(&cache_dir)
+/// List the folders in the cache directory with their modification time and total size
+fn scan_cache_folders(cache_dir: &Path) -> Result<Vec<(PathBuf, SystemTime, u64)>, String> {
+    let entries = std::fs::read_dir(cache_dir)
         .map_err(|e| format!("Failed to read cache directory: {}", e))?;
 
-    let mut removed_count = 0;
+    let mut folders = Vec::new();
 
     for entry in entries {
         let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
@@ -168,6 +1942,12 @@ async fn evict_old_cache() -> Result<String, String> {
             continue;
         }
 
+        // Kept originals are evicted a day at a time, like the other cache folders
+        if entry.file_name() == timelapse::ORIGINALS_CACHE_DIR {
+            folders.extend(scan_cache_folders(&path)?);
+            continue;
+        }
+
         // Get the modification time of the directory
         let metadata = std::fs::metadata(&path)
             .map_err(|e| format!("Failed to get metadata for {:?}: {}", path, e))?;
@@ -175,22 +1955,85 @@ async fn evict_old_cache() -> Result<String, String> {
         let modified = metadata.modified()
             .map_err(|e| format!("Failed to get modified time for {:?}: {}", path, e))?;
 
-        // Check if older than 15 days
-        if let Ok(age) = now.duration_since(modified) {
-            if age > fifteen_days {
-                println!("Removing old cache folder: {:?} (age: {} days)", path, age.as_secs() / 86400);
-                std::fs::remove_dir_all(&path)
-                    .map_err(|e| format!("Failed to remove directory {:?}: {}", path, e))?;
-                removed_count += 1;
-            }
-        }
+        let size = timelapse::dir_size(&path)
+            .map_err(|e| format!("Failed to get size of {:?}: {}", path, e))?;
+
+        folders.push((path, modified, size));
     }
 
-    Ok(format!("Removed {} old cache folders", removed_count))
+    Ok(folders)
+}
+
+/// Folder the daily log files are written to
+fn log_dir() -> Result<PathBuf, String> {
+    let home_dir = dirs::home_dir().ok_or("Unable to find home directory")?;
+    Ok(home_dir.join("Timelapse").join(timelapse::LOG_DIR_NAME))
+}
+
+/// The log file currently being written to, for attaching to bug reports
+#[tauri::command]
+async fn get_log_path() -> Result<String, String> {
+    Ok(current_log_file(&log_dir()?)?.to_string_lossy().to_string())
+}
+
+/// The newest log file in `log_dir`. Their names end in the date they cover, so
+/// the newest one sorts last.
+fn current_log_file(log_dir: &Path) -> Result<PathBuf, String> {
+    let prefix = format!("{}.", LOG_FILE_PREFIX);
+    std::fs::read_dir(log_dir)
+        .map_err(|e| format!("Failed to read log folder {:?}: {}", log_dir, e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".log"))
+        })
+        .max()
+        .ok_or_else(|| format!("No log file in {:?} yet", log_dir))
+}
+
+/// Write log events to a file in `log_dir` that rotates daily, and to the console
+/// as well in debug builds. Events are written from a background thread until the
+/// returned guard is dropped.
+fn init_logging() -> Result<WorkerGuard, String> {
+    let log_dir = log_dir()?;
+    std::fs::create_dir_all(&log_dir)
+        .map_err(|e| format!("Failed to create log folder {:?}: {}", log_dir, e))?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(&log_dir)
+        .map_err(|e| format!("Failed to open log file: {}", e))?;
+    let (file_writer, guard) = tracing_appender::non_blocking(appender);
+
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_max_level(tracing::Level::INFO);
+    #[cfg(debug_assertions)]
+    let result = {
+        use tracing_subscriber::fmt::writer::MakeWriterExt;
+        subscriber.with_writer(file_writer.and(std::io::stdout)).try_init()
+    };
+    #[cfg(not(debug_assertions))]
+    let result = subscriber.with_writer(file_writer).try_init();
+    result.map_err(|e| format!("Failed to set up logging: {}", e))?;
+
+    Ok(guard)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Held until the app exits so buffered log lines still reach the file
+    let _log_guard = match init_logging() {
+        Ok(guard) => Some(guard),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    };
     let photographer_state: PhotographerState = Arc::new(Mutex::new(None));
 
     tauri::Builder::default()
@@ -201,25 +2044,40 @@ pub fn run() {
             // Start timelapse automatically when app is ready
             let photographer_state = app.state::<PhotographerState>();
             let state_clone = Arc::clone(&photographer_state.inner());
+            let app_handle = app.handle().clone();
 
             tauri::async_runtime::spawn(async move {
                 tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
 
                 // Evict old cache entries on startup
-                match evict_old_cache().await {
-                    Ok(msg) => println!("Cache eviction: {}", msg),
-                    Err(e) => eprintln!("Failed to evict old cache: {}", e),
+                match evict_old_cache(Some(DEFAULT_MAX_CACHE_BYTES)).await {
+                    Ok(report) => info!(
+                        removed_by_age = report.removed_by_age,
+                        removed_by_size = report.removed_by_size,
+                        "Cache eviction finished"
+                    ),
+                    Err(e) => error!(error = %e, "Failed to evict old cache"),
                 }
 
                 match Photographer::new() {
                     Ok(photographer) => {
+                        let photographer = photographer.with_app_handle(app_handle);
+                        let evict = photographer.evict_old_screenshots();
+                        match tokio::task::spawn_blocking(evict).await {
+                            Ok(Ok(report)) => info!(
+                                days_removed = report.days_removed,
+                                "Screenshot eviction finished"
+                            ),
+                            Ok(Err(e)) => error!(error = %e, "Failed to evict old screenshots"),
+                            Err(e) => error!(error = %e, "Failed to evict old screenshots"),
+                        }
                         photographer.start();
                         let mut guard = state_clone.lock().unwrap();
                         *guard = Some(photographer);
-                        println!("Timelapse started automatically on app startup");
+                        info!("Timelapse started automatically on app startup");
                     }
                     Err(e) => {
-                        eprintln!("Failed to start timelapse automatically: {}", e);
+                        error!(error = %e, "Failed to start timelapse automatically");
                     }
                 }
             });
@@ -231,11 +2089,98 @@ pub fn run() {
             start_timelapse,
             stop_timelapse,
             is_timelapse_running,
+            pause_timelapse,
+            resume_timelapse,
+            is_timelapse_paused,
+            capture_single_frame,
             get_error_logs,
+            get_error_logs_paginated,
             clear_error_logs,
+            set_black_threshold,
+            set_black_sample_target,
+            set_similarity_threshold,
+            set_output_size,
+            set_blocked_apps,
+            set_battery_saver,
+            set_output_format,
+            set_dir_layout,
+            check_ffmpeg_available,
             extract_video_frames,
+            compile_day_video,
             evict_old_cache,
-            get_screenshot_metadata
+            preview_cache_eviction,
+            get_capture_health,
+            generate_day_gif,
+            set_max_error_logs,
+            get_frames_by_local_hour,
+            set_privacy_masks,
+            clear_privacy_masks,
+            verify_integrity,
+            set_capture_logical_resolution,
+            set_idle_pause,
+            set_aspect_ratio,
+            get_thumbnail,
+            set_generate_thumbnails,
+            backfill_thumbnails,
+            label_day,
+            get_day_labels,
+            set_black_backoff,
+            get_activity_heatmap,
+            set_capture_mode,
+            export_day_zip,
+            set_png_compression_level,
+            frame_at_time,
+            get_day_frames,
+            set_database_enabled,
+            set_timestamp_overlay,
+            estimate_video,
+            preview_capture,
+            purge_frames_between,
+            set_display_output_format,
+            get_capture_bounds,
+            set_capture_crop,
+            get_error_logs_grouped,
+            restart_timelapse,
+            get_frames_by_app,
+            set_min_free_space,
+            set_keep_originals,
+            reprocess_frame,
+            get_log_path,
+            set_interval_schedule,
+            set_record_change_scores,
+            get_change_scores,
+            delete_frame,
+            delete_frames,
+            set_profiling_enabled,
+            get_timing_summary,
+            get_keyframes,
+            compile_keyframes_video,
+            set_capture_display,
+            list_displays,
+            set_filename_width,
+            export_renumbered_sequence,
+            set_retention_days,
+            evict_old_screenshots,
+            get_screenshot_metadata,
+            get_screenshot_context,
+            get_frames_between,
+            list_capture_days,
+            get_frame_image,
+            analyze_frame,
+            get_day_stats,
+            get_total_storage,
+            rebuild_database,
+            set_schedule,
+            clear_schedule,
+            set_auto_compile,
+            set_capture_cursor,
+            reveal_timelapse_folder,
+            set_resize_filter,
+            get_config,
+            set_config,
+            optimize_database,
+            delete_day,
+            export_database_csv
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -261,7 +2206,7 @@ mod tests {
         // Create a mock State wrapper
         let state_wrapper = State::from(&state);
 
-        let result = start_timelapse(state_wrapper).await;
+        let result = start_photographer(state_wrapper, None);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "Timelapse started successfully");
 
@@ -276,12 +2221,12 @@ mod tests {
 
         // Start timelapse first time
         let state_wrapper = State::from(&state);
-        let result = start_timelapse(state_wrapper).await;
+        let result = start_photographer(state_wrapper, None);
         assert!(result.is_ok());
 
         // Try to start again - should fail
         let state_wrapper = State::from(&state);
-        let result = start_timelapse(state_wrapper).await;
+        let result = start_photographer(state_wrapper, None);
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "Timelapse is already running");
     }
@@ -292,7 +2237,7 @@ mod tests {
 
         // Start timelapse first
         let state_wrapper = State::from(&state);
-        let _ = start_timelapse(state_wrapper).await;
+        let _ = start_photographer(state_wrapper, None);
 
         // Stop timelapse
         let state_wrapper = State::from(&state);
@@ -327,7 +2272,7 @@ mod tests {
 
         // Start timelapse
         let state_wrapper = State::from(&state);
-        let _ = start_timelapse(state_wrapper).await;
+        let _ = start_photographer(state_wrapper, None);
 
         // Now should be running
         let state_wrapper = State::from(&state);
@@ -346,19 +2291,64 @@ mod tests {
         assert!(!result.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_pause_and_resume_timelapse() {
+        let state: PhotographerState = Arc::new(Mutex::new(None));
+
+        // Start timelapse
+        let state_wrapper = State::from(&state);
+        let _ = start_photographer(state_wrapper, None);
+
+        // Pause
+        let state_wrapper = State::from(&state);
+        let result = pause_timelapse(state_wrapper).await;
+        assert_eq!(result.unwrap(), "Timelapse paused successfully");
+
+        let state_wrapper = State::from(&state);
+        assert!(is_timelapse_paused(state_wrapper).await.unwrap());
+
+        // Still running while paused
+        let state_wrapper = State::from(&state);
+        assert!(is_timelapse_running(state_wrapper).await.unwrap());
+
+        // Resume
+        let state_wrapper = State::from(&state);
+        let result = resume_timelapse(state_wrapper).await;
+        assert_eq!(result.unwrap(), "Timelapse resumed successfully");
+
+        let state_wrapper = State::from(&state);
+        assert!(!is_timelapse_paused(state_wrapper).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_pause_timelapse_not_running() {
+        let state: PhotographerState = Arc::new(Mutex::new(None));
+
+        let state_wrapper = State::from(&state);
+        let result = pause_timelapse(state_wrapper).await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "Timelapse is not running");
+
+        let state_wrapper = State::from(&state);
+        let result = resume_timelapse(state_wrapper).await;
+        assert!(result.is_err());
+
+        let state_wrapper = State::from(&state);
+        assert!(!is_timelapse_paused(state_wrapper).await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_get_error_logs_when_running() {
         let state: PhotographerState = Arc::new(Mutex::new(None));
 
         // Start timelapse
         let state_wrapper = State::from(&state);
-        let _ = start_timelapse(state_wrapper).await;
+        let _ = start_photographer(state_wrapper, None);
 
-        // Get error logs
+        // Get error logs (persisted errors from earlier sessions may be present)
         let state_wrapper = State::from(&state);
         let result = get_error_logs(state_wrapper).await;
         assert!(result.is_ok());
-        assert_eq!(result.unwrap().len(), 0);
     }
 
     #[tokio::test]
@@ -377,7 +2367,7 @@ mod tests {
 
         // Start timelapse
         let state_wrapper = State::from(&state);
-        let _ = start_timelapse(state_wrapper).await;
+        let _ = start_photographer(state_wrapper, None);
 
         // Clear error logs
         let state_wrapper = State::from(&state);
@@ -395,4 +2385,326 @@ mod tests {
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "Timelapse is not running");
     }
+
+    #[tokio::test]
+    async fn test_set_black_threshold_not_running() {
+        let state: PhotographerState = Arc::new(Mutex::new(None));
+
+        let state_wrapper = State::from(&state);
+        let result = set_black_threshold(state_wrapper, 0.05).await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "Timelapse is not running");
+    }
+
+    fn make_cache_folder(cache_dir: &Path, name: &str, bytes: usize, age: Duration) -> PathBuf {
+        let folder = cache_dir.join(name);
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("frame000001.jpg"), vec![0u8; bytes]).unwrap();
+        std::fs::File::open(&folder)
+            .unwrap()
+            .set_modified(SystemTime::now() - age)
+            .unwrap();
+        folder
+    }
+
+    #[test]
+    fn test_evict_cache_folders_by_age() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cache_dir = temp_dir.path();
+
+        let old = make_cache_folder(cache_dir, "old", 10, Duration::from_secs(20 * 86400));
+        let recent = make_cache_folder(cache_dir, "recent", 10, Duration::from_secs(86400));
+
+        let report = evict_cache_folders(cache_dir, CACHE_MAX_AGE, None).unwrap();
+        assert_eq!(report.removed_by_age, 1);
+        assert_eq!(report.removed_by_size, 0);
+        assert_eq!(report.remaining_bytes, 10);
+        assert!(!old.exists());
+        assert!(recent.exists());
+    }
+
+    #[test]
+    fn test_evict_cache_folders_by_size() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cache_dir = temp_dir.path();
+
+        let oldest = make_cache_folder(cache_dir, "oldest", 100, Duration::from_secs(3 * 86400));
+        let middle = make_cache_folder(cache_dir, "middle", 100, Duration::from_secs(2 * 86400));
+        let newest = make_cache_folder(cache_dir, "newest", 100, Duration::from_secs(86400));
+
+        // Removing the two oldest folders gets the cache under the limit
+        let report = evict_cache_folders(cache_dir, CACHE_MAX_AGE, Some(150)).unwrap();
+        assert_eq!(report.removed_by_age, 0);
+        assert_eq!(report.removed_by_size, 2);
+        assert_eq!(report.remaining_bytes, 100);
+        assert!(!oldest.exists());
+        assert!(!middle.exists());
+        assert!(newest.exists());
+    }
+
+    #[test]
+    fn test_evict_cache_folders_evicts_original_days() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cache_dir = temp_dir.path();
+        let originals = cache_dir.join(timelapse::ORIGINALS_CACHE_DIR);
+
+        let old = make_cache_folder(&originals, "2024-03-01", 10, Duration::from_secs(20 * 86400));
+        let recent = make_cache_folder(&originals, "2024-03-19", 10, Duration::from_secs(86400));
+
+        let report = evict_cache_folders(cache_dir, CACHE_MAX_AGE, None).unwrap();
+        assert_eq!(report.removed_by_age, 1);
+        assert_eq!(report.remaining_bytes, 10);
+        assert!(!old.exists());
+        assert!(recent.exists());
+        assert!(originals.is_dir());
+    }
+
+    #[test]
+    fn test_plan_cache_eviction_picks_expired_then_oldest_folders() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cache_dir = temp_dir.path();
+
+        let old = make_cache_folder(cache_dir, "old", 10, Duration::from_secs(20 * 86400));
+        let oldest = make_cache_folder(cache_dir, "oldest", 100, Duration::from_secs(3 * 86400));
+        let newest = make_cache_folder(cache_dir, "newest", 100, Duration::from_secs(86400));
+
+        let plan = plan_cache_eviction(cache_dir, CACHE_MAX_AGE, Some(150)).unwrap();
+        let picked: Vec<_> = plan
+            .folders
+            .iter()
+            .map(|candidate| (candidate.folder.as_str(), candidate.reason))
+            .collect();
+        assert_eq!(picked, vec![("old", EvictionReason::Age), ("oldest", EvictionReason::Size)]);
+        assert_eq!(plan.folders[0].age_days, 20);
+        assert_eq!(plan.folders[1].size_bytes, 100);
+        assert_eq!(plan.remaining_bytes, 100);
+
+        assert!(old.exists());
+        assert!(oldest.exists());
+        assert!(newest.exists());
+    }
+
+    #[test]
+    fn test_evict_cache_folders_missing_dir() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+
+        let report =
+            evict_cache_folders(&temp_dir.path().join(".cache"), CACHE_MAX_AGE, Some(0)).unwrap();
+        assert_eq!(report.removed_by_age, 0);
+        assert_eq!(report.removed_by_size, 0);
+    }
+
+    #[test]
+    fn test_parse_ffmpeg_version() {
+        let output = "ffmpeg version 6.1.1 Copyright (c) 2000-2023 the FFmpeg developers\n\
+                      built with Apple clang version 15.0.0";
+        assert_eq!(parse_ffmpeg_version(output), Some("6.1.1".to_string()));
+
+        let output = "ffmpeg version n7.0-1-gdeadbeef Copyright (c) 2000-2024";
+        assert_eq!(parse_ffmpeg_version(output), Some("n7.0-1-gdeadbeef".to_string()));
+
+        assert_eq!(parse_ffmpeg_version(""), None);
+        assert_eq!(parse_ffmpeg_version("command not found"), None);
+    }
+
+    #[test]
+    fn test_ffmpeg_unavailable_message() {
+        let error = FfmpegUnavailable::new("ffmpeg could not be executed".to_string());
+        assert!(error.to_string().starts_with("ffmpeg could not be executed. Install ffmpeg"));
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert!(parse_date("2024-03-15").is_ok());
+        assert!(parse_date("2024-3-15").is_err());
+        assert!(parse_date("2024-13-01").is_err());
+        assert!(parse_date("../2024-03-15").is_err());
+        assert!(parse_date("").is_err());
+    }
+
+    #[test]
+    fn test_check_video_filename() {
+        assert_eq!(check_video_filename("2024-03-15.mov").unwrap(), "2024-03-15");
+
+        for traversal in [
+            "../2024-03-15.mov",
+            "../../etc/passwd",
+            "..\\2024-03-15.mov",
+            "/etc/2024-03-15.mov",
+            "2024-03-15/../../x.mov",
+            "..",
+            "2024-03-15..mov",
+        ] {
+            let error = check_video_filename(traversal).unwrap_err();
+            assert!(error.contains("must not contain"), "{}", traversal);
+        }
+
+        let malformed_names =
+            ["", "2024-03-15", "2024-03-15.mp4", "notes.mov", "2024-3-15.mov", ".mov"];
+        for malformed in malformed_names {
+            let error = check_video_filename(malformed).unwrap_err();
+            assert!(error.contains("expected YYYY-MM-DD.mov"), "{}", malformed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extract_video_frames_rejects_traversal() {
+        let result = extract_video_frames("../../etc/passwd".to_string(), 30, None).await;
+        assert!(result.unwrap_err().starts_with("Invalid video filename"));
+    }
+
+    #[tokio::test]
+    async fn test_extract_video_frames_rejects_invalid_fps() {
+        for fps in [0, 121] {
+            let result = extract_video_frames("2024-03-15.mov".to_string(), fps, None).await;
+            assert!(result.unwrap_err().starts_with("Invalid fps"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extract_video_frames_rejects_invalid_quality() {
+        for quality in [0, 32] {
+            let result =
+                extract_video_frames("2024-03-15.mov".to_string(), 30, Some(quality)).await;
+            assert!(result.unwrap_err().starts_with("Invalid quality"));
+        }
+    }
+
+    #[test]
+    fn test_count_extracted_frames() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(count_extracted_frames(temp_dir.path()).unwrap(), 0);
+
+        for name in ["frame000001.jpg", "frame000002.jpg", "frame000003.jpg"] {
+            std::fs::write(temp_dir.path().join(name), "test").unwrap();
+        }
+        std::fs::write(temp_dir.path().join("frame.jpg"), "test").unwrap();
+        std::fs::write(temp_dir.path().join(".DS_Store"), "test").unwrap();
+        assert_eq!(count_extracted_frames(temp_dir.path()).unwrap(), 3);
+
+        assert!(count_extracted_frames(&temp_dir.path().join("missing")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_frame_image_rejects_invalid_date() {
+        let state: PhotographerState = Arc::new(Mutex::new(None));
+
+        let state_wrapper = State::from(&state);
+        let result = get_frame_image(state_wrapper, "../../etc/passwd".to_string(), 1).await;
+        assert!(result.unwrap_err().starts_with("Invalid date"));
+    }
+
+    #[tokio::test]
+    async fn test_set_output_size_requires_both_dimensions() {
+        let state: PhotographerState = Arc::new(Mutex::new(None));
+
+        let state_wrapper = State::from(&state);
+        let result = set_output_size(state_wrapper, Some(1920), None).await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "Both width and height must be provided");
+    }
+
+    #[tokio::test]
+    async fn test_get_frames_between_invalid_timestamp() {
+        let state: PhotographerState = Arc::new(Mutex::new(None));
+
+        let state_wrapper = State::from(&state);
+        let result = get_frames_between(
+            state_wrapper,
+            "yesterday".to_string(),
+            "2024-03-15T12:00:00Z".to_string(),
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Invalid timestamp"));
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("14:34").unwrap().to_string(), "14:34:00");
+        assert_eq!(parse_time("14:34:05").unwrap().to_string(), "14:34:05");
+        assert!(parse_time("2:34pm").is_err());
+        assert!(parse_time("25:00").is_err());
+    }
+
+    #[test]
+    fn test_parse_rfc3339_normalizes_to_utc() {
+        let timestamp = parse_rfc3339("2024-03-15T14:00:00+02:00").unwrap();
+        assert_eq!(timestamp.to_rfc3339(), "2024-03-15T12:00:00+00:00");
+    }
+
+    #[tokio::test]
+    async fn test_get_day_stats_not_running() {
+        let state: PhotographerState = Arc::new(Mutex::new(None));
+
+        let state_wrapper = State::from(&state);
+        let result = get_day_stats(state_wrapper, "2024-03-15".to_string()).await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "Timelapse is not running");
+    }
+
+    #[tokio::test]
+    async fn test_delete_day_rejects_invalid_date() {
+        let state: PhotographerState = Arc::new(Mutex::new(None));
+
+        let state_wrapper = State::from(&state);
+        let result = delete_day(state_wrapper, "..".to_string()).await;
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Invalid date"));
+    }
+
+    #[test]
+    fn test_resolve_reveal_folder() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let day_dir = root.join("2024").join("03").join("15");
+        std::fs::create_dir_all(&day_dir).unwrap();
+
+        assert_eq!(resolve_reveal_folder(root, None).unwrap(), root);
+        assert_eq!(resolve_reveal_folder(root, Some("2024-03-15")).unwrap(), day_dir);
+        assert_eq!(
+            resolve_reveal_folder(root, Some("2024-03-16")).unwrap_err(),
+            "No captures found for 2024-03-16"
+        );
+        assert!(resolve_reveal_folder(root, Some("../etc"))
+            .unwrap_err()
+            .contains("Invalid date"));
+        assert!(resolve_reveal_folder(&root.join("missing"), None).is_err());
+    }
+
+    #[test]
+    fn test_current_log_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log_dir = temp_dir.path();
+        assert!(current_log_file(log_dir).is_err());
+
+        std::fs::write(log_dir.join("timelapse.2024-03-14.log"), "").unwrap();
+        std::fs::write(log_dir.join("timelapse.2024-03-15.log"), "").unwrap();
+        std::fs::write(log_dir.join("notes.txt"), "").unwrap();
+        assert_eq!(
+            current_log_file(log_dir).unwrap(),
+            log_dir.join("timelapse.2024-03-15.log")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compile_day_video_invalid_date() {
+        let result = compile_day_video("../../etc".to_string(), 30, None, None).await;
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Invalid date"));
+    }
+
+    #[tokio::test]
+    async fn test_compile_day_video_missing_day() {
+        let result = compile_day_video("1970-01-01".to_string(), 0, None, None).await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "No captures found for 1970-01-01");
+    }
+
+    #[tokio::test]
+    async fn test_compile_day_video_rejects_unknown_codec() {
+        let result =
+            compile_day_video("2024-03-15".to_string(), 30, Some("-vf".to_string()), None).await;
+        assert!(result.unwrap_err().contains("codec must be one of"));
+    }
 }
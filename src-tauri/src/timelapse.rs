@@ -1,21 +1,148 @@
-use active_win_pos_rs::get_active_window;
-use chrono::{DateTime, Utc, Local};
-use magick_rust::{magick_wand_genesis, MagickWand};
+use active_win_pos_rs::{get_active_window, ActiveWindow};
+use base64::Engine;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+use magick_rust::{magick_query_formats, magick_wand_genesis, MagickWand};
 use screenshots::Screen;
 use serde::{Deserialize, Serialize};
 use std::{
-    path::PathBuf,
+    collections::{BTreeMap, VecDeque},
+    io::Write,
+    path::{Path, PathBuf},
     sync::atomic::{AtomicBool, Ordering},
-    sync::Once,
-    sync::{Arc, Mutex},
+    sync::{Once, OnceLock},
+    time::Instant,
+    sync::{Arc, Mutex, PoisonError},
 };
+use tauri::{AppHandle, Emitter};
 use thiserror::Error;
-use tokio::time::{sleep, Duration};
-use crate::database::ScreenshotDatabase;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Duration};
+use tracing::{debug, error, info, warn};
+use crate::database::{
+    ChangeScore, FrameDisplay, FrameMeta, ScreenshotContext, ScreenshotDatabase, ScreenshotRecord,
+    ScreenshotWriter,
+};
 
 // Ensure MagickWand is initialized only once
 static MAGICK_WAND_GENESIS: Once = Once::new();
 
+// Default mean brightness below which a frame is considered all black
+const DEFAULT_BLACK_THRESHOLD: f64 = 0.01;
+
+// Default number of pixels sampled when measuring a frame's brightness
+const DEFAULT_BLACK_SAMPLE_TARGET: usize = 1000;
+
+// Default mean per-pixel difference below which a frame is considered a duplicate
+// of the previous one
+const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.001;
+
+// Dimensions of the downscaled grayscale signature used to compare frames
+const SIGNATURE_WIDTH: usize = 32;
+const SIGNATURE_HEIGHT: usize = 20;
+
+// Width frames are scaled down to before scoring how much they changed
+const CHANGE_SCORE_WIDTH: usize = 320;
+
+// Largest output dimension accepted by set_output_size
+const MAX_OUTPUT_DIMENSION: u32 = 16384;
+
+// Default number of error log entries kept in memory and returned to the frontend
+const DEFAULT_MAX_ERROR_LOGS: usize = 10000;
+
+// Events emitted to the frontend from the capture loop
+pub const ERROR_EVENT: &str = "timelapse-error";
+pub const FRAME_SAVED_EVENT: &str = "timelapse-frame-saved";
+pub const REBUILD_PROGRESS_EVENT: &str = "database-rebuild-progress";
+pub const DAY_COMPILED_EVENT: &str = "day-compiled";
+pub const DISPLAYS_CHANGED_EVENT: &str = "displays-changed";
+pub const PERMISSION_WARNING_EVENT: &str = "permission-warning";
+pub const LOW_DISK_SPACE_EVENT: &str = "low-disk-space";
+pub const DISPLAY_MISSING_EVENT: &str = "display-missing";
+
+// All-black frames in a row after which missing screen recording permission is
+// the likely cause, since macOS then hands back black captures instead of failing
+const PERMISSION_BLACK_FRAME_RUN: u32 = 10;
+
+// Idle time after which the user is taken to be away or the screen locked. Black
+// frames are expected then, so they don't count towards the permission warning.
+const AWAY_IDLE_SECS: f64 = 60.0;
+
+// Frame rate used when compiling a day's video without an explicit fps
+pub const DEFAULT_VIDEO_FPS: u32 = 30;
+
+// Encoders a day's video can be compiled with, the one used when none is given,
+// and x264/x265's default quality on their 0 (lossless) to 51 CRF scale
+const VIDEO_CODECS: &[&str] = &["libx264", "libx265"];
+const DEFAULT_VIDEO_CODEC: &str = "libx264";
+const DEFAULT_VIDEO_CRF: u8 = 23;
+const MAX_VIDEO_CRF: u8 = 51;
+
+// Rough size of an H.264 frame relative to the still it was encoded from. Screen
+// recordings change little between frames, so most of each still compresses away.
+const VIDEO_SIZE_FACTOR: f64 = 0.05;
+
+// Playback rate of day GIFs, and the most frames one may contain
+const GIF_FPS: u32 = 15;
+const MAX_GIF_FRAMES: u32 = 300;
+
+// How long stop_and_join waits for the capture task to notice it should exit
+const STOP_JOIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+// How often the capture loop's longer waits check whether it's been stopped
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+// Wait after the first failed capture, doubling on each consecutive failure up to the cap
+const ERROR_BACKOFF_INITIAL_SECS: u64 = 2;
+const ERROR_BACKOFF_MAX_SECS: u64 = 60;
+
+// Seconds between captures normally, and the longest interval accepted for battery
+// saver or an interval schedule
+const CAPTURE_INTERVAL_SECS: u64 = 1;
+const MAX_BATTERY_INTERVAL_SECS: u64 = 3600;
+
+// Folder in `.cache` full-resolution captures are kept in, one folder per day
+pub const ORIGINALS_CACHE_DIR: &str = "originals";
+
+// Folder under the Timelapse root the app's log files are written to. Hidden so
+// it isn't listed with the days.
+pub const LOG_DIR_NAME: &str = ".logs";
+
+// Width of the thumbnails used to browse a day's frames
+const THUMBNAIL_WIDTH: usize = 160;
+
+// Wait after an all-black frame by default, and the longest wait accepted
+const DEFAULT_BLACK_BACKOFF_SECS: u64 = 10;
+const MAX_BLACK_BACKOFF_SECS: u64 = 3600;
+
+// zlib level PNGs are written at by default, matching ImageMagick's own default
+const DEFAULT_PNG_COMPRESSION_LEVEL: u8 = 7;
+const MAX_PNG_COMPRESSION_LEVEL: u8 = 9;
+
+// Digits frame numbers are zero-padded to in filenames by default, and the range
+// accepted. Narrower names would sort out of order long before a day fills them,
+// and no frame number needs more than 10 digits.
+const DEFAULT_FILENAME_WIDTH: usize = 5;
+const MIN_FILENAME_WIDTH: usize = 5;
+const MAX_FILENAME_WIDTH: usize = 10;
+
+// strftime format of the timestamp overlay unless one is configured
+const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// Free space below which capture waits rather than fill the Timelapse volume, by
+// default, and how often the loop checks whether space has been freed up since
+const DEFAULT_MIN_FREE_BYTES: u64 = 500 * 1024 * 1024;
+const LOW_DISK_SPACE_RECHECK_SECS: u64 = 30;
+
+// How often the loop asks which displays are connected, to notice docking
+const DISPLAY_POLL_SECS: u64 = 10;
+
+// Longest idle threshold accepted for idle pause
+const MAX_IDLE_THRESHOLD_SECS: u64 = 24 * 3600;
+
+// Attempts at writing an encoded frame before giving up on it, and the pause between them
+const FRAME_WRITE_ATTEMPTS: u32 = 3;
+const FRAME_WRITE_RETRY_DELAY: Duration = Duration::from_millis(250);
+
 fn init_magick_wand() {
     MAGICK_WAND_GENESIS.call_once(|| {
         magick_wand_genesis();
@@ -28,495 +155,7697 @@ pub struct ErrorLogEntry {
     pub error_message: String,
 }
 
-#[derive(Error, Debug)]
-pub enum Error {
-    #[error("Unable to find home dir")]
-    UnableToFindHomeDir,
-
-    #[error("Unable to create screenshot because: {reason}")]
-    UnableToCreateScreenshot { reason: String },
+/// In-memory error log, dropping the oldest entries once it holds `capacity`
+#[derive(Debug, Clone)]
+struct ErrorLog {
+    entries: VecDeque<ErrorLogEntry>,
+    capacity: usize,
+}
 
-    #[error("Unable to resize screenshot {path} because: {reason}")]
-    UnableToResizeScreenshot { path: String, reason: String },
+impl ErrorLog {
+    fn new(capacity: usize) -> Self {
+        ErrorLog {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
 
-    #[error("Unable convert screenshot path to string")]
-    UnableToConvertScreenshotPathToString,
+    fn push(&mut self, entry: ErrorLogEntry) {
+        self.entries.push_back(entry);
+        self.truncate();
+    }
 
-    #[error("Unable to check if image is black: {reason}")]
-    UnableToCheckIfImageIsBlack { reason: String },
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.truncate();
+    }
 
-    #[error("Database error: {0}")]
-    DatabaseError(#[from] rusqlite::Error),
+    fn truncate(&mut self) {
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+}
 
-    #[error("IO Error")]
-    IoError(#[from] std::io::Error),
+/// A run of consecutive error log entries with the same message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorLogGroup {
+    pub error_message: String,
+    pub count: usize,
+    pub first_timestamp: DateTime<Utc>,
+    pub last_timestamp: DateTime<Utc>,
 }
 
-pub struct Photographer {
-    timelapse_root_path: PathBuf,
-    running: Arc<AtomicBool>,
-    error_logs: Arc<Mutex<Vec<ErrorLogEntry>>>,
-    db: Arc<Mutex<ScreenshotDatabase>>,
+/// A page of error log entries, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorLogPage {
+    pub entries: Vec<ErrorLogEntry>,
+    /// Total number of entries available across all pages
+    pub total: usize,
 }
 
-impl Photographer {
-    pub fn new() -> Result<Photographer, Error> {
-        // Initialize MagickWand
-        init_magick_wand();
+/// What happened to a single capture attempt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScreenshotOutcome {
+    /// The frame was written to disk and recorded in the database
+    Saved { frame_number: u32, path: PathBuf },
+    /// The frame was all black and was discarded
+    Black,
+    /// The frame was nearly identical to the previous one and was discarded
+    Duplicate,
+    /// Capture was skipped because a blocked app was in the foreground
+    Skipped,
+}
 
-        let timelapse_root_path = dirs::home_dir()
-            .ok_or(Error::UnableToFindHomeDir)?
-            .join("Timelapse");
+/// The result of the most recent capture loop iteration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TickResult {
+    Saved,
+    Black,
+    Duplicate,
+    Skipped,
+    Error,
+}
 
-        // Create the Timelapse directory if it doesn't exist
-        std::fs::create_dir_all(&timelapse_root_path)?;
+/// Whether the capture loop is actually producing frames, as opposed to just running
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaptureHealth {
+    pub last_frame_at: Option<DateTime<Utc>>,
+    pub secs_since_last_frame: Option<i64>,
+    pub consecutive_errors: u32,
+    /// All-black frames in a row since the last frame with content, not counting
+    /// those taken while the user was away or the screen locked
+    pub consecutive_black_frames: u32,
+    pub last_tick: Option<TickResult>,
+}
 
-        // Initialize the database
-        let db_path = timelapse_root_path.join("screenshots.db");
-        let db = ScreenshotDatabase::new(db_path)?;
+impl CaptureHealth {
+    fn record(&mut self, result: &Result<ScreenshotOutcome, Error>, now: DateTime<Utc>) {
+        let tick = match result {
+            Ok(ScreenshotOutcome::Saved { .. }) => TickResult::Saved,
+            Ok(ScreenshotOutcome::Black) => TickResult::Black,
+            Ok(ScreenshotOutcome::Duplicate) => TickResult::Duplicate,
+            Ok(ScreenshotOutcome::Skipped) => TickResult::Skipped,
+            Err(_) => TickResult::Error,
+        };
 
-        Ok(Photographer {
-            timelapse_root_path,
-            running: Arc::new(AtomicBool::new(false)),
-            error_logs: Arc::new(Mutex::new(Vec::new())),
-            db: Arc::new(Mutex::new(db)),
-        })
+        match tick {
+            TickResult::Saved => {
+                self.last_frame_at = Some(now);
+                self.consecutive_errors = 0;
+            }
+            TickResult::Error => self.consecutive_errors = self.consecutive_errors.saturating_add(1),
+            _ => self.consecutive_errors = 0,
+        }
+        // Skipped and failed ticks say nothing about what the screen shows
+        match tick {
+            TickResult::Black => {
+                self.consecutive_black_frames = self.consecutive_black_frames.saturating_add(1);
+            }
+            TickResult::Saved | TickResult::Duplicate => self.consecutive_black_frames = 0,
+            TickResult::Skipped | TickResult::Error => {}
+        }
+        self.last_tick = Some(tick);
     }
 
-    pub fn start(&self) -> Arc<AtomicBool> {
-        let running = Arc::clone(&self.running);
-        running.store(true, Ordering::SeqCst);
+    /// A copy with `secs_since_last_frame` filled in relative to `now`
+    fn as_of(&self, now: DateTime<Utc>) -> CaptureHealth {
+        CaptureHealth {
+            secs_since_last_frame: self.last_frame_at.map(|at| (now - at).num_seconds()),
+            ..self.clone()
+        }
+    }
+}
 
-        let timelapse_root_path = self.timelapse_root_path.clone();
-        let running_clone = Arc::clone(&running);
-        let error_logs_clone = Arc::clone(&self.error_logs);
-        let db_clone = Arc::clone(&self.db);
+/// How day directories are arranged under the Timelapse root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DirLayout {
+    /// `~/Timelapse/YYYY-MM-DD`, the layout the frontend browses
+    #[default]
+    Flat,
+    /// `~/Timelapse/YYYY/MM/DD`, which keeps the root small after years of capture
+    Monthly,
+}
 
-        tokio::spawn(async move {
-            println!("Starting timelapse background task...");
+impl DirLayout {
+    /// The directory frames captured on `date` are written to
+    pub fn day_dir(&self, timelapse_root_path: &Path, date: NaiveDate) -> PathBuf {
+        match self {
+            DirLayout::Flat => timelapse_root_path.join(date.format("%Y-%m-%d").to_string()),
+            DirLayout::Monthly => timelapse_root_path
+                .join(date.format("%Y").to_string())
+                .join(date.format("%m").to_string())
+                .join(date.format("%d").to_string()),
+        }
+    }
+}
 
-            while running_clone.load(Ordering::SeqCst) {
-                match Self::do_screenshot(&timelapse_root_path, &db_clone).await {
-                    Ok(is_black) => {
-                        if is_black {
-                            // Image was all black and deleted, wait 10 seconds
-                            sleep(Duration::from_secs(10)).await;
-                        } else {
-                            // Normal screenshot, wait 1 second
-                            sleep(Duration::from_secs(1)).await;
-                        }
-                    }
-                    Err(error) => {
-                        eprintln!("Screenshot error: {}", error);
-
-                        // Log the error
-                        let entry = ErrorLogEntry {
-                            timestamp: Utc::now(),
-                            error_message: error.to_string(),
-                        };
-
-                        if let Ok(mut logs) = error_logs_clone.lock() {
-                            logs.push(entry);
-                            if logs.len() > 10000 {
-                                logs.remove(0);
-                            }
-                        }
+/// What part of the screen each frame shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CaptureMode {
+    /// The whole screen showing the foreground window
+    #[default]
+    FocusedScreen,
+    /// Just the foreground window, cropped to the part of it that's on screen
+    ActiveWindow,
+    /// The whole screen of the display with this id, wherever the foreground
+    /// window is. Falls back to the primary display while it's disconnected.
+    FixedDisplay(u32),
+}
 
-                        sleep(Duration::from_secs(60)).await;
-                    }
-                }
-            }
+/// A rectangle in screen coordinates, relative to the top-left of its screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
 
-            println!("Timelapse background task stopped.");
-        });
+impl Rect {
+    /// The part of this rectangle inside `region` as `(x, y, width, height)`
+    /// fractions of the region, so it can be mapped onto the scaled frame. `None`
+    /// if the two don't overlap.
+    fn fraction_of(&self, region: Rect) -> Option<(f64, f64, f64, f64)> {
+        let right = |rect: &Rect| i64::from(rect.x) + i64::from(rect.width);
+        let bottom = |rect: &Rect| i64::from(rect.y) + i64::from(rect.height);
+        let left = i64::from(self.x.max(region.x));
+        let top = i64::from(self.y.max(region.y));
+        let (right, bottom) = (right(self).min(right(&region)), bottom(self).min(bottom(&region)));
+        if right <= left || bottom <= top {
+            return None;
+        }
 
-        running
+        let (rw, rh) = (f64::from(region.width), f64::from(region.height));
+        Some((
+            (left - i64::from(region.x)) as f64 / rw,
+            (top - i64::from(region.y)) as f64 / rh,
+            (right - left) as f64 / rw,
+            (bottom - top) as f64 / rh,
+        ))
     }
+}
 
-    pub fn stop(&self) {
-        self.running.store(false, Ordering::SeqCst);
+/// A screen region blacked out in captured frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrivacyMask {
+    /// The region, in the coordinates of the screen it's on
+    #[serde(flatten)]
+    pub rect: Rect,
+    /// The display the mask is on, as `list_displays` reports it, or `None` to
+    /// mask the same region of whichever screen is captured
+    #[serde(default)]
+    pub display_id: Option<u32>,
+}
+
+impl PrivacyMask {
+    /// Whether the mask covers frames captured from display `display_id`
+    fn applies_to(&self, display_id: u32) -> bool {
+        self.display_id.is_none_or(|id| id == display_id)
     }
+}
 
-    pub fn get_error_logs(&self) -> Vec<ErrorLogEntry> {
-        self.error_logs
-            .lock()
-            .map(|logs| logs.clone())
-            .unwrap_or_default()
+/// Amounts trimmed off each edge of the captured screen, in screen coordinates,
+/// e.g. to leave the menu bar or dock out of every frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct EdgeCrop {
+    pub top: u32,
+    pub bottom: u32,
+    pub left: u32,
+    pub right: u32,
+}
+
+impl EdgeCrop {
+    /// The part of a `width` by `height` screen left after trimming. Crops larger
+    /// than the screen are clamped so at least a pixel remains each way.
+    fn apply(&self, width: u32, height: u32) -> Rect {
+        let left = self.left.min(width.saturating_sub(1));
+        let right = self.right.min(width.saturating_sub(left + 1));
+        let top = self.top.min(height.saturating_sub(1));
+        let bottom = self.bottom.min(height.saturating_sub(top + 1));
+        Rect {
+            x: left as i32,
+            y: top as i32,
+            width: width - left - right,
+            height: height - top - bottom,
+        }
     }
+}
 
-    pub fn clear_error_logs(&self) {
-        if let Ok(mut logs) = self.error_logs.lock() {
-            logs.clear();
+/// Filter used when scaling captures to the output size, trading speed for sharpness
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ResizeFilter {
+    /// Fastest, but soft
+    #[default]
+    Box,
+    Triangle,
+    Catrom,
+    /// Sharpest, and the most CPU-intensive
+    Lanczos,
+}
+
+impl ResizeFilter {
+    fn magick_filter(&self) -> magick_rust::FilterType {
+        match self {
+            ResizeFilter::Box => magick_rust::FilterType::Box,
+            ResizeFilter::Triangle => magick_rust::FilterType::Triangle,
+            ResizeFilter::Catrom => magick_rust::FilterType::Catrom,
+            ResizeFilter::Lanczos => magick_rust::FilterType::Lanczos,
         }
     }
+}
 
-    pub fn get_screenshot_metadata(&self, frame_number: u32) -> Result<Option<(String, String)>, Error> {
-        if let Ok(db_guard) = self.db.lock() {
-            Ok(db_guard.get_screenshot_by_frame(frame_number)?)
-        } else {
-            Err(Error::DatabaseError(rusqlite::Error::InvalidQuery))
+/// Corner of the frame the timestamp overlay is drawn in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OverlayCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl OverlayCorner {
+    fn gravity(&self) -> magick_rust::GravityType {
+        match self {
+            OverlayCorner::TopLeft => magick_rust::GravityType::NorthWest,
+            OverlayCorner::TopRight => magick_rust::GravityType::NorthEast,
+            OverlayCorner::BottomLeft => magick_rust::GravityType::SouthWest,
+            OverlayCorner::BottomRight => magick_rust::GravityType::SouthEast,
         }
     }
+}
 
-    fn create_day_dir_if_needed(timelapse_root_path: &PathBuf) -> Result<PathBuf, Error> {
-        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
-        let day_dir = timelapse_root_path.join(&today);
-        std::fs::create_dir_all(&day_dir)?;
-        Ok(day_dir)
+impl std::str::FromStr for ResizeFilter {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.trim().to_lowercase().as_str() {
+            "box" => Ok(ResizeFilter::Box),
+            "triangle" => Ok(ResizeFilter::Triangle),
+            "catrom" => Ok(ResizeFilter::Catrom),
+            "lanczos" => Ok(ResizeFilter::Lanczos),
+            _ => Err(Error::InvalidSetting {
+                reason: format!(
+                    "unknown resize filter '{}', expected box, triangle, catrom or lanczos",
+                    name
+                ),
+            }),
+        }
     }
+}
 
-    async fn do_screenshot(
-        timelapse_root_path: &PathBuf,
-        db: &Arc<Mutex<ScreenshotDatabase>>,
-    ) -> Result<bool, Error> {
-        let day_dir = Self::create_day_dir_if_needed(timelapse_root_path)?;
-        let filename = next_filename(&day_dir)?;
-        let screenshot_path = String::from(
-            day_dir
-                .join(&filename)
-                .to_str()
-                .ok_or(Error::UnableToConvertScreenshotPathToString)?,
-        );
+/// Image format frames are encoded as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    /// WebP, either lossless or lossy at `quality` (0-100)
+    WebP { lossless: bool, quality: u8 },
+    /// AVIF at `quality` (0-100), the smallest files but the slowest to encode
+    Avif { quality: u8 },
+}
 
-        let image_data = capture_screenshot().await?;
-        resize_screenshot(&image_data, &screenshot_path).await?;
+impl OutputFormat {
+    /// File extension used for frames in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::WebP { .. } => "webp",
+            OutputFormat::Avif { .. } => "avif",
+        }
+    }
 
-        // Check if the image is all black
-        if is_image_all_black(&screenshot_path).await? {
-            println!("Screenshot is all black, deleting: {}", screenshot_path);
-            std::fs::remove_file(&screenshot_path)?;
-            Ok(true) // Return true to indicate image was black and deleted
-        } else {
-            // Extract frame number from filename (e.g., "00001.png" -> 1)
-            let frame_number: u32 = filename
-                .replace(".png", "")
-                .parse()
-                .unwrap_or(0);
+    /// MIME type of frames encoded in this format
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "image/png",
+            OutputFormat::WebP { .. } => "image/webp",
+            OutputFormat::Avif { .. } => "image/avif",
+        }
+    }
 
-            // Insert metadata into database with both UTC and local timestamps
-            let created_at = Utc::now();
-            let local_time = Local::now();
-            if let Ok(db_guard) = db.lock() {
-                db_guard.insert_screenshot(frame_number, created_at, local_time)?;
-            }
+    /// Format name as ImageMagick knows it
+    fn magick_format(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "PNG",
+            OutputFormat::WebP { .. } => "WEBP",
+            OutputFormat::Avif { .. } => "AVIF",
+        }
+    }
 
-            Ok(false) // Return false for normal screenshots
+    /// Whether the linked ImageMagick build can encode this format. Formats can be
+    /// listed even when their encoder is missing, so WebP and AVIF are checked by
+    /// encoding a test image, once per format.
+    pub fn is_supported(&self) -> bool {
+        static WEBP_SUPPORTED: OnceLock<bool> = OnceLock::new();
+        static AVIF_SUPPORTED: OnceLock<bool> = OnceLock::new();
+
+        match self {
+            OutputFormat::Png => magick_query_formats(self.magick_format())
+                .map(|formats| !formats.is_empty())
+                .unwrap_or(false),
+            OutputFormat::WebP { .. } => *WEBP_SUPPORTED.get_or_init(|| can_encode(self)),
+            OutputFormat::Avif { .. } => *AVIF_SUPPORTED.get_or_init(|| can_encode(self)),
         }
     }
 }
 
-fn next_filename(day_dir: &PathBuf) -> Result<String, Error> {
-    let entries = std::fs::read_dir(day_dir)?;
-    let files = entries
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| {
-            entry
-                .file_type()
-                .map(|file_type| file_type.is_file())
-                .unwrap_or(false)
-        })
-        .filter_map(|entry| entry.file_name().to_str().map(|s| s.to_string()));
+/// Try encoding a 1x1 image in `format`
+fn can_encode(format: &OutputFormat) -> bool {
+    init_magick_wand();
 
-    let max = files
-        .filter_map(|filename| {
-            filename
-                .replace(".jpg", "")
-                .replace(".png", "")
-                .parse::<i32>()
-                .ok()
-        })
-        .max()
-        .unwrap_or(0);
+    let wand = MagickWand::new();
+    wand.new_image(1, 1, &magick_rust::PixelWand::new())
+        .and_then(|_| wand.set_image_format(format.magick_format()))
+        .and_then(|_| wand.write_image_blob(format.magick_format()))
+        .is_ok_and(|encoded| !encoded.is_empty())
+}
 
-    Ok(format!("{:05}.png", max + 1))
+/// Existing directories holding frames for `date`. Both layouts are checked so
+/// days captured before the layout was changed can still be found.
+pub fn existing_day_dirs(timelapse_root_path: &Path, date: NaiveDate) -> Vec<PathBuf> {
+    [DirLayout::Flat, DirLayout::Monthly]
+        .iter()
+        .map(|layout| layout.day_dir(timelapse_root_path, date))
+        .filter(|day_dir| day_dir.is_dir())
+        .collect()
 }
 
-async fn capture_screenshot() -> Result<Vec<u8>, Error> {
-    // Get the focused screen by finding which screen contains the active window
-    let focused_screen = get_focused_screen().await?;
+/// Every day directory under the Timelapse root, in either layout, with the
+/// date it holds. Directories whose names don't form a valid date are skipped.
+pub fn list_day_dirs(timelapse_root_path: &Path) -> Vec<(NaiveDate, PathBuf)> {
+    let subdirs = |dir: &Path| -> Vec<(String, PathBuf)> {
+        std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_type()
+                    .map(|file_type| file_type.is_dir())
+                    .unwrap_or(false)
+            })
+            .filter_map(|entry| {
+                let name = entry.file_name().to_str()?.to_string();
+                Some((name, entry.path()))
+            })
+            .collect()
+    };
+    let is_digits = |name: &str, len: usize| {
+        name.len() == len && name.chars().all(|c| c.is_ascii_digit())
+    };
 
-    // Capture screenshot using system API
-    let image = focused_screen
-        .capture()
-        .map_err(|err| Error::UnableToCreateScreenshot {
-            reason: err.to_string(),
-        })?;
+    let mut day_dirs = Vec::new();
+    for (name, path) in subdirs(timelapse_root_path) {
+        // Flat layout: YYYY-MM-DD
+        if name.len() == 10 {
+            if let Ok(date) = NaiveDate::parse_from_str(&name, "%Y-%m-%d") {
+                day_dirs.push((date, path));
+            }
+            continue;
+        }
 
-    // The Image struct contains PNG data in its buffer
-    // We need to save it as a file
-    let buffer = image.buffer();
-    // std::fs::write(screenshot_path, buffer).map_err(|_| Error::UnableToCreateScreenshot)?;
+        // Monthly layout: YYYY/MM/DD
+        if !is_digits(&name, 4) {
+            continue;
+        }
+        let months = subdirs(&path).into_iter().filter(|(month, _)| is_digits(month, 2));
+        for (month, month_path) in months {
+            let days = subdirs(&month_path).into_iter().filter(|(day, _)| is_digits(day, 2));
+            for (day, day_path) in days {
+                let date_string = format!("{}-{}-{}", name, month, day);
+                if let Ok(date) = NaiveDate::parse_from_str(&date_string, "%Y-%m-%d") {
+                    day_dirs.push((date, day_path));
+                }
+            }
+        }
+    }
 
-    // println!("Screenshot saved to {}", screenshot_path);
-    Ok(buffer.clone())
+    day_dirs
 }
 
-async fn get_focused_screen() -> Result<Screen, Error> {
-    // Get the active window to determine which screen is focused
-    let active_window = get_active_window().map_err(|_| Error::UnableToCreateScreenshot {
-        reason: "Can't get active window".to_owned(),
-    })?;
-
-    // Get all available screens
-    let screens = Screen::all().map_err(|err| Error::UnableToCreateScreenshot {
-        reason: err.to_string(),
-    })?;
+/// Every adjustable capture setting. Kept behind one mutex on the photographer
+/// so a capture tick always sees a consistent set, and saved to `config.json`
+/// in the Timelapse root.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimelapseConfig {
+    pub black_threshold: f64,
+    pub black_sample_target: usize,
+    /// Seconds to wait before capturing again after an all-black frame
+    pub black_backoff_secs: u64,
+    pub similarity_threshold: f64,
+    /// Output canvas size, or `None` to keep the focused screen's native resolution
+    pub output_size: Option<(u32, u32)>,
+    /// `(width, height)` ratio frames are letterboxed to, keeping the capture's
+    /// longer edge. Takes precedence over `output_size` when set.
+    pub aspect_ratio: Option<(u32, u32)>,
+    pub blocked_apps: Vec<String>,
+    pub dir_layout: DirLayout,
+    pub output_format: OutputFormat,
+    /// Formats for frames captured from particular displays, by display id.
+    /// Displays without an entry use `output_format`. Each tick captures a single
+    /// screen (there's no all-screens mode), so this applies to whichever display
+    /// that is. Ids rather than screen indexes, since indexes shift as displays are
+    /// connected. A day's frames can mix formats as a result, which compiling and
+    /// exporting handle by listing frames rather than globbing one extension.
+    pub display_output_formats: BTreeMap<u32, OutputFormat>,
+    /// zlib level (0-9) for PNG frames. Higher levels spend more CPU per frame
+    /// for smaller files; screenshots usually shrink only a few percent past 6.
+    pub png_compression_level: u8,
+    /// Digits frame numbers are zero-padded to in filenames, e.g. 5 for `00042.png`
+    pub filename_width: usize,
+    /// Capture interval used while discharging, or `None` when battery saver is off
+    pub battery_interval_secs: Option<u64>,
+    /// Seconds without keyboard or mouse input after which capture pauses until
+    /// input resumes, or `None` when idle pause is off
+    pub idle_pause_secs: Option<u64>,
+    /// Local `(start_hour, end_hour)` ranges to capture in, or empty to always capture
+    pub schedule: Vec<(u8, u8)>,
+    /// Local `(start_hour, end_hour, interval_secs)` ranges to capture at a different
+    /// interval in. The first matching range wins; outside them the base interval applies.
+    pub interval_schedule: Vec<(u8, u8, u64)>,
+    /// Compile the previous day's video when the date changes
+    pub auto_compile: bool,
+    /// Draw a marker where the mouse pointer is
+    pub capture_cursor: bool,
+    pub resize_filter: ResizeFilter,
+    pub capture_mode: CaptureMode,
+    /// Edges trimmed off the screen before frames are fitted to the canvas
+    pub capture_crop: EdgeCrop,
+    /// Downscale captures to the screen's logical size (e.g. half of a Retina
+    /// display's pixels) before fitting them to `output_size`. Frames are smaller
+    /// and cheaper to process, but text is noticeably softer when zoomed in.
+    pub capture_logical_resolution: bool,
+    /// Store a small thumbnail of each frame for browsing a day's grid
+    pub generate_thumbnails: bool,
+    /// Also keep each capture at full resolution, so `reprocess_frame` can render
+    /// the frame again after settings change. Takes several times the space.
+    /// Originals are masked with the privacy masks set when they're captured,
+    /// but keep everything outside the crop or window being captured, and masks
+    /// added later don't reach originals that were already kept.
+    pub keep_originals: bool,
+    /// Score how much each saved frame changed from the one before it, for
+    /// finding busy periods. Costs an extra image comparison per frame.
+    pub record_change_scores: bool,
+    /// Record how long each stage of every capture takes, for `get_timing_summary`
+    pub profiling_enabled: bool,
+    /// Screen regions blacked out in frames, on one display or on all of them
+    pub privacy_masks: Vec<PrivacyMask>,
+    /// Burn the capture time into each frame
+    pub timestamp_overlay: bool,
+    /// strftime format the overlay's capture time is written in
+    pub timestamp_format: String,
+    pub timestamp_corner: OverlayCorner,
+    /// Days of screenshots to keep, 0 to keep them forever
+    pub retention_days: u32,
+    /// Error log entries kept in memory and returned by `get_error_logs`
+    pub max_error_logs: usize,
+    /// Capture waits while the Timelapse volume has less free space than this,
+    /// or 0 to capture until writes fail
+    pub min_free_bytes: u64,
+    /// Record each frame's metadata in `screenshots.db`. When off, only the frame
+    /// files are written. Read once at launch.
+    pub database_enabled: bool,
+}
 
-    // Find the screen that contains the active window
-    for screen in screens {
-        let screen_rect = (
-            screen.display_info.x,
-            screen.display_info.y,
-            screen.display_info.width,
-            screen.display_info.height,
-        );
-        // Convert window position from f64 to i32
-        let window_rect = (
-            active_window.position.x as i32,
-            active_window.position.y as i32,
-            active_window.position.width as i32,
-            active_window.position.height as i32,
-        );
+impl Default for TimelapseConfig {
+    fn default() -> Self {
+        TimelapseConfig {
+            black_threshold: DEFAULT_BLACK_THRESHOLD,
+            black_sample_target: DEFAULT_BLACK_SAMPLE_TARGET,
+            black_backoff_secs: DEFAULT_BLACK_BACKOFF_SECS,
+            similarity_threshold: DEFAULT_SIMILARITY_THRESHOLD,
+            output_size: None,
+            aspect_ratio: None,
+            blocked_apps: Vec::new(),
+            dir_layout: DirLayout::default(),
+            output_format: OutputFormat::default(),
+            display_output_formats: BTreeMap::new(),
+            png_compression_level: DEFAULT_PNG_COMPRESSION_LEVEL,
+            filename_width: DEFAULT_FILENAME_WIDTH,
+            battery_interval_secs: None,
+            idle_pause_secs: None,
+            schedule: Vec::new(),
+            interval_schedule: Vec::new(),
+            auto_compile: false,
+            capture_cursor: false,
+            resize_filter: ResizeFilter::default(),
+            capture_mode: CaptureMode::default(),
+            capture_crop: EdgeCrop::default(),
+            capture_logical_resolution: false,
+            generate_thumbnails: false,
+            keep_originals: false,
+            record_change_scores: false,
+            profiling_enabled: false,
+            privacy_masks: Vec::new(),
+            timestamp_overlay: false,
+            timestamp_format: DEFAULT_TIMESTAMP_FORMAT.to_string(),
+            timestamp_corner: OverlayCorner::default(),
+            retention_days: 0,
+            max_error_logs: DEFAULT_MAX_ERROR_LOGS,
+            min_free_bytes: DEFAULT_MIN_FREE_BYTES,
+            database_enabled: true,
+        }
+    }
+}
 
-        // Check if the window is primarily on this screen
-        if window_overlaps_screen(window_rect, screen_rect) {
-            return Ok(screen);
+impl TimelapseConfig {
+    /// Check every value is in range, returning the first problem found
+    pub fn validate(&self) -> Result<(), Error> {
+        check_fraction("black threshold", self.black_threshold)?;
+        check_black_sample_target(self.black_sample_target)?;
+        check_black_backoff(self.black_backoff_secs)?;
+        check_fraction("similarity threshold", self.similarity_threshold)?;
+        check_output_size(self.output_size)?;
+        check_aspect_ratio(self.aspect_ratio)?;
+        check_output_format(&self.output_format)?;
+        for format in self.display_output_formats.values() {
+            check_output_format(format)?;
         }
+        check_png_compression_level(self.png_compression_level)?;
+        check_filename_width(self.filename_width)?;
+        check_battery_interval(self.battery_interval_secs)?;
+        check_idle_threshold(self.idle_pause_secs)?;
+        check_schedule(&self.schedule)?;
+        check_interval_schedule(&self.interval_schedule)?;
+        check_privacy_masks(&self.privacy_masks)?;
+        check_timestamp_format(&self.timestamp_format)?;
+        check_max_error_logs(self.max_error_logs)
     }
 
-    // Fallback to primary screen if no overlap found
-    Screen::from_point(0, 0).map_err(|err| Error::UnableToCreateScreenshot {
-        reason: err.to_string(),
-    })
-}
+    /// The format frames captured from display `display_id` are saved in
+    pub fn output_format_for(&self, display_id: u32) -> &OutputFormat {
+        self.display_output_formats
+            .get(&display_id)
+            .unwrap_or(&self.output_format)
+    }
 
-fn window_overlaps_screen(window: (i32, i32, i32, i32), screen: (i32, i32, u32, u32)) -> bool {
-    let (wx, wy, ww, wh) = window;
-    let (sx, sy, sw, sh) = screen;
+    /// Whether capture pauses after `idle_secs` without input
+    fn is_idle_paused(&self, idle_secs: f64) -> bool {
+        self.idle_pause_secs.is_some_and(|threshold_secs| idle_secs >= threshold_secs as f64)
+    }
 
-    // Check if window center is within screen bounds
-    let window_center_x = wx + ww / 2;
-    let window_center_y = wy + wh / 2;
+    /// Whether capture waits for space with `free_bytes` left on the Timelapse volume
+    fn is_low_on_space(&self, free_bytes: u64) -> bool {
+        free_bytes < self.min_free_bytes
+    }
 
-    window_center_x >= sx
-        && window_center_x < sx + sw as i32
-        && window_center_y >= sy
-        && window_center_y < sy + sh as i32
+    /// How long capture waits after an all-black frame
+    fn black_backoff(&self) -> Duration {
+        Duration::from_secs(self.black_backoff_secs)
+    }
 }
 
-async fn resize_screenshot(data: &[u8], file_path: &str) -> Result<(), Error> {
-    let wand = MagickWand::new();
+fn check_fraction(name: &str, value: f64) -> Result<(), Error> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(Error::InvalidSetting {
+            reason: format!("{} must be between 0.0 and 1.0, got {}", name, value),
+        });
+    }
+    Ok(())
+}
 
-    // Read the image
-    wand.read_image_blob(data)
-        .map_err(|e| Error::UnableToResizeScreenshot {
-            path: file_path.to_string(),
-            reason: format!("Failed to read image: {:?}", e),
-        })?;
+fn check_black_sample_target(target: usize) -> Result<(), Error> {
+    if target == 0 {
+        return Err(Error::InvalidSetting {
+            reason: "black sample target must be at least 1".to_string(),
+        });
+    }
+    Ok(())
+}
 
-    // Get original dimensions
-    let orig_width = wand.get_image_width() as f64;
-    let orig_height = wand.get_image_height() as f64;
+fn check_black_backoff(backoff_secs: u64) -> Result<(), Error> {
+    if !(1..=MAX_BLACK_BACKOFF_SECS).contains(&backoff_secs) {
+        return Err(Error::InvalidSetting {
+            reason: format!(
+                "black frame backoff must be between 1 and {} seconds, got {}",
+                MAX_BLACK_BACKOFF_SECS, backoff_secs
+            ),
+        });
+    }
+    Ok(())
+}
 
-    // Target dimensions
-    let target_width = 1800.0;
-    let target_height = 1124.0;
+fn check_output_size(size: Option<(u32, u32)>) -> Result<(), Error> {
+    if let Some((width, height)) = size {
+        let in_range = |dimension: u32| (1..=MAX_OUTPUT_DIMENSION).contains(&dimension);
+        if !in_range(width) || !in_range(height) {
+            return Err(Error::InvalidSetting {
+                reason: format!(
+                    "output size must be between 1 and {} pixels per side, got {}x{}",
+                    MAX_OUTPUT_DIMENSION, width, height
+                ),
+            });
+        }
+    }
+    Ok(())
+}
 
-    // Calculate scaling to fit within target dimensions while maintaining aspect ratio
-    let scale_x = target_width / orig_width;
-    let scale_y = target_height / orig_height;
-    let scale = scale_x.min(scale_y);
+fn check_aspect_ratio(ratio: Option<(u32, u32)>) -> Result<(), Error> {
+    if let Some((width, height)) = ratio {
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidSetting {
+                reason: format!("aspect ratio must be positive, got {}:{}", width, height),
+            });
+        }
+    }
+    Ok(())
+}
 
-    // Calculate new dimensions
-    let new_width = (orig_width * scale) as usize;
-    let new_height = (orig_height * scale) as usize;
+fn check_output_format(format: &OutputFormat) -> Result<(), Error> {
+    let (name, quality) = match format {
+        OutputFormat::Png => return Ok(()),
+        OutputFormat::WebP { quality, .. } => ("WebP", quality),
+        OutputFormat::Avif { quality } => ("AVIF", quality),
+    };
+    if *quality > 100 {
+        return Err(Error::InvalidSetting {
+            reason: format!("{} quality must be between 0 and 100, got {}", name, quality),
+        });
+    }
+    Ok(())
+}
+
+fn check_png_compression_level(level: u8) -> Result<(), Error> {
+    if level > MAX_PNG_COMPRESSION_LEVEL {
+        return Err(Error::InvalidSetting {
+            reason: format!(
+                "PNG compression level must be between 0 and {}, got {}",
+                MAX_PNG_COMPRESSION_LEVEL, level
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn check_filename_width(width: usize) -> Result<(), Error> {
+    if !(MIN_FILENAME_WIDTH..=MAX_FILENAME_WIDTH).contains(&width) {
+        return Err(Error::InvalidSetting {
+            reason: format!(
+                "filename width must be between {} and {} digits, got {}",
+                MIN_FILENAME_WIDTH, MAX_FILENAME_WIDTH, width
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Fails if the ImageMagick build lacks the delegate for the format
+fn check_output_format_supported(format: &OutputFormat) -> Result<(), Error> {
+    if !format.is_supported() {
+        return Err(Error::UnsupportedOutputFormat {
+            format: format.magick_format().to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn check_idle_threshold(threshold_secs: Option<u64>) -> Result<(), Error> {
+    if let Some(threshold_secs) = threshold_secs {
+        if !(1..=MAX_IDLE_THRESHOLD_SECS).contains(&threshold_secs) {
+            return Err(Error::InvalidSetting {
+                reason: format!(
+                    "idle threshold must be between 1 and {} seconds, got {}",
+                    MAX_IDLE_THRESHOLD_SECS, threshold_secs
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn check_privacy_masks(masks: &[PrivacyMask]) -> Result<(), Error> {
+    let is_empty = |mask: &&PrivacyMask| mask.rect.width == 0 || mask.rect.height == 0;
+    if let Some(mask) = masks.iter().find(is_empty) {
+        return Err(Error::InvalidSetting {
+            reason: format!(
+                "privacy masks must have a non-zero size, got {}x{}",
+                mask.rect.width, mask.rect.height
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn check_timestamp_format(format: &str) -> Result<(), Error> {
+    let is_invalid = format.trim().is_empty()
+        || chrono::format::StrftimeItems::new(format)
+            .any(|item| item == chrono::format::Item::Error);
+    if is_invalid {
+        return Err(Error::InvalidSetting {
+            reason: format!("invalid timestamp format '{}'", format),
+        });
+    }
+    Ok(())
+}
+
+fn check_max_error_logs(max_error_logs: usize) -> Result<(), Error> {
+    if max_error_logs == 0 {
+        return Err(Error::InvalidSetting {
+            reason: "error log size must be at least 1".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn check_battery_interval(interval_secs: Option<u64>) -> Result<(), Error> {
+    if let Some(interval_secs) = interval_secs {
+        if !(1..=MAX_BATTERY_INTERVAL_SECS).contains(&interval_secs) {
+            return Err(Error::InvalidSetting {
+                reason: format!(
+                    "battery interval must be between 1 and {} seconds, got {}",
+                    MAX_BATTERY_INTERVAL_SECS, interval_secs
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn check_schedule(schedule: &[(u8, u8)]) -> Result<(), Error> {
+    if let Some((start, end)) = schedule
+        .iter()
+        .find(|(start, end)| *start > 23 || *end > 23)
+    {
+        return Err(Error::InvalidSetting {
+            reason: format!(
+                "schedule hours must be between 0 and 23, got {}-{}",
+                start, end
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn check_interval_schedule(schedule: &[(u8, u8, u64)]) -> Result<(), Error> {
+    let hours: Vec<(u8, u8)> = schedule.iter().map(|&(start, end, _)| (start, end)).collect();
+    check_schedule(&hours)?;
+
+    if let Some((_, _, interval_secs)) = schedule
+        .iter()
+        .find(|(_, _, interval_secs)| !(1..=MAX_BATTERY_INTERVAL_SECS).contains(interval_secs))
+    {
+        return Err(Error::InvalidSetting {
+            reason: format!(
+                "scheduled interval must be between 1 and {} seconds, got {}",
+                MAX_BATTERY_INTERVAL_SECS, interval_secs
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// The Timelapse folder in the home directory, where captures, the database and
+/// the config are kept
+pub fn default_root_path() -> Result<PathBuf, Error> {
+    Ok(dirs::home_dir().ok_or(Error::UnableToFindHomeDir)?.join("Timelapse"))
+}
+
+/// Check that `config` can replace `current`. Output formats are only checked
+/// for support when they change, so a saved one the encoder lost isn't fatal.
+fn check_config_change(current: &TimelapseConfig, config: &TimelapseConfig) -> Result<(), Error> {
+    config.validate()?;
+    if config.output_format != current.output_format {
+        check_output_format_supported(&config.output_format)?;
+    }
+    for (display_id, format) in &config.display_output_formats {
+        if current.display_output_formats.get(display_id) != Some(format) {
+            check_output_format_supported(format)?;
+        }
+    }
+    Ok(())
+}
+
+/// Validate `config` and save it as the config the next photographer starts with,
+/// for changing settings while capture is stopped
+pub fn replace_saved_config(
+    timelapse_root_path: &Path,
+    config: &TimelapseConfig,
+) -> Result<(), Error> {
+    check_config_change(&load_config(timelapse_root_path), config)?;
+    std::fs::create_dir_all(timelapse_root_path)?;
+    save_config(timelapse_root_path, config)
+}
+
+/// Where the config is saved under the Timelapse root
+fn config_path(timelapse_root_path: &Path) -> PathBuf {
+    timelapse_root_path.join("config.json")
+}
+
+/// Read the saved config, falling back to the defaults if there isn't one or it
+/// can't be used. Settings missing from the file take their default values.
+pub fn load_config(timelapse_root_path: &Path) -> TimelapseConfig {
+    let path = config_path(timelapse_root_path);
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return TimelapseConfig::default(),
+        Err(e) => {
+            warn!(?path, error = %e, "Failed to read settings, using defaults");
+            return TimelapseConfig::default();
+        }
+    };
+
+    let config = serde_json::from_str::<TimelapseConfig>(&contents)
+        .map_err(Error::from)
+        .and_then(|config| config.validate().map(|_| config));
+    match config {
+        Ok(config) => config,
+        Err(e) => {
+            warn!(?path, error = %e, "Ignoring invalid settings, using defaults");
+            TimelapseConfig::default()
+        }
+    }
+}
+
+fn save_config(timelapse_root_path: &Path, config: &TimelapseConfig) -> Result<(), Error> {
+    let contents = serde_json::to_string_pretty(config)?;
+    write_file_atomically(&config_path(timelapse_root_path), contents.as_bytes())
+}
+
+/// Copy out the current config for one capture. A panic elsewhere while holding
+/// the lock doesn't reset it: falling back to the defaults would drop privacy
+/// masks and blocked apps and capture what they hide.
+fn config_snapshot(config: &Mutex<TimelapseConfig>) -> TimelapseConfig {
+    config.lock().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayStats {
+    pub frame_count: u32,
+    pub total_bytes: u64,
+    pub first_frame_time: Option<String>,
+    pub last_frame_time: Option<String>,
+}
+
+/// The span of everything ever captured, from the database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureBounds {
+    /// UTC time of the oldest frame, or `None` if nothing was captured
+    pub first_capture: Option<String>,
+    /// UTC time of the newest frame, or `None` if nothing was captured
+    pub last_capture: Option<String>,
+    pub frame_count: u32,
+}
+
+/// Space left on the Timelapse volume, as sent with `LOW_DISK_SPACE_EVENT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskSpaceWarning {
+    pub free_bytes: u64,
+    pub min_free_bytes: u64,
+}
+
+/// A connected display, as sent with `DISPLAYS_CHANGED_EVENT`. The display API
+/// doesn't report names, so displays are told apart by position and resolution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplaySummary {
+    pub id: u32,
+    /// Position of the top-left corner in global screen coordinates
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f32,
+    pub is_primary: bool,
+}
+
+/// What compiling a day's video is expected to produce
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoEstimate {
+    pub frame_count: usize,
+    pub duration_secs: f64,
+    /// A ballpark figure; the real size depends on how much the screen changed
+    pub estimated_bytes: u64,
+}
+
+/// The encoder and quality a day's video is compiled with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoEncoding {
+    codec: String,
+    crf: u8,
+}
+
+impl Default for VideoEncoding {
+    fn default() -> Self {
+        VideoEncoding {
+            codec: DEFAULT_VIDEO_CODEC.to_string(),
+            crf: DEFAULT_VIDEO_CRF,
+        }
+    }
+}
+
+impl VideoEncoding {
+    /// Check `codec` is one of `VIDEO_CODECS` and `crf` is in range, using the
+    /// defaults for whichever isn't given
+    pub fn new(codec: Option<String>, crf: Option<u8>) -> Result<Self, Error> {
+        let codec = codec.unwrap_or_else(|| DEFAULT_VIDEO_CODEC.to_string());
+        if !VIDEO_CODECS.contains(&codec.as_str()) {
+            return Err(Error::InvalidSetting {
+                reason: format!(
+                    "codec must be one of {}, got {}",
+                    VIDEO_CODECS.join(", "),
+                    codec
+                ),
+            });
+        }
+
+        let crf = crf.unwrap_or(DEFAULT_VIDEO_CRF);
+        if crf > MAX_VIDEO_CRF {
+            return Err(Error::InvalidSetting {
+                reason: format!("crf must be between 0 and {}, got {}", MAX_VIDEO_CRF, crf),
+            });
+        }
+
+        Ok(VideoEncoding { codec, crf })
+    }
+}
+
+/// A compiled video and the ffmpeg command that made it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompiledVideo {
+    pub video_path: PathBuf,
+    pub ffmpeg_command: String,
+}
+
+/// What `export_day_zip` wrote
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZipExportSummary {
+    /// Frames in the archive, not counting the manifest
+    pub file_count: usize,
+    /// Size of the finished archive
+    pub compressed_bytes: u64,
+}
+
+/// What `export_renumbered_sequence` wrote
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceExport {
+    pub frame_count: usize,
+    pub output_dir: PathBuf,
+}
+
+/// Frame counts bucketed by local day of week and hour of day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityHeatmap {
+    /// 7 rows of 24 hourly counts, row 0 being Sunday
+    pub counts: Vec<Vec<u32>>,
+    /// The largest single bucket, for scaling colors
+    pub max_count: u32,
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaySummary {
+    /// The day as `YYYY-MM-DD`
+    pub date: String,
+    pub frame_count: usize,
+    /// The label given with `label_day`, if any
+    pub label: Option<String>,
+}
+
+/// A label given to a capture day with `label_day`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayLabel {
+    /// The day as `YYYY-MM-DD`
+    pub date: String,
+    pub label: String,
+}
+
+/// How long one capture stage took across a day's profiled frames
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageTimingSummary {
+    pub stage: String,
+    pub frames: usize,
+    pub average_ms: f64,
+    pub p95_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletionSummary {
+    pub files_removed: usize,
+    pub rows_removed: usize,
+}
+
+/// Brightness measurements for a saved frame, for calibrating black detection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameAnalysis {
+    pub width: usize,
+    pub height: usize,
+    pub mean_brightness: f64,
+    /// Whether the current black threshold would discard this frame
+    pub is_black: bool,
+}
+
+/// Disk space used under the Timelapse root, in bytes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageBreakdown {
+    /// Frames in day directories
+    pub screenshots_bytes: u64,
+    /// Frames extracted from videos into `.cache`
+    pub cache_bytes: u64,
+    /// The screenshot database and its journals
+    pub database_bytes: u64,
+    /// Everything under the root except the app's logs, including compiled videos
+    /// and other files
+    pub total_bytes: u64,
+}
+
+/// A frame's encoded image, ready to use in a `data:` URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameImage {
+    pub data_base64: String,
+    pub mime_type: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScreenshotEvictionReport {
+    pub days_removed: usize,
+    pub files_removed: usize,
+    pub rows_removed: usize,
+}
+
+/// Payload of `DAY_COMPILED_EVENT`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayCompiled {
+    /// The day as `YYYY-MM-DD`
+    pub date: String,
+    pub video_path: String,
+}
+
+/// Size of `screenshots.db` around a vacuum
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseOptimizeReport {
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Frames of one day that are only in the database or only on disk
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    /// Frame numbers with a row but no file
+    pub orphaned_rows: Vec<u32>,
+    /// Frame files with no row
+    pub untracked_files: Vec<String>,
+    /// Whether the mismatches were repaired
+    pub fixed: bool,
+}
+
+/// Progress of a database rebuild, reported after each day directory is scanned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebuildProgress {
+    pub days_scanned: usize,
+    pub total_days: usize,
+    pub frames_found: usize,
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Unable to find home dir")]
+    UnableToFindHomeDir,
+
+    #[error("Unable to create screenshot because: {reason}")]
+    UnableToCreateScreenshot { reason: String },
+
+    #[error("Unable to resize screenshot {path} because: {reason}")]
+    UnableToResizeScreenshot { path: String, reason: String },
+
+    #[error("Unable convert screenshot path to string")]
+    UnableToConvertScreenshotPathToString,
+
+    #[error("Unable to check if image is black: {reason}")]
+    UnableToCheckIfImageIsBlack { reason: String },
+
+    #[error("Unable to compare frames: {reason}")]
+    UnableToCompareFrames { reason: String },
+
+    #[error("Invalid setting: {reason}")]
+    InvalidSetting { reason: String },
+
+    #[error("{format} output is not supported by this ImageMagick build (missing delegate)")]
+    UnsupportedOutputFormat { format: String },
+
+    #[error("Frame {frame_number} from {date} not found")]
+    FrameNotFound { date: NaiveDate, frame_number: u32 },
+
+    #[error("No thumbnail for frame {frame_number} from {date}")]
+    ThumbnailNotFound { date: NaiveDate, frame_number: u32 },
+
+    #[error("Path {path} is outside the Timelapse folder")]
+    PathOutsideTimelapseRoot { path: String },
+
+    #[error("No captures found for {date}")]
+    NoCapturesForDay { date: NaiveDate },
+
+    #[error("Unable to compile video: {reason}")]
+    VideoCompilationFailed { reason: String },
+
+    #[error(
+        "{black_frames} black frames in a row, screen recording permission may have been \
+         revoked. Check System Settings > Privacy & Security > Screen Recording."
+    )]
+    PermissionLikelyDenied { black_frames: u32 },
+
+    #[error("The database is disabled, so frame metadata isn't recorded")]
+    DatabaseDisabled,
+
+    #[error("Unable to write {path}: {reason}")]
+    OutputNotWritable { path: String, reason: String },
+
+    #[error("Unable to write archive: {0}")]
+    ArchiveError(#[from] zip::result::ZipError),
+
+    #[error("Invalid config: {0}")]
+    ConfigError(#[from] serde_json::Error),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] rusqlite::Error),
+
+    #[error("IO Error")]
+    IoError(#[from] std::io::Error),
+}
+
+pub struct Photographer {
+    timelapse_root_path: PathBuf,
+    running: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    error_logs: Arc<Mutex<ErrorLog>>,
+    db: Arc<Mutex<ScreenshotDatabase>>,
+    /// Records saved frames on its own connection, off the capture path. `None`
+    /// when the database is disabled, in which case `db` is only kept in memory.
+    writer: Option<ScreenshotWriter>,
+    config: Arc<Mutex<TimelapseConfig>>,
+    previous_signature: Arc<Mutex<Option<Vec<f64>>>>,
+    /// Scaled-down copy of the last saved frame, kept while change scoring is on
+    previous_change_frame: Arc<Mutex<Option<MagickWand>>>,
+    /// Updated after every capture loop iteration
+    health: Arc<Mutex<CaptureHealth>>,
+    /// Held for the duration of a capture so the loop and one-shot captures
+    /// never pick the same frame number
+    capture_lock: Arc<tokio::sync::Mutex<()>>,
+    /// The capture task spawned by `start`
+    task: Mutex<Option<JoinHandle<()>>>,
+    /// Used to push capture events to the frontend; `None` when running headless
+    app_handle: Option<AppHandle>,
+}
+
+impl Photographer {
+    pub fn new() -> Result<Photographer, Error> {
+        Self::with_root_path(default_root_path()?)
+    }
+
+    pub fn with_root_path(timelapse_root_path: PathBuf) -> Result<Photographer, Error> {
+        // Initialize MagickWand
+        init_magick_wand();
+
+        // Create the Timelapse directory if it doesn't exist
+        std::fs::create_dir_all(&timelapse_root_path)?;
+
+        let config = load_config(&timelapse_root_path);
+
+        // Initialize the database, leaving the file alone in files-only mode
+        let (db, writer) = if config.database_enabled {
+            let db_path = timelapse_root_path.join("screenshots.db");
+            let db = ScreenshotDatabase::new(db_path.clone())?;
+            let writer = ScreenshotWriter::spawn(ScreenshotDatabase::open_existing(&db_path)?);
+            (db, Some(writer))
+        } else {
+            (ScreenshotDatabase::in_memory()?, None)
+        };
+
+        // Report missing optional encoders up front rather than when they're selected
+        let webp = OutputFormat::WebP { lossless: true, quality: 100 };
+        if !webp.is_supported() {
+            warn!("{}", Error::UnsupportedOutputFormat { format: "WebP".to_string() });
+        }
+        let avif = OutputFormat::Avif { quality: 50 };
+        if !avif.is_supported() {
+            warn!("{}", Error::UnsupportedOutputFormat { format: "AVIF".to_string() });
+        }
+
+        Ok(Photographer {
+            timelapse_root_path,
+            running: Arc::new(AtomicBool::new(false)),
+            paused: Arc::new(AtomicBool::new(false)),
+            error_logs: Arc::new(Mutex::new(ErrorLog::new(config.max_error_logs))),
+            db: Arc::new(Mutex::new(db)),
+            writer,
+            config: Arc::new(Mutex::new(config)),
+            previous_signature: Arc::new(Mutex::new(None)),
+            previous_change_frame: Arc::new(Mutex::new(None)),
+            health: Arc::new(Mutex::new(CaptureHealth::default())),
+            capture_lock: Arc::new(tokio::sync::Mutex::new(())),
+            task: Mutex::new(None),
+            app_handle: None,
+        })
+    }
+
+    /// Emit capture events through `app_handle` once started
+    pub fn with_app_handle(mut self, app_handle: AppHandle) -> Self {
+        self.app_handle = Some(app_handle);
+        self
+    }
+
+    pub fn start(&self) -> Arc<AtomicBool> {
+        let running = Arc::clone(&self.running);
+        running.store(true, Ordering::SeqCst);
+
+        let timelapse_root_path = self.timelapse_root_path.clone();
+        let running_clone = Arc::clone(&running);
+        let paused_clone = Arc::clone(&self.paused);
+        let error_logs_clone = Arc::clone(&self.error_logs);
+        let db_clone = Arc::clone(&self.db);
+        let writer_clone = self.writer.clone();
+        let config_clone = Arc::clone(&self.config);
+        let previous_signature_clone = Arc::clone(&self.previous_signature);
+        let previous_change_frame_clone = Arc::clone(&self.previous_change_frame);
+        let health_clone = Arc::clone(&self.health);
+        let capture_lock_clone = Arc::clone(&self.capture_lock);
+        let app_handle = self.app_handle.clone();
+
+        // Frames interrupted mid-write by a previous run are never renamed into place
+        let today_dir = config_snapshot(&config_clone)
+            .dir_layout
+            .day_dir(&timelapse_root_path, Local::now().date_naive());
+        if let Err(e) = remove_partial_frames(&today_dir) {
+            warn!(day_dir = ?today_dir, error = %e, "Failed to clean up partial frames");
+        }
+
+        let task = tokio::spawn(async move {
+            info!("Starting timelapse background task");
+
+            let mut consecutive_failures: u32 = 0;
+            let mut current_day = Local::now().date_naive();
+            let mut idle_unsupported_logged = false;
+            let mut low_on_space = false;
+            let mut free_space_unsupported_logged = false;
+            let mut known_display_ids: Option<Vec<u32>> = None;
+            let mut missing_display_id: Option<u32> = None;
+            let mut last_display_poll: Option<Instant> = None;
+
+            while running_clone.load(Ordering::SeqCst) {
+                // Keep the task alive while paused, but don't capture anything
+                if paused_clone.load(Ordering::SeqCst) {
+                    sleep(Duration::from_millis(500)).await;
+                    continue;
+                }
+
+                // Intervals are measured from the start of each tick, so the time
+                // spent capturing doesn't stretch the gap between frames
+                let tick_started = Instant::now();
+                let settings = config_snapshot(&config_clone);
+
+                let today = Local::now().date_naive();
+                if let Some(finished_day) = day_to_compile(&mut current_day, today, &settings) {
+                    spawn_day_compile(
+                        timelapse_root_path.clone(),
+                        finished_day,
+                        Arc::clone(&error_logs_clone),
+                        Arc::clone(&db_clone),
+                        app_handle.clone(),
+                    );
+                }
+
+                // Outside the scheduled hours, check again at the next minute
+                let now = Local::now();
+                if !is_within_schedule(&settings.schedule, now.hour() as u8) {
+                    sleep_while_running(until_next_minute(now), &running_clone).await;
+                    continue;
+                }
+                let base_interval =
+                    scheduled_interval_secs(&settings.interval_schedule, now.hour() as u8);
+
+                // While the user is away the screen is static, so don't capture at all
+                if settings.idle_pause_secs.is_some() {
+                    match idle_seconds() {
+                        Some(idle) if settings.is_idle_paused(idle) => {
+                            if let Ok(mut health) = health_clone.lock() {
+                                health.consecutive_black_frames = 0;
+                            }
+                            let interval = Duration::from_secs(CAPTURE_INTERVAL_SECS);
+                            sleep_while_running(interval, &running_clone).await;
+                            continue;
+                        }
+                        Some(_) => {}
+                        None if !idle_unsupported_logged => {
+                            warn!("Idle time can't be read here, ignoring idle pause");
+                            idle_unsupported_logged = true;
+                        }
+                        None => {}
+                    }
+                }
+
+                // Every write would fail on a full disk, so wait for space to be freed
+                // instead of piling up errors
+                if settings.min_free_bytes > 0 {
+                    match fs2::available_space(&timelapse_root_path) {
+                        Ok(free_bytes) if settings.is_low_on_space(free_bytes) => {
+                            if !low_on_space {
+                                low_on_space = true;
+                                warn!(free_bytes, "Low on disk space, waiting to capture");
+                                let warning = DiskSpaceWarning {
+                                    free_bytes,
+                                    min_free_bytes: settings.min_free_bytes,
+                                };
+                                if let Some(app_handle) = &app_handle {
+                                    if let Err(e) = app_handle.emit(LOW_DISK_SPACE_EVENT, warning) {
+                                        warn!(
+                                            event = LOW_DISK_SPACE_EVENT,
+                                            error = %e,
+                                            "Failed to emit event"
+                                        );
+                                    }
+                                }
+                            }
+                            let recheck = Duration::from_secs(LOW_DISK_SPACE_RECHECK_SECS);
+                            sleep_while_running(recheck, &running_clone).await;
+                            continue;
+                        }
+                        Ok(_) if low_on_space => {
+                            info!("Disk space freed up, resuming capture");
+                            low_on_space = false;
+                        }
+                        Ok(_) => {}
+                        Err(e) if !free_space_unsupported_logged => {
+                            warn!(error = %e, "Free disk space can't be read, not checking it");
+                            free_space_unsupported_logged = true;
+                        }
+                        Err(_) => {}
+                    }
+                }
+
+                // Docking and undocking change the screens under the loop. Tell the
+                // frontend so it can explain the gap, and start afresh on the new setup.
+                // Enumerating screens isn't free, so it's only done every few seconds.
+                let display_poll = Duration::from_secs(DISPLAY_POLL_SECS);
+                let display_poll_due =
+                    last_display_poll.is_none_or(|polled| polled.elapsed() >= display_poll);
+                if display_poll_due {
+                    last_display_poll = Some(Instant::now());
+                }
+                if let Some(displays) = display_poll_due.then(list_displays).and_then(Result::ok) {
+                    let display_ids = sorted_display_ids(&displays);
+                    if displays_changed(known_display_ids.as_deref(), &display_ids) {
+                        info!(displays = displays.len(), "Displays changed");
+                        consecutive_failures = 0;
+                        if let Ok(mut previous) = previous_signature_clone.lock() {
+                            *previous = None;
+                        }
+                        if let Ok(mut previous) = previous_change_frame_clone.lock() {
+                            *previous = None;
+                        }
+                        if let Some(app_handle) = &app_handle {
+                            if let Err(e) = app_handle.emit(DISPLAYS_CHANGED_EVENT, &displays) {
+                                warn!(
+                                    event = DISPLAYS_CHANGED_EVENT,
+                                    error = %e,
+                                    "Failed to emit event"
+                                );
+                            }
+                        }
+                    }
+
+                    // Say once when the display chosen for capture goes away
+                    let missing = missing_fixed_display(settings.capture_mode, &display_ids);
+                    if let Some(display_id) = missing.filter(|_| missing_display_id != missing) {
+                        warn!(display_id, "Display to capture is missing, capturing the primary");
+                        if let Some(app_handle) = &app_handle {
+                            if let Err(e) = app_handle.emit(DISPLAY_MISSING_EVENT, display_id) {
+                                warn!(
+                                    event = DISPLAY_MISSING_EVENT,
+                                    error = %e,
+                                    "Failed to emit event"
+                                );
+                            }
+                        }
+                    }
+                    missing_display_id = missing;
+                    known_display_ids = Some(display_ids);
+                }
+
+                let result = {
+                    let _capture_guard = capture_lock_clone.lock().await;
+                    Self::do_screenshot(
+                        &timelapse_root_path,
+                        &db_clone,
+                        writer_clone.as_ref(),
+                        &settings,
+                        &previous_signature_clone,
+                        &previous_change_frame_clone,
+                    )
+                    .await
+                };
+
+                // Frames the writer couldn't record are reported like capture errors
+                let write_failures = writer_clone.iter().flat_map(|writer| writer.take_failures());
+                for failure in write_failures {
+                    let entry = log_error(&error_logs_clone, &db_clone, failure);
+                    if let Some(app_handle) = &app_handle {
+                        if let Err(e) = app_handle.emit(ERROR_EVENT, entry) {
+                            warn!(event = ERROR_EVENT, error = %e, "Failed to emit event");
+                        }
+                    }
+                }
+
+                if result.is_ok() {
+                    consecutive_failures = 0;
+                }
+                let away = is_user_away(idle_seconds());
+                let black_frames = health_clone.lock().ok().map(|mut health| {
+                    health.record(&result, Utc::now());
+                    if away {
+                        health.consecutive_black_frames = 0;
+                    }
+                    health.consecutive_black_frames
+                });
+
+                // Warn once per run rather than on every black frame after it. Only
+                // macOS hands back black frames when permission is missing.
+                if cfg!(target_os = "macos") && black_frames == Some(PERMISSION_BLACK_FRAME_RUN) {
+                    let error = Error::PermissionLikelyDenied {
+                        black_frames: PERMISSION_BLACK_FRAME_RUN,
+                    };
+                    warn!("{}", error);
+                    let entry = log_error(&error_logs_clone, &db_clone, error.to_string());
+                    if let Some(app_handle) = &app_handle {
+                        if let Err(e) = app_handle.emit(PERMISSION_WARNING_EVENT, entry) {
+                            warn!(
+                                event = PERMISSION_WARNING_EVENT,
+                                error = %e,
+                                "Failed to emit event"
+                            );
+                        }
+                    }
+                }
+
+                match result {
+                    Ok(ScreenshotOutcome::Black) => {
+                        // Image was all black and discarded, back off for a while
+                        sleep_while_running(settings.black_backoff(), &running_clone).await;
+                    }
+                    Ok(
+                        outcome @ (ScreenshotOutcome::Saved { .. }
+                        | ScreenshotOutcome::Duplicate
+                        | ScreenshotOutcome::Skipped),
+                    ) => {
+                        if let (ScreenshotOutcome::Saved { frame_number, .. }, Some(app_handle)) =
+                            (outcome, &app_handle)
+                        {
+                            if let Err(e) = app_handle.emit(FRAME_SAVED_EVENT, frame_number) {
+                                warn!(
+                                    event = FRAME_SAVED_EVENT,
+                                    error = %e,
+                                    "Failed to emit event"
+                                );
+                            }
+                        }
+
+                        // Normal screenshot, capture again the scheduled interval (or longer on
+                        // battery) after this tick started, or straight away if it overran
+                        let interval =
+                            capture_interval_secs(base_interval, settings.battery_interval_secs)
+                                .await;
+                        let remaining = remaining_interval(
+                            Duration::from_secs(interval),
+                            tick_started,
+                            Instant::now(),
+                        );
+                        if let Some(remaining) = remaining {
+                            sleep_while_running(remaining, &running_clone).await;
+                        }
+                    }
+                    Err(error) => {
+                        error!(error = %error, consecutive_failures, "Screenshot failed");
+                        let entry = log_error(&error_logs_clone, &db_clone, error.to_string());
+                        if let Some(app_handle) = &app_handle {
+                            if let Err(e) = app_handle.emit(ERROR_EVENT, entry) {
+                                warn!(event = ERROR_EVENT, error = %e, "Failed to emit event");
+                            }
+                        }
+
+                        // Back off gradually so brief glitches (e.g. displays being
+                        // reconfigured) don't leave a large gap
+                        consecutive_failures = consecutive_failures.saturating_add(1);
+                        sleep_while_running(error_backoff(consecutive_failures), &running_clone)
+                            .await;
+                    }
+                }
+            }
+
+            info!("Timelapse background task stopped");
+        });
+
+        if let Ok(mut task_guard) = self.task.lock() {
+            *task_guard = Some(task);
+        }
+
+        running
+    }
+
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+
+    /// Stop the capture loop and wait for it to exit. If it's still sleeping
+    /// between captures after `STOP_JOIN_TIMEOUT`, wait for any in-flight
+    /// capture to finish and then cancel the task. Returns once every frame
+    /// saved so far has been recorded in the database.
+    pub async fn stop_and_join(&self) {
+        self.stop();
+
+        let task = self.task.lock().ok().and_then(|mut task_guard| task_guard.take());
+        if let Some(mut task) = task {
+            if timeout(STOP_JOIN_TIMEOUT, &mut task).await.is_err() {
+                let _capture_guard = self.capture_lock.lock().await;
+                task.abort();
+            }
+        }
+
+        if let Some(writer) = &self.writer {
+            writer.flush().await;
+            for failure in writer.take_failures() {
+                log_error(&self.error_logs, &self.db, failure);
+            }
+        }
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Capture one frame through the same pipeline as the loop, whether or not
+    /// the loop is running or paused. The returned future doesn't borrow the
+    /// photographer, so callers can release any lock on it before awaiting.
+    pub fn capture_single_frame(
+        &self,
+    ) -> impl std::future::Future<Output = Result<ScreenshotOutcome, Error>> + Send + 'static {
+        let timelapse_root_path = self.timelapse_root_path.clone();
+        let db = Arc::clone(&self.db);
+        let writer = self.writer.clone();
+        let settings = self.get_config();
+        let previous_signature = Arc::clone(&self.previous_signature);
+        let previous_change_frame = Arc::clone(&self.previous_change_frame);
+        let capture_lock = Arc::clone(&self.capture_lock);
+
+        async move {
+            let _capture_guard = capture_lock.lock().await;
+            Self::do_screenshot(
+                &timelapse_root_path,
+                &db,
+                writer.as_ref(),
+                &settings,
+                &previous_signature,
+                &previous_change_frame,
+            )
+            .await
+        }
+    }
+
+    /// Capture and render a frame with the current settings, returning it encoded
+    /// in the output format without saving or recording it. `None` when the
+    /// settings would skip the frame, e.g. because a blocked app is in front.
+    pub fn preview_capture(
+        &self,
+    ) -> impl std::future::Future<Output = Result<Option<FrameImage>, Error>> + Send + 'static {
+        let settings = self.get_config();
+
+        async move {
+            let Some(capture) = capture_frame(&settings).await? else {
+                return Ok(None);
+            };
+            let output_format = settings.output_format_for(capture.display.id);
+            let mut canvas = render_capture(&capture, "preview", &settings)?;
+            let encoded = encode_frame(
+                &mut canvas,
+                "preview",
+                output_format,
+                settings.png_compression_level,
+            )?;
+
+            Ok(Some(FrameImage {
+                data_base64: base64::engine::general_purpose::STANDARD.encode(encoded),
+                mime_type: output_format.mime_type().to_string(),
+            }))
+        }
+    }
+
+    /// Repopulate the screenshots table from the frames on disk, using each file's
+    /// modification time as its capture time. The returned closure does the work
+    /// and blocks for a while, so callers should run it off the async runtime.
+    /// It holds the capture lock so frames saved mid-rebuild aren't dropped.
+    pub fn rebuild_database<F>(
+        &self,
+        mut on_progress: F,
+    ) -> impl FnOnce() -> Result<usize, Error> + Send + 'static
+    where
+        F: FnMut(RebuildProgress) + Send + 'static,
+    {
+        let timelapse_root_path = self.timelapse_root_path.clone();
+        let db = Arc::clone(&self.db);
+        let writer = self.writer.clone();
+        let capture_lock = Arc::clone(&self.capture_lock);
+
+        move || {
+            let writer = writer.ok_or(Error::DatabaseDisabled)?;
+            let _capture_guard = capture_lock.blocking_lock();
+            // Rows still queued would otherwise land on top of the rebuilt table
+            writer.blocking_flush();
+
+            let mut day_dirs = list_day_dirs(&timelapse_root_path);
+            day_dirs.sort();
+            let total_days = day_dirs.len();
+
+            let mut frames = Vec::new();
+            for (days_scanned, (_, day_dir)) in day_dirs.iter().enumerate() {
+                for entry in std::fs::read_dir(day_dir)? {
+                    let entry = entry?;
+                    let metadata = entry.metadata()?;
+                    if !metadata.is_file() {
+                        continue;
+                    }
+                    let Some(frame_number) = entry
+                        .file_name()
+                        .to_str()
+                        .and_then(frame_number_from_filename)
+                    else {
+                        continue;
+                    };
+                    let created_at: DateTime<Utc> = metadata.modified()?.into();
+                    frames.push((frame_number, created_at));
+                }
+
+                on_progress(RebuildProgress {
+                    days_scanned: days_scanned + 1,
+                    total_days,
+                    frames_found: frames.len(),
+                });
+            }
+
+            frames.sort_by_key(|&(frame_number, created_at)| (created_at, frame_number));
+            match db.lock() {
+                Ok(mut db_guard) => Ok(db_guard.replace_screenshots(&frames)?),
+                Err(_) => Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+            }
+        }
+    }
+
+    /// Cross-check `date`'s rows against the frames in its day directories. With
+    /// `fix`, orphaned rows are deleted and untracked files get rows dated by their
+    /// modification time. Like `rebuild_database`, the returned closure blocks and
+    /// holds the capture lock so a frame being saved isn't reported as untracked.
+    pub fn verify_integrity(
+        &self,
+        date: NaiveDate,
+        fix: bool,
+    ) -> impl FnOnce() -> Result<IntegrityReport, Error> + Send + 'static {
+        let timelapse_root_path = self.timelapse_root_path.clone();
+        let db = Arc::clone(&self.db);
+        let writer = self.writer.clone();
+        let capture_lock = Arc::clone(&self.capture_lock);
+
+        move || {
+            let writer = writer.ok_or(Error::DatabaseDisabled)?;
+            let _capture_guard = capture_lock.blocking_lock();
+            writer.blocking_flush();
+
+            let mut files = Vec::new();
+            for day_dir in existing_day_dirs(&timelapse_root_path, date) {
+                for entry in std::fs::read_dir(&day_dir)? {
+                    let entry = entry?;
+                    let metadata = entry.metadata()?;
+                    let filename = entry.file_name().to_string_lossy().to_string();
+                    if let Some(frame_number) = frame_number_from_filename(&filename) {
+                        if metadata.is_file() {
+                            files.push((frame_number, filename, metadata));
+                        }
+                    }
+                }
+            }
+            files.sort_by(|a, b| a.1.cmp(&b.1));
+
+            let (start, end) = local_day_bounds(date);
+            let mut db_guard = db
+                .lock()
+                .map_err(|_| Error::DatabaseError(rusqlite::Error::InvalidQuery))?;
+            let rows = db_guard.get_frame_numbers_between(start, end)?;
+
+            let orphaned_rows: Vec<u32> = rows
+                .iter()
+                .copied()
+                .filter(|frame_number| !files.iter().any(|(number, _, _)| number == frame_number))
+                .collect();
+            let untracked: Vec<_> = files
+                .into_iter()
+                .filter(|(frame_number, _, _)| rows.binary_search(frame_number).is_err())
+                .collect();
+
+            if fix {
+                db_guard.delete_frames_between(start, end, &orphaned_rows)?;
+                let recovered = untracked
+                    .iter()
+                    .map(|(frame_number, _, metadata)| {
+                        Ok((*frame_number, metadata.modified()?.into()))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                db_guard.insert_recovered_screenshots(&recovered)?;
+            }
+
+            Ok(IntegrityReport {
+                orphaned_rows,
+                untracked_files: untracked.into_iter().map(|(_, filename, _)| filename).collect(),
+                fixed: fix,
+            })
+        }
+    }
+
+    /// Vacuum and optimize the database. The returned closure blocks until SQLite
+    /// has rewritten the file, so callers should run it off the async runtime.
+    pub fn optimize_database(
+        &self,
+    ) -> impl FnOnce() -> Result<DatabaseOptimizeReport, Error> + Send + 'static {
+        let db_path = self.timelapse_root_path.join("screenshots.db");
+        let db = Arc::clone(&self.db);
+        let database_enabled = self.writer.is_some();
+
+        move || {
+            if !database_enabled {
+                return Err(Error::DatabaseDisabled);
+            }
+            let bytes_before = std::fs::metadata(&db_path)?.len();
+            match db.lock() {
+                Ok(db_guard) => db_guard.vacuum()?,
+                Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+            }
+            let bytes_after = std::fs::metadata(&db_path)?.len();
+
+            Ok(DatabaseOptimizeReport {
+                bytes_before,
+                bytes_after,
+            })
+        }
+    }
+
+    pub fn get_capture_health(&self) -> CaptureHealth {
+        self.health
+            .lock()
+            .map(|health| health.as_of(Utc::now()))
+            .unwrap_or_default()
+    }
+
+    pub fn get_error_logs(&self) -> Vec<ErrorLogEntry> {
+        // Prefer the persisted log so errors from previous sessions are included,
+        // falling back to the in-memory log if the database can't be read
+        let persisted = self
+            .db
+            .lock()
+            .ok()
+            .and_then(|db_guard| {
+                db_guard.get_recent_errors(self.get_config().max_error_logs).ok()
+            });
+
+        match persisted {
+            Some(rows) => rows.into_iter().filter_map(parse_error_row).collect(),
+            None => self
+                .error_logs
+                .lock()
+                .map(|logs| logs.entries.iter().cloned().collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The error log with runs of the same message collapsed into one entry each,
+    /// oldest first
+    pub fn get_error_logs_grouped(&self) -> Vec<ErrorLogGroup> {
+        group_error_logs(self.get_error_logs())
+    }
+
+    /// Get `limit` error log entries, newest first, skipping the `offset` most
+    /// recent ones
+    pub fn get_error_logs_paginated(&self, offset: usize, limit: usize) -> ErrorLogPage {
+        let persisted = self
+            .db
+            .lock()
+            .ok()
+            .and_then(|db_guard| db_guard.get_errors_page(offset, limit).ok());
+
+        if let Some((rows, total)) = persisted {
+            return ErrorLogPage {
+                entries: rows.into_iter().filter_map(parse_error_row).collect(),
+                total,
+            };
+        }
+
+        // Only clone the requested entries while the lock is held
+        self.error_logs
+            .lock()
+            .map(|logs| ErrorLogPage {
+                entries: logs.entries.iter().rev().skip(offset).take(limit).cloned().collect(),
+                total: logs.entries.len(),
+            })
+            .unwrap_or(ErrorLogPage {
+                entries: Vec::new(),
+                total: 0,
+            })
+    }
+
+    pub fn clear_error_logs(&self) {
+        if let Ok(mut logs) = self.error_logs.lock() {
+            logs.entries.clear();
+        }
+        if let Ok(db_guard) = self.db.lock() {
+            if let Err(e) = db_guard.clear_errors() {
+                warn!(error = %e, "Failed to clear persisted error logs");
+            }
+        }
+    }
+
+    /// Set how long to wait before capturing again after an all-black frame
+    pub fn set_black_backoff(&self, backoff_secs: u64) -> Result<(), Error> {
+        check_black_backoff(backoff_secs)?;
+
+        self.update_config(|config| config.black_backoff_secs = backoff_secs)
+    }
+
+    /// Set the free space below which capture waits for room on the Timelapse
+    /// volume, or 0 to never wait
+    pub fn set_min_free_space(&self, min_free_bytes: u64) -> Result<(), Error> {
+        self.update_config(|config| config.min_free_bytes = min_free_bytes)
+    }
+
+    /// Set the zlib level (0-9) PNG frames are written at
+    pub fn set_png_compression_level(&self, level: u8) -> Result<(), Error> {
+        check_png_compression_level(level)?;
+
+        self.update_config(|config| config.png_compression_level = level)
+    }
+
+    /// Set the mean brightness (0.0 to 1.0) below which frames are discarded as
+    /// all black. Values outside that range are clamped; 0.0 disables black-frame
+    /// deletion entirely. Returns the threshold that was applied.
+    pub fn set_black_threshold(&self, threshold: f64) -> Result<f64, Error> {
+        if threshold.is_nan() {
+            return Err(Error::InvalidSetting {
+                reason: "black threshold must be a number".to_string(),
+            });
+        }
+
+        let threshold = threshold.clamp(0.0, 1.0);
+        self.update_config(|config| config.black_threshold = threshold)?;
+        Ok(threshold)
+    }
+
+    /// Set roughly how many pixels are sampled when checking whether a frame is
+    /// black. Fewer samples are faster; more catch small bright regions.
+    pub fn set_black_sample_target(&self, target: usize) -> Result<(), Error> {
+        check_black_sample_target(target)?;
+
+        self.update_config(|config| config.black_sample_target = target)
+    }
+
+    /// Set the mean per-pixel difference (0.0 to 1.0) below which a frame is
+    /// discarded as a duplicate of the previously saved one. Values outside that
+    /// range are clamped; 0.0 disables duplicate detection. Returns the threshold
+    /// that was applied.
+    pub fn set_similarity_threshold(&self, threshold: f64) -> Result<f64, Error> {
+        if threshold.is_nan() {
+            return Err(Error::InvalidSetting {
+                reason: "similarity threshold must be a number".to_string(),
+            });
+        }
+
+        let threshold = threshold.clamp(0.0, 1.0);
+        self.update_config(|config| config.similarity_threshold = threshold)?;
+        Ok(threshold)
+    }
+
+    /// Set the size of the output canvas frames are letterboxed into, or `None`
+    /// to keep the focused screen's native resolution
+    pub fn set_output_size(&self, size: Option<(u32, u32)>) -> Result<(), Error> {
+        check_output_size(size)?;
+
+        self.update_config(|config| config.output_size = size)
+    }
+
+    /// Letterbox frames to a `(width, height)` ratio such as `(16, 9)`, or `None`
+    /// to go back to `output_size`
+    pub fn set_aspect_ratio(&self, ratio: Option<(u32, u32)>) -> Result<(), Error> {
+        check_aspect_ratio(ratio)?;
+
+        self.update_config(|config| config.aspect_ratio = ratio)
+    }
+
+    /// Set the apps (matched case-insensitively by name) that pause capture
+    /// while they are in the foreground
+    pub fn set_blocked_apps(&self, apps: Vec<String>) -> Result<(), Error> {
+        let apps = apps
+            .into_iter()
+            .map(|app| app.trim().to_string())
+            .filter(|app| !app.is_empty())
+            .collect();
+
+        self.update_config(|config| config.blocked_apps = apps)
+    }
+
+    /// Enable or disable lengthening the capture interval while running on battery
+    pub fn set_battery_saver(&self, enabled: bool, interval_secs: u64) -> Result<(), Error> {
+        let battery_interval_secs = enabled.then_some(interval_secs);
+        check_battery_interval(battery_interval_secs)?;
+
+        self.update_config(|config| config.battery_interval_secs = battery_interval_secs)
+    }
+
+    /// Enable or disable skipping captures once there's been no input for `threshold_secs`
+    pub fn set_idle_pause(&self, enabled: bool, threshold_secs: u64) -> Result<(), Error> {
+        let idle_pause_secs = enabled.then_some(threshold_secs);
+        check_idle_threshold(idle_pause_secs)?;
+
+        self.update_config(|config| config.idle_pause_secs = idle_pause_secs)
+    }
+
+    /// Enable or disable burning the capture time into frames, written with the
+    /// strftime `format` in `corner`
+    pub fn set_timestamp_overlay(
+        &self,
+        enabled: bool,
+        format: String,
+        corner: OverlayCorner,
+    ) -> Result<(), Error> {
+        check_timestamp_format(&format)?;
+
+        self.update_config(|config| {
+            config.timestamp_overlay = enabled;
+            config.timestamp_format = format;
+            config.timestamp_corner = corner;
+        })
+    }
+
+    /// Only capture during the given local `(start_hour, end_hour)` ranges. A range
+    /// includes its start hour but not its end hour, and wraps past midnight when
+    /// the end is before the start (e.g. `(22, 6)`).
+    pub fn set_schedule(&self, schedule: Vec<(u8, u8)>) -> Result<(), Error> {
+        check_schedule(&schedule)?;
+
+        self.update_config(|config| config.schedule = schedule)
+    }
+
+    /// Capture at `interval_secs` during the local `(start_hour, end_hour, interval_secs)`
+    /// ranges, e.g. every second during work hours and every minute in the evening.
+    /// Ranges wrap past midnight like `set_schedule`'s, the first match wins where
+    /// they overlap, and an empty schedule captures at the normal interval.
+    pub fn set_interval_schedule(&self, schedule: Vec<(u8, u8, u64)>) -> Result<(), Error> {
+        check_interval_schedule(&schedule)?;
+
+        self.update_config(|config| config.interval_schedule = schedule)
+    }
+
+    /// Draw a marker at the mouse pointer's position in captured frames
+    pub fn set_capture_cursor(&self, enabled: bool) -> Result<(), Error> {
+        self.update_config(|config| config.capture_cursor = enabled)
+    }
+
+    /// Capture at the screen's logical resolution rather than its native pixels
+    pub fn set_capture_logical_resolution(&self, enabled: bool) -> Result<(), Error> {
+        self.update_config(|config| config.capture_logical_resolution = enabled)
+    }
+
+    /// Generate a thumbnail of each frame as it's captured
+    pub fn set_generate_thumbnails(&self, enabled: bool) -> Result<(), Error> {
+        self.update_config(|config| config.generate_thumbnails = enabled)
+    }
+
+    /// Score how much each frame changes from the one before it, from the next frame on
+    pub fn set_record_change_scores(&self, enabled: bool) -> Result<(), Error> {
+        self.update_config(|config| config.record_change_scores = enabled)
+    }
+
+    /// Record how long each stage of every capture takes, from the next frame on
+    pub fn set_profiling_enabled(&self, enabled: bool) -> Result<(), Error> {
+        self.update_config(|config| config.profiling_enabled = enabled)
+    }
+
+    /// Zero-pad frame numbers in new frames' filenames to `width` digits, from the
+    /// next day on. A day that already has frames keeps their width, since names of
+    /// mixed widths don't sort in capture order.
+    pub fn set_filename_width(&self, width: usize) -> Result<(), Error> {
+        check_filename_width(width)?;
+
+        self.update_config(|config| config.filename_width = width)
+    }
+
+    /// Keep full-resolution captures of frames taken from now on
+    pub fn set_keep_originals(&self, enabled: bool) -> Result<(), Error> {
+        self.update_config(|config| config.keep_originals = enabled)
+    }
+
+    /// Turn recording frame metadata in the database on or off. Saved right away,
+    /// but only takes effect the next time the app starts.
+    pub fn set_database_enabled(&self, enabled: bool) -> Result<(), Error> {
+        self.update_config(|config| config.database_enabled = enabled)
+    }
+
+    /// Compile the previous day's video automatically when the date changes
+    pub fn set_auto_compile(&self, enabled: bool) -> Result<(), Error> {
+        self.update_config(|config| config.auto_compile = enabled)
+    }
+
+    /// Capture at all hours again
+    pub fn clear_schedule(&self) -> Result<(), Error> {
+        self.update_config(|config| config.schedule.clear())
+    }
+
+    /// Black out `masks` in captured frames. Each mask is in the coordinates of its
+    /// screen, and applies to its display only, or to whichever screen is being
+    /// captured when it has no display id.
+    pub fn set_privacy_masks(&self, masks: Vec<PrivacyMask>) -> Result<(), Error> {
+        check_privacy_masks(&masks)?;
+
+        self.update_config(|config| config.privacy_masks = masks)
+    }
+
+    /// Stop blacking out any part of captured frames
+    pub fn clear_privacy_masks(&self) -> Result<(), Error> {
+        self.update_config(|config| config.privacy_masks.clear())
+    }
+
+    /// Set the image format for frames captured from now on. Fails if the
+    /// ImageMagick build lacks the delegate for that format.
+    pub fn set_output_format(&self, format: OutputFormat) -> Result<(), Error> {
+        check_output_format(&format)?;
+        check_output_format_supported(&format)?;
+
+        self.update_config(|config| config.output_format = format)
+    }
+
+    /// Save frames captured from display `display_id`, as `list_displays` reports
+    /// it, in `format`, or in the global output format again when `format` is `None`
+    pub fn set_display_output_format(
+        &self,
+        display_id: u32,
+        format: Option<OutputFormat>,
+    ) -> Result<(), Error> {
+        if let Some(format) = &format {
+            check_output_format(format)?;
+            check_output_format_supported(format)?;
+        }
+
+        self.update_config(|config| match format {
+            Some(format) => {
+                config.display_output_formats.insert(display_id, format);
+            }
+            None => {
+                config.display_output_formats.remove(&display_id);
+            }
+        })
+    }
+
+    /// Set the filter used to scale captures to the output size
+    pub fn set_resize_filter(&self, filter: ResizeFilter) -> Result<(), Error> {
+        self.update_config(|config| config.resize_filter = filter)
+    }
+
+    /// Set whether frames show the whole focused screen or just the active window
+    pub fn set_capture_mode(&self, mode: CaptureMode) -> Result<(), Error> {
+        self.update_config(|config| config.capture_mode = mode)
+    }
+
+    /// Always capture the display with id `display_id`, as listed by `list_displays`,
+    /// or follow the foreground window's screen again when `None`. Fails if no
+    /// display with that id is connected.
+    pub fn set_capture_display(&self, display_id: Option<u32>) -> Result<(), Error> {
+        let mode = display_id.map_or(CaptureMode::FocusedScreen, CaptureMode::FixedDisplay);
+        if display_id.is_some() {
+            check_capture_display(mode, &sorted_display_ids(&list_displays()?))?;
+        }
+
+        self.update_config(|config| config.capture_mode = mode)
+    }
+
+    /// Trim the given edges off every captured screen, e.g. to hide the menu bar
+    pub fn set_capture_crop(&self, crop: EdgeCrop) -> Result<(), Error> {
+        self.update_config(|config| config.capture_crop = crop)
+    }
+
+    /// Set the directory layout used for days captured from now on
+    pub fn set_dir_layout(&self, layout: DirLayout) -> Result<(), Error> {
+        self.update_config(|config| config.dir_layout = layout)
+    }
+
+    /// The current value of every setting
+    pub fn get_config(&self) -> TimelapseConfig {
+        config_snapshot(&self.config)
+    }
+
+    /// Replace every setting at once and save them. Nothing changes unless the
+    /// whole config is valid.
+    pub fn set_config(&self, config: TimelapseConfig) -> Result<(), Error> {
+        check_config_change(&self.get_config(), &config)?;
+        save_config(&self.timelapse_root_path, &config)?;
+
+        if let Ok(mut logs) = self.error_logs.lock() {
+            logs.set_capacity(config.max_error_logs);
+        }
+        *self.config.lock().unwrap_or_else(PoisonError::into_inner) = config;
+        Ok(())
+    }
+
+    /// Change one setting and save the result. Nothing changes if saving fails.
+    /// The lock is held while saving so concurrent changes can't undo each other.
+    fn update_config(&self, update: impl FnOnce(&mut TimelapseConfig)) -> Result<(), Error> {
+        let mut config = self.config.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut updated = config.clone();
+        update(&mut updated);
+
+        if let Err(e) = save_config(&self.timelapse_root_path, &updated) {
+            error!(error = %e, "Failed to save settings");
+            return Err(e);
+        }
+        *config = updated;
+        Ok(())
+    }
+
+    /// Fails with `DatabaseDisabled` in files-only mode, where the in-memory
+    /// database never holds any frames
+    fn require_database(&self) -> Result<(), Error> {
+        if self.writer.is_none() {
+            return Err(Error::DatabaseDisabled);
+        }
+        Ok(())
+    }
+
+    pub fn get_screenshot_metadata(&self, frame_number: u32) -> Result<Option<(String, String)>, Error> {
+        self.require_database()?;
+        if let Ok(db_guard) = self.db.lock() {
+            Ok(db_guard.get_screenshot_by_frame(frame_number)?)
+        } else {
+            Err(Error::DatabaseError(rusqlite::Error::InvalidQuery))
+        }
+    }
+
+    pub fn get_frames_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(u32, String, String)>, Error> {
+        self.require_database()?;
+        if let Ok(db_guard) = self.db.lock() {
+            Ok(db_guard.get_screenshots_in_range(start, end)?)
+        } else {
+            Err(Error::DatabaseError(rusqlite::Error::InvalidQuery))
+        }
+    }
+
+    /// Timestamps of every frame captured on `date`, ordered by frame number
+    pub fn get_day_frames(&self, date: NaiveDate) -> Result<Vec<FrameMeta>, Error> {
+        self.require_database()?;
+        let (start, end) = local_day_bounds(date);
+        if let Ok(db_guard) = self.db.lock() {
+            Ok(db_guard.get_frame_metas_between(start, end)?)
+        } else {
+            Err(Error::DatabaseError(rusqlite::Error::InvalidQuery))
+        }
+    }
+
+    /// Frames captured between `start_hour` and `end_hour` local time on any day,
+    /// in the timezone each frame was captured in. The range wraps past midnight
+    /// like a schedule range.
+    pub fn get_frames_by_local_hour(
+        &self,
+        start_hour: u8,
+        end_hour: u8,
+    ) -> Result<Vec<(u32, String, String)>, Error> {
+        self.require_database()?;
+        check_schedule(&[(start_hour, end_hour)])?;
+        if let Ok(db_guard) = self.db.lock() {
+            Ok(db_guard.get_screenshots_by_local_hour(start_hour, end_hour)?)
+        } else {
+            Err(Error::DatabaseError(rusqlite::Error::InvalidQuery))
+        }
+    }
+
+    /// The frame captured on `date` closest to local time `time`, as its frame number
+    /// and local capture time, or `None` if the day has no frames. Times before the
+    /// first frame or after the last get that frame.
+    pub fn frame_at_time(
+        &self,
+        date: NaiveDate,
+        time: NaiveTime,
+    ) -> Result<Option<(u32, String)>, Error> {
+        self.require_database()?;
+        let (start, end) = local_day_bounds(date);
+        let target = local_to_utc(date.and_time(time));
+
+        let nearest = match self.db.lock() {
+            Ok(db_guard) => db_guard.get_nearest_screenshot(start, end, target)?,
+            Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+        };
+        Ok(nearest.map(|(frame_number, _, local_time)| (frame_number, local_time)))
+    }
+
+    /// Write every screenshot row to a CSV file at `output_path`, returning the
+    /// number of rows written
+    pub fn export_database_csv(&self, output_path: &Path) -> Result<usize, Error> {
+        self.require_database()?;
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(output_path)?);
+        writeln!(writer, "frame_number,created_at,local_time")?;
+
+        let count = match self.db.lock() {
+            Ok(db_guard) => db_guard.get_all_screenshots(|frame_number, created_at, local_time| {
+                writeln!(writer, "{},{},{}", frame_number, created_at, local_time)
+                    .map_err(Error::from)
+            })?,
+            Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+        };
+
+        writer.flush()?;
+        Ok(count)
+    }
+
+    /// Archive every frame captured on `date` into a ZIP at `output_path`, along with
+    /// a `manifest.json` of their database metadata. Frames are streamed from disk one
+    /// at a time, and a partly written archive is removed on failure. Fails with
+    /// `DatabaseDisabled` in files-only mode. The returned closure blocks while it
+    /// writes, so run it off the async runtime.
+    pub fn export_day_zip(
+        &self,
+        date: NaiveDate,
+        output_path: PathBuf,
+    ) -> impl FnOnce() -> Result<ZipExportSummary, Error> + Send + 'static {
+        let timelapse_root_path = self.timelapse_root_path.clone();
+        let db = Arc::clone(&self.db);
+        let writer = self.writer.clone();
+
+        move || {
+            let writer = writer.ok_or(Error::DatabaseDisabled)?;
+            let day_dirs = existing_day_dirs(&timelapse_root_path, date);
+            if day_dirs.is_empty() {
+                return Err(Error::NoCapturesForDay { date });
+            }
+
+            // Frames still queued for the database would be missing from the manifest
+            writer.blocking_flush();
+            let (start, end) = local_day_bounds(date);
+            let manifest = match db.lock() {
+                Ok(db_guard) => db_guard.get_screenshot_contexts_between(start, end)?,
+                Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+            };
+
+            let file = std::fs::File::create(&output_path).map_err(|e| {
+                Error::OutputNotWritable {
+                    path: output_path.display().to_string(),
+                    reason: e.to_string(),
+                }
+            })?;
+            let result = write_day_zip(file, &day_dirs, &manifest);
+            if result.is_err() {
+                let _ = std::fs::remove_file(&output_path);
+            }
+            let file_count = result?;
+
+            Ok(ZipExportSummary {
+                file_count,
+                compressed_bytes: std::fs::metadata(&output_path)?.len(),
+            })
+        }
+    }
+
+    /// Copy every frame captured on `date` into `output_dir` as a gap-free sequence
+    /// numbered from `0001` in capture order, for editors that expect contiguous
+    /// image sequences. A day that switched formats is exported as PNG throughout.
+    /// `output_dir` must be empty or not exist yet, so a longer earlier export can't
+    /// leave stray frames on the end. The returned closure blocks while it copies,
+    /// so run it off the async runtime.
+    pub fn export_renumbered_sequence(
+        &self,
+        date: NaiveDate,
+        output_dir: PathBuf,
+    ) -> impl FnOnce() -> Result<SequenceExport, Error> + Send + 'static {
+        let timelapse_root_path = self.timelapse_root_path.clone();
+
+        move || {
+            let frames = numbered_frames(&existing_day_dirs(&timelapse_root_path, date))?;
+            if frames.is_empty() {
+                return Err(Error::NoCapturesForDay { date });
+            }
+
+            let not_writable = |reason: String| Error::OutputNotWritable {
+                path: output_dir.display().to_string(),
+                reason,
+            };
+            std::fs::create_dir_all(&output_dir).map_err(|e| not_writable(e.to_string()))?;
+            if std::fs::read_dir(&output_dir)?.next().is_some() {
+                return Err(not_writable("directory is not empty".to_string()));
+            }
+
+            let written = copy_renumbered(&frames, &output_dir);
+            if written.is_err() {
+                for entry in std::fs::read_dir(&output_dir).into_iter().flatten().flatten() {
+                    let _ = std::fs::remove_file(entry.path());
+                }
+            }
+
+            Ok(SequenceExport {
+                frame_count: written?,
+                output_dir,
+            })
+        }
+    }
+
+    pub fn get_day_stats(&self, date: NaiveDate) -> Result<DayStats, Error> {
+        self.require_database()?;
+        let (start, end) = local_day_bounds(date);
+        let (frame_count, first_frame_time, last_frame_time) = match self.db.lock() {
+            Ok(db_guard) => db_guard.count_screenshots_between(start, end)?,
+            Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+        };
+
+        let mut total_bytes = 0;
+        for day_dir in existing_day_dirs(&self.timelapse_root_path, date) {
+            for entry in std::fs::read_dir(&day_dir)? {
+                let metadata = entry?.metadata()?;
+                if metadata.is_file() {
+                    total_bytes += metadata.len();
+                }
+            }
+        }
+
+        Ok(DayStats {
+            frame_count,
+            total_bytes,
+            first_frame_time,
+            last_frame_time,
+        })
+    }
+
+    /// When the first and latest frames were captured, and how many there are in all
+    pub fn get_capture_bounds(&self) -> Result<CaptureBounds, Error> {
+        self.require_database()?;
+        let (first_capture, last_capture, frame_count) = match self.db.lock() {
+            Ok(db_guard) => db_guard.get_capture_bounds()?,
+            Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+        };
+
+        Ok(CaptureBounds {
+            first_capture,
+            last_capture,
+            frame_count,
+        })
+    }
+
+    /// Frames captured between `start_date` and `end_date` inclusive while `app_name`
+    /// was in front, ignoring case. Empty for apps that were never captured.
+    pub fn get_frames_by_app(
+        &self,
+        app_name: &str,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> Result<Vec<FrameMeta>, Error> {
+        self.require_database()?;
+        if start_date > end_date {
+            return Err(Error::InvalidSetting {
+                reason: format!("start date {} is after end date {}", start_date, end_date),
+            });
+        }
+        let (start, _) = local_day_bounds(start_date);
+        let (_, end) = local_day_bounds(end_date);
+        match self.db.lock() {
+            Ok(db_guard) => Ok(db_guard.get_frames_by_app(app_name.trim(), start, end)?),
+            Err(_) => Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+        }
+    }
+
+    /// How much each frame of local day `date` changed from the one before it, for
+    /// frames captured while change scoring was on
+    pub fn get_change_scores(&self, date: NaiveDate) -> Result<Vec<ChangeScore>, Error> {
+        self.require_database()?;
+        let (start, end) = local_day_bounds(date);
+        match self.db.lock() {
+            Ok(db_guard) => Ok(db_guard.get_change_scores(start, end)?),
+            Err(_) => Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+        }
+    }
+
+    /// Frame numbers of local day `date` that changed from the frame before them by
+    /// more than `min_change`, in capture order. Only frames captured while change
+    /// scoring was on can qualify.
+    pub fn get_keyframes(&self, date: NaiveDate, min_change: f64) -> Result<Vec<u32>, Error> {
+        self.require_database()?;
+        keyframes(&self.db, date, min_change)
+    }
+
+    /// Compile the keyframes `get_keyframes` picks for `date` into
+    /// `<date>_keyframes.mov` in the Timelapse root, a condensed video that skips
+    /// quiet stretches. The returned closure blocks while ffmpeg runs, so callers
+    /// should run it off the async runtime.
+    pub fn compile_keyframes_video(
+        &self,
+        date: NaiveDate,
+        min_change: f64,
+        fps: u32,
+        encoding: VideoEncoding,
+    ) -> impl FnOnce() -> Result<CompiledVideo, Error> + Send + 'static {
+        let timelapse_root_path = self.timelapse_root_path.clone();
+        let db = Arc::clone(&self.db);
+        let database_enabled = self.writer.is_some();
+
+        move || {
+            if !database_enabled {
+                return Err(Error::DatabaseDisabled);
+            }
+            let keyframe_numbers = keyframes(&db, date, min_change)?;
+            let mut frames: std::collections::HashMap<u32, PathBuf> =
+                numbered_frames(&existing_day_dirs(&timelapse_root_path, date))?
+                    .into_iter()
+                    .collect();
+            let frame_paths: Vec<PathBuf> = keyframe_numbers
+                .into_iter()
+                .filter_map(|frame_number| frames.remove(&frame_number))
+                .collect();
+            if frame_paths.is_empty() {
+                return Err(Error::VideoCompilationFailed {
+                    reason: format!(
+                        "no frames on {} changed by more than {}",
+                        date.format("%Y-%m-%d"),
+                        min_change
+                    ),
+                });
+            }
+
+            let day_name = date.format("%Y-%m-%d").to_string();
+            let cache_dir = timelapse_root_path.join(".cache");
+            std::fs::create_dir_all(&cache_dir)?;
+            compile_frames_video(
+                &frame_paths,
+                &cache_dir.join(format!("{}_keyframes.txt", day_name)),
+                &timelapse_root_path.join(format!("{}_keyframes.mov", day_name)),
+                fps,
+                &encoding,
+            )
+        }
+    }
+
+    /// Average and 95th percentile duration of each capture stage on local day `date`,
+    /// over the frames captured while profiling was on
+    pub fn get_timing_summary(&self, date: NaiveDate) -> Result<Vec<StageTimingSummary>, Error> {
+        self.require_database()?;
+        let timings = match self.db.lock() {
+            Ok(db_guard) => db_guard.get_frame_timings(date)?,
+            Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+        };
+        Ok(summarize_stage_timings(timings))
+    }
+
+    /// Frame counts captured between `start_date` and `end_date` inclusive, bucketed by
+    /// the local weekday and hour they were taken in
+    pub fn get_activity_heatmap(
+        &self,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> Result<ActivityHeatmap, Error> {
+        self.require_database()?;
+        if start_date > end_date {
+            return Err(Error::InvalidSetting {
+                reason: format!("start date {} is after end date {}", start_date, end_date),
+            });
+        }
+        let (start, _) = local_day_bounds(start_date);
+        let (_, end) = local_day_bounds(end_date);
+        let buckets = match self.db.lock() {
+            Ok(db_guard) => db_guard.count_screenshots_by_weekday_hour(start, end)?,
+            Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+        };
+
+        let mut counts = vec![vec![0; 24]; 7];
+        for (weekday, hour, count) in buckets {
+            if let Some(bucket) = counts
+                .get_mut(weekday as usize)
+                .and_then(|row| row.get_mut(hour as usize))
+            {
+                *bucket += count;
+            }
+        }
+        let max_count = counts.iter().flatten().copied().max().unwrap_or(0);
+        let total = counts.iter().flatten().sum();
+        Ok(ActivityHeatmap {
+            counts,
+            max_count,
+            total,
+        })
+    }
+
+    /// Every day with captured frames, most recent first
+    pub fn list_capture_days(&self) -> Result<Vec<DaySummary>, Error> {
+        // A day can have directories in both layouts if the layout changed mid-day
+        let mut frame_counts = std::collections::BTreeMap::new();
+        for (date, day_dir) in list_day_dirs(&self.timelapse_root_path) {
+            *frame_counts.entry(date).or_insert(0) += count_files(&day_dir)?;
+        }
+
+        let labels = if self.writer.is_some() { self.get_day_labels()? } else { Vec::new() };
+        let mut labels: std::collections::HashMap<String, String> = labels
+            .into_iter()
+            .map(|day_label| (day_label.date, day_label.label))
+            .collect();
+
+        Ok(frame_counts
+            .into_iter()
+            .rev()
+            .map(|(date, frame_count)| {
+                let date = date.format("%Y-%m-%d").to_string();
+                let label = labels.remove(&date);
+                DaySummary {
+                    date,
+                    frame_count,
+                    label,
+                }
+            })
+            .collect())
+    }
+
+    /// Give `date` a label such as a project name, or remove its label when
+    /// `label` is blank. The label only lives in the database, so the day's
+    /// directory and frame paths are unchanged.
+    pub fn label_day(&self, date: NaiveDate, label: &str) -> Result<(), Error> {
+        self.require_database()?;
+        let label = label.trim();
+        let label = (!label.is_empty()).then_some(label);
+
+        match self.db.lock() {
+            Ok(db_guard) => Ok(db_guard.set_day_label(date, label)?),
+            Err(_) => Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+        }
+    }
+
+    /// Every day label, ordered by date
+    pub fn get_day_labels(&self) -> Result<Vec<DayLabel>, Error> {
+        self.require_database()?;
+        let labels = match self.db.lock() {
+            Ok(db_guard) => db_guard.get_day_labels()?,
+            Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+        };
+
+        Ok(labels
+            .into_iter()
+            .map(|(date, label)| DayLabel { date, label })
+            .collect())
+    }
+
+    /// Read a captured frame and return it base64-encoded with its mime type
+    pub fn get_frame_image(&self, date: NaiveDate, frame_number: u32) -> Result<FrameImage, Error> {
+        let frame_path = find_frame(&self.timelapse_root_path, date, frame_number)
+            .ok_or(Error::FrameNotFound { date, frame_number })?;
+
+        // Resolve symlinks before checking, so a link in a day directory can't
+        // be used to read arbitrary files
+        let root = self.timelapse_root_path.canonicalize()?;
+        let resolved = frame_path.canonicalize()?;
+        if !resolved.starts_with(&root) {
+            return Err(Error::PathOutsideTimelapseRoot {
+                path: resolved.to_string_lossy().to_string(),
+            });
+        }
+
+        let data = std::fs::read(&resolved)?;
+        Ok(FrameImage {
+            data_base64: base64::engine::general_purpose::STANDARD.encode(data),
+            mime_type: mime_type_for(&resolved).to_string(),
+        })
+    }
+
+    /// A frame's stored thumbnail, base64-encoded with its mime type
+    pub fn get_thumbnail(&self, date: NaiveDate, frame_number: u32) -> Result<FrameImage, Error> {
+        self.require_database()?;
+        let data = match self.db.lock() {
+            Ok(db_guard) => db_guard.get_thumbnail(date, frame_number)?,
+            Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+        };
+        let data = data.ok_or(Error::ThumbnailNotFound { date, frame_number })?;
+
+        Ok(FrameImage {
+            data_base64: base64::engine::general_purpose::STANDARD.encode(data),
+            mime_type: "image/jpeg".to_string(),
+        })
+    }
+
+    /// Generate thumbnails for every frame on disk that doesn't have one yet,
+    /// returning how many were added. Frames that can't be read are skipped. The
+    /// returned closure blocks while it decodes frames, so run it off the async runtime.
+    pub fn backfill_thumbnails(&self) -> impl FnOnce() -> Result<usize, Error> + Send + 'static {
+        let timelapse_root_path = self.timelapse_root_path.clone();
+        let db = Arc::clone(&self.db);
+        let database_enabled = self.writer.is_some();
+
+        move || {
+            if !database_enabled {
+                return Err(Error::DatabaseDisabled);
+            }
+            let mut added = 0;
+            for (date, day_dir) in list_day_dirs(&timelapse_root_path) {
+                let existing = match db.lock() {
+                    Ok(db_guard) => db_guard.get_thumbnail_frame_numbers(date)?,
+                    Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+                };
+
+                for entry in std::fs::read_dir(&day_dir)? {
+                    let path = entry?.path();
+                    let Some(frame_number) = path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .and_then(frame_number_from_filename)
+                    else {
+                        continue;
+                    };
+                    if !path.is_file() || existing.binary_search(&frame_number).is_ok() {
+                        continue;
+                    }
+
+                    let Some(frame_path) = path.to_str() else {
+                        continue;
+                    };
+                    let wand = MagickWand::new();
+                    let thumbnail = wand
+                        .read_image(frame_path)
+                        .map_err(|e| Error::UnableToResizeScreenshot {
+                            path: frame_path.to_string(),
+                            reason: format!("Failed to read image: {:?}", e),
+                        })
+                        .and_then(|_| make_thumbnail(&wand, frame_path));
+                    match thumbnail {
+                        Ok(thumbnail) => {
+                            let db_guard = db
+                                .lock()
+                                .map_err(|_| Error::DatabaseError(rusqlite::Error::InvalidQuery))?;
+                            db_guard.insert_thumbnail(date, frame_number, &thumbnail)?;
+                            added += 1;
+                        }
+                        Err(e) => warn!(frame_number, error = %e, "Skipping thumbnail"),
+                    }
+                }
+            }
+            Ok(added)
+        }
+    }
+
+    /// Render frame `frame_number` of `date` again from its kept original with the
+    /// current settings, replacing the frame and its thumbnail. Returns `false`
+    /// and leaves the frame alone when no original was kept. Window crops and the
+    /// pointer aren't part of the original, so the frame shows the whole screen
+    /// without a cursor. The returned closure blocks while rendering, so callers
+    /// should run it off the async runtime.
+    pub fn reprocess_frame(
+        &self,
+        date: NaiveDate,
+        frame_number: u32,
+    ) -> impl FnOnce() -> Result<bool, Error> + Send + 'static {
+        let timelapse_root_path = self.timelapse_root_path.clone();
+        let db = Arc::clone(&self.db);
+        let database_enabled = self.writer.is_some();
+        let settings = self.get_config();
+
+        move || {
+            let original = original_path(&timelapse_root_path, date, frame_number);
+            if !original.is_file() {
+                return Ok(false);
+            }
+            let frame_path = find_frame(&timelapse_root_path, date, frame_number)
+                .ok_or(Error::FrameNotFound { date, frame_number })?;
+            let image_data = std::fs::read(&original)?;
+
+            // When and from which screen it was captured, from the frame's record
+            // if there is one, or else the frame file and the original itself
+            let context = if database_enabled {
+                let (start, end) = local_day_bounds(date);
+                match db.lock() {
+                    Ok(db_guard) => db_guard
+                        .get_screenshot_contexts_between(start, end)?
+                        .into_iter()
+                        .find(|context| context.frame_number == frame_number),
+                    Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+                }
+            } else {
+                None
+            };
+            let local_time = context
+                .as_ref()
+                .and_then(|context| DateTime::parse_from_rfc3339(&context.local_time).ok());
+            let captured_at = match local_time {
+                Some(local_time) => local_time.with_timezone(&Local),
+                None => DateTime::from(std::fs::metadata(&frame_path)?.modified()?),
+            };
+            let display = match context.and_then(|context| context.display) {
+                Some(display) => display,
+                None => {
+                    let (width, height) = image_size(&image_data, &original.to_string_lossy())?;
+                    FrameDisplay { id: 0, width, height, scale_factor: 1.0 }
+                }
+            };
+
+            let region = settings.capture_crop.apply(display.width, display.height);
+            let screenshot = CapturedScreenshot {
+                image_data,
+                app_name: String::new(),
+                window_title: String::new(),
+            };
+            let capture =
+                FrameCapture::new(screenshot, captured_at, display, region, None, &settings);
+
+            // The output format may have changed since, taking the extension with it
+            let output_format = settings.output_format_for(display.id);
+            let output_path = frame_path.with_extension(output_format.extension());
+            let output_name = output_path.to_string_lossy().to_string();
+            let mut canvas = render_capture(&capture, &output_name, &settings)?;
+            let encoded = encode_frame(
+                &mut canvas,
+                &output_name,
+                output_format,
+                settings.png_compression_level,
+            )?;
+            write_file_atomically(&output_path, &encoded)?;
+            if output_path != frame_path {
+                std::fs::remove_file(&frame_path)?;
+            }
+
+            if database_enabled && settings.generate_thumbnails {
+                let thumbnail = make_thumbnail(&canvas, &output_name)?;
+                if let Ok(db_guard) = db.lock() {
+                    db_guard.insert_thumbnail(date, frame_number, &thumbnail)?;
+                }
+            }
+            Ok(true)
+        }
+    }
+
+    /// Measure a saved frame the same way black detection does
+    pub fn analyze_frame(
+        &self,
+        date: NaiveDate,
+        frame_number: u32,
+    ) -> Result<FrameAnalysis, Error> {
+        let frame_path = find_frame(&self.timelapse_root_path, date, frame_number)
+            .ok_or(Error::FrameNotFound { date, frame_number })?;
+        let frame_path = frame_path
+            .to_str()
+            .ok_or(Error::UnableToConvertScreenshotPathToString)?;
+
+        let settings = self.get_config();
+        let wand = MagickWand::new();
+        wand.read_image(frame_path)
+            .map_err(|e| Error::UnableToCheckIfImageIsBlack {
+                reason: format!("Failed to read image: {:?}", e),
+            })?;
+        let brightness = mean_brightness(&wand, settings.black_sample_target)?;
+
+        Ok(FrameAnalysis {
+            width: wand.get_image_width(),
+            height: wand.get_image_height(),
+            mean_brightness: brightness,
+            is_black: brightness < settings.black_threshold,
+        })
+    }
+
+    /// Sum the size of everything under the Timelapse root, broken down by kind.
+    /// The app's logs aren't captures, so they're left out.
+    pub fn get_total_storage(&self) -> Result<StorageBreakdown, Error> {
+        let mut breakdown = StorageBreakdown::default();
+
+        for entry in std::fs::read_dir(&self.timelapse_root_path)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == LOG_DIR_NAME {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            let size = if metadata.is_dir() {
+                dir_size(&entry.path())?
+            } else {
+                metadata.len()
+            };
+
+            if name == ".cache" {
+                breakdown.cache_bytes += size;
+            } else if name.starts_with("screenshots.db") {
+                breakdown.database_bytes += size;
+            } else if metadata.is_dir() {
+                breakdown.screenshots_bytes += size;
+            }
+            breakdown.total_bytes += size;
+        }
+
+        Ok(breakdown)
+    }
+
+    pub fn get_screenshot_context(
+        &self,
+        frame_number: u32,
+    ) -> Result<Option<ScreenshotContext>, Error> {
+        self.require_database()?;
+        if let Ok(db_guard) = self.db.lock() {
+            Ok(db_guard.get_screenshot_context_by_frame(frame_number)?)
+        } else {
+            Err(Error::DatabaseError(rusqlite::Error::InvalidQuery))
+        }
+    }
+
+    /// Remove everything captured on `date`: the day's frames, their database
+    /// rows, and any frames extracted from that day's video into the cache. Like
+    /// `purge_frames_between`, the returned closure blocks and holds the capture lock.
+    pub fn delete_day(
+        &self,
+        date: NaiveDate,
+    ) -> impl FnOnce() -> Result<DeletionSummary, Error> + Send + 'static {
+        let timelapse_root_path = self.timelapse_root_path.clone();
+        let db = Arc::clone(&self.db);
+        let writer = self.writer.clone();
+        let capture_lock = Arc::clone(&self.capture_lock);
+
+        move || {
+            let _capture_guard = capture_lock.blocking_lock();
+            if let Some(writer) = &writer {
+                writer.blocking_flush();
+            }
+            remove_day(&timelapse_root_path, &db, date)
+        }
+    }
+
+    /// Remove the frames captured on `date` between `start_hour` and `end_hour` local
+    /// time: their files, database rows and thumbnails. The range wraps past midnight
+    /// like a schedule range, so 22-6 removes the start and the end of the day. Like
+    /// `verify_integrity`, the returned closure blocks and holds the capture lock so
+    /// a frame being saved can't get the number of one being removed.
+    pub fn purge_frames_between(
+        &self,
+        date: NaiveDate,
+        start_hour: u8,
+        end_hour: u8,
+    ) -> impl FnOnce() -> Result<DeletionSummary, Error> + Send + 'static {
+        let timelapse_root_path = self.timelapse_root_path.clone();
+        let db = Arc::clone(&self.db);
+        let writer = self.writer.clone();
+        let capture_lock = Arc::clone(&self.capture_lock);
+
+        move || {
+            let writer = writer.ok_or(Error::DatabaseDisabled)?;
+            check_schedule(&[(start_hour, end_hour)])?;
+            let (start, end) = local_day_bounds(date);
+
+            let _capture_guard = capture_lock.blocking_lock();
+            writer.blocking_flush();
+            let mut db_guard = db
+                .lock()
+                .map_err(|_| Error::DatabaseError(rusqlite::Error::InvalidQuery))?;
+            let frame_numbers =
+                db_guard.get_frame_numbers_by_local_hour(start, end, start_hour, end_hour)?;
+            let mut frames: std::collections::HashMap<u32, PathBuf> =
+                numbered_frames(&existing_day_dirs(&timelapse_root_path, date))?
+                    .into_iter()
+                    .collect();
+
+            let mut files_removed = 0;
+            for &frame_number in &frame_numbers {
+                if let Some(path) = frames.remove(&frame_number) {
+                    std::fs::remove_file(path)?;
+                    files_removed += 1;
+                }
+                let original = original_path(&timelapse_root_path, date, frame_number);
+                if original.is_file() {
+                    std::fs::remove_file(original)?;
+                    files_removed += 1;
+                }
+                db_guard.delete_thumbnail(date, frame_number)?;
+            }
+            let rows_removed = db_guard.delete_frames_between(start, end, &frame_numbers)?;
+
+            info!(
+                %date,
+                start_hour,
+                end_hour,
+                files_removed,
+                rows_removed,
+                "Purged frames"
+            );
+
+            Ok(DeletionSummary {
+                files_removed,
+                rows_removed,
+            })
+        }
+    }
+
+    /// Remove frame `frame_number` of `date`: its file, original, thumbnail and
+    /// database row. The returned closure says whether there was anything to remove.
+    pub fn delete_frame(
+        &self,
+        date: NaiveDate,
+        frame_number: u32,
+    ) -> impl FnOnce() -> Result<bool, Error> + Send + 'static {
+        let delete = self.delete_frames(date, vec![frame_number]);
+        move || Ok(delete()? > 0)
+    }
+
+    /// Remove the given frames of `date` like `delete_frame`, returning how many
+    /// were actually removed. Frames that are already gone are skipped. Like
+    /// `purge_frames_between`, the returned closure blocks and holds the capture lock.
+    pub fn delete_frames(
+        &self,
+        date: NaiveDate,
+        frame_numbers: Vec<u32>,
+    ) -> impl FnOnce() -> Result<usize, Error> + Send + 'static {
+        let timelapse_root_path = self.timelapse_root_path.clone();
+        let db = Arc::clone(&self.db);
+        let writer = self.writer.clone();
+        let capture_lock = Arc::clone(&self.capture_lock);
+
+        move || {
+            let mut frame_numbers = frame_numbers;
+            frame_numbers.sort_unstable();
+            frame_numbers.dedup();
+            let (start, end) = local_day_bounds(date);
+
+            let _capture_guard = capture_lock.blocking_lock();
+            if let Some(writer) = &writer {
+                writer.blocking_flush();
+            }
+            let mut db_guard = db
+                .lock()
+                .map_err(|_| Error::DatabaseError(rusqlite::Error::InvalidQuery))?;
+            let mut frames: std::collections::HashMap<u32, PathBuf> =
+                numbered_frames(&existing_day_dirs(&timelapse_root_path, date))?
+                    .into_iter()
+                    .collect();
+            let mut frames_removed = 0;
+            for frame_number in frame_numbers {
+                let mut removed = false;
+                if let Some(path) = frames.remove(&frame_number) {
+                    removed = remove_entry(&path)?;
+                }
+                remove_entry(&original_path(&timelapse_root_path, date, frame_number))?;
+                db_guard.delete_thumbnail(date, frame_number)?;
+                if db_guard.delete_frames_between(start, end, &[frame_number])? > 0 {
+                    removed = true;
+                }
+                if removed {
+                    frames_removed += 1;
+                }
+            }
+
+            info!(%date, frames_removed, "Deleted frames");
+            Ok(frames_removed)
+        }
+    }
+
+    /// Set how many error log entries to keep in memory, dropping the oldest
+    /// ones if there are already more
+    pub fn set_max_error_logs(&self, max_error_logs: usize) -> Result<(), Error> {
+        check_max_error_logs(max_error_logs)?;
+        self.update_config(|config| config.max_error_logs = max_error_logs)?;
+        if let Ok(mut logs) = self.error_logs.lock() {
+            logs.set_capacity(max_error_logs);
+        }
+        Ok(())
+    }
+
+    /// Set how many days of screenshots to keep, or 0 to keep them forever
+    pub fn set_retention_days(&self, days: u32) -> Result<(), Error> {
+        self.update_config(|config| config.retention_days = days)
+    }
+
+    /// Delete every day older than the retention period, along with its cached
+    /// frames and database rows. Does nothing when retention is 0. Like `delete_day`,
+    /// the returned closure blocks and holds the capture lock.
+    pub fn evict_old_screenshots(
+        &self,
+    ) -> impl FnOnce() -> Result<ScreenshotEvictionReport, Error> + Send + 'static {
+        let retention_days = self.get_config().retention_days;
+        let timelapse_root_path = self.timelapse_root_path.clone();
+        let db = Arc::clone(&self.db);
+        let writer = self.writer.clone();
+        let capture_lock = Arc::clone(&self.capture_lock);
+
+        move || {
+            if retention_days == 0 {
+                return Ok(ScreenshotEvictionReport::default());
+            }
+
+            // Today counts as the first retained day
+            let today = Local::now().date_naive();
+            let oldest_kept = today - chrono::Days::new(u64::from(retention_days) - 1);
+
+            let _capture_guard = capture_lock.blocking_lock();
+            if let Some(writer) = &writer {
+                writer.blocking_flush();
+            }
+            // Originals can outlive their day's frames, e.g. when the day was moved away
+            let mut expired_days: Vec<NaiveDate> = list_day_dirs(&timelapse_root_path)
+                .into_iter()
+                .map(|(date, _)| date)
+                .chain(original_days(&timelapse_root_path))
+                .filter(|date| *date < oldest_kept)
+                .collect();
+            expired_days.sort();
+            expired_days.dedup();
+
+            let mut report = ScreenshotEvictionReport::default();
+            for date in expired_days {
+                let summary = remove_day(&timelapse_root_path, &db, date)?;
+                report.days_removed += 1;
+                report.files_removed += summary.files_removed;
+                report.rows_removed += summary.rows_removed;
+            }
+
+            // Also drop rows for expired days whose directories were already gone
+            let (cutoff, _) = local_day_bounds(oldest_kept);
+            report.rows_removed += match db.lock() {
+                Ok(db_guard) => {
+                    db_guard.delete_thumbnails_before(oldest_kept)?;
+                    db_guard.delete_frame_timings_before(oldest_kept)?;
+                    db_guard.delete_screenshots_before(cutoff)?
+                }
+                Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+            };
+
+            Ok(report)
+        }
+    }
+
+    fn create_day_dir_if_needed(
+        timelapse_root_path: &Path,
+        dir_layout: DirLayout,
+        date: NaiveDate,
+    ) -> Result<PathBuf, Error> {
+        let day_dir = dir_layout.day_dir(timelapse_root_path, date);
+        std::fs::create_dir_all(&day_dir)?;
+        Ok(day_dir)
+    }
+
+    async fn do_screenshot(
+        timelapse_root_path: &Path,
+        db: &Arc<Mutex<ScreenshotDatabase>>,
+        writer: Option<&ScreenshotWriter>,
+        settings: &TimelapseConfig,
+        previous_signature: &Arc<Mutex<Option<Vec<f64>>>>,
+        previous_change_frame: &Arc<Mutex<Option<MagickWand>>>,
+    ) -> Result<ScreenshotOutcome, Error> {
+        let mut timer = StageTimer::start();
+        let Some(capture) = capture_frame(settings).await? else {
+            return Ok(ScreenshotOutcome::Skipped);
+        };
+        timer.lap("capture");
+
+        let output_format = settings.output_format_for(capture.display.id);
+        // The frame's day is the day it was captured, for its folder and its original
+        let date = capture.captured_at.date_naive();
+        let day_dir =
+            Self::create_day_dir_if_needed(timelapse_root_path, settings.dir_layout, date)?;
+        let filename = next_frame_filename(db, &day_dir, date, output_format, settings)?;
+        let screenshot_path = String::from(
+            day_dir
+                .join(&filename)
+                .to_str()
+                .ok_or(Error::UnableToConvertScreenshotPathToString)?,
+        );
+        timer.lap("frame number");
+
+        // Everything below works on the in-memory canvas, so discarded frames
+        // are never written to disk
+        let mut canvas = match render_capture(&capture, &screenshot_path, settings) {
+            Ok(canvas) => canvas,
+            Err(e) => {
+                // Keep the frame as captured rather than leave a gap in the sequence
+                let Some(mut canvas) = unrendered_canvas(&capture, &screenshot_path, settings, e)?
+                else {
+                    info!(path = %screenshot_path, "Screenshot is all black, discarding");
+                    return Ok(ScreenshotOutcome::Black);
+                };
+                let encoded = encode_frame(
+                    &mut canvas,
+                    &screenshot_path,
+                    output_format,
+                    settings.png_compression_level,
+                )?;
+                write_with_retry(FRAME_WRITE_ATTEMPTS, FRAME_WRITE_RETRY_DELAY, || {
+                    write_file_atomically(Path::new(&screenshot_path), &encoded)
+                })
+                .await?;
+                timer.lap("write");
+                if let Ok(mut previous) = previous_signature.lock() {
+                    *previous = None;
+                }
+                if let Ok(mut previous) = previous_change_frame.lock() {
+                    *previous = None;
+                }
+                let details = SavedFrameDetails {
+                    stage_timings: timer.finish(&screenshot_path),
+                    ..SavedFrameDetails::default()
+                };
+                return Ok(Self::record_saved_frame(
+                    timelapse_root_path,
+                    writer,
+                    settings,
+                    capture,
+                    screenshot_path,
+                    details,
+                ));
+            }
+        };
+        timer.lap("render");
+
+        // Check if the image is all black
+        let is_black =
+            is_frame_black(&canvas, settings.black_threshold, settings.black_sample_target)?;
+        timer.lap("black check");
+        if is_black {
+            info!(path = %screenshot_path, "Screenshot is all black, discarding");
+            return Ok(ScreenshotOutcome::Black);
+        }
+
+        // Compare against the last saved frame and drop near-identical ones
+        let signature = frame_signature(&canvas)?;
+        let is_duplicate = previous_signature.lock().is_ok_and(|previous| {
+            previous.as_ref().is_some_and(|previous| {
+                signature_difference(previous, &signature) < settings.similarity_threshold
+            })
+        });
+        timer.lap("duplicate check");
+        if is_duplicate {
+            debug!(path = %screenshot_path, "Screenshot unchanged since last frame, discarding");
+            return Ok(ScreenshotOutcome::Duplicate);
+        }
+
+        let encoded = encode_frame(
+            &mut canvas,
+            &screenshot_path,
+            output_format,
+            settings.png_compression_level,
+        )?;
+        timer.lap("encode");
+        let thumbnail = if settings.generate_thumbnails {
+            make_thumbnail(&canvas, &screenshot_path)
+                .map_err(|e| warn!(error = %e, "Failed to make thumbnail"))
+                .ok()
+        } else {
+            None
+        };
+        write_with_retry(FRAME_WRITE_ATTEMPTS, FRAME_WRITE_RETRY_DELAY, || {
+            write_file_atomically(Path::new(&screenshot_path), &encoded)
+        })
+        .await?;
+        timer.lap("write");
+
+        if let Ok(mut previous) = previous_signature.lock() {
+            *previous = Some(signature);
+        }
+
+        let change_score = if settings.record_change_scores {
+            // Scaling and comparing the frame is CPU-bound, so keep it off the async task
+            let previous_change_frame = Arc::clone(previous_change_frame);
+            let score =
+                tokio::task::spawn_blocking(move || score_change(canvas, &previous_change_frame))
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!(error = %e, "Failed to score change");
+                        None
+                    });
+            timer.lap("change score");
+            score
+        } else {
+            if let Ok(mut previous) = previous_change_frame.lock() {
+                *previous = None;
+            }
+            None
+        };
+        let details = SavedFrameDetails {
+            thumbnail,
+            change_score,
+            stage_timings: timer.finish(&screenshot_path),
+        };
+
+        Ok(Self::record_saved_frame(
+            timelapse_root_path,
+            writer,
+            settings,
+            capture,
+            screenshot_path,
+            details,
+        ))
+    }
+
+    /// Keep the original of a frame just written to `screenshot_path` if enabled,
+    /// and queue its metadata for the database
+    fn record_saved_frame(
+        timelapse_root_path: &Path,
+        writer: Option<&ScreenshotWriter>,
+        settings: &TimelapseConfig,
+        capture: FrameCapture,
+        screenshot_path: String,
+        details: SavedFrameDetails,
+    ) -> ScreenshotOutcome {
+        // Extract frame number from filename (e.g., "00001.png" -> 1)
+        let frame_number: u32 = Path::new(&screenshot_path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok())
+            .unwrap_or(0);
+
+        // Kept as captured so the frame can be rendered again with other settings,
+        // but never without the masks that hid parts of it
+        if settings.keep_originals {
+            let date = capture.captured_at.date_naive();
+            let original = original_path(timelapse_root_path, date, frame_number);
+            let kept = masked_original(&capture, settings, &screenshot_path)
+                .and_then(|data| keep_original(&original, &data));
+            if let Err(e) = kept {
+                warn!(frame_number, error = %e, "Failed to keep original");
+            }
+        }
+
+        debug!(frame_number, path = %screenshot_path, "Frame saved");
+
+        // Queue the metadata with both UTC and local timestamps for the writer
+        if let Some(writer) = writer {
+            writer.insert(ScreenshotRecord {
+                frame_number,
+                created_at: capture.captured_at.with_timezone(&Utc),
+                local_time: capture.captured_at,
+                app_name: Some(capture.screenshot.app_name),
+                window_title: Some(capture.screenshot.window_title),
+                display: Some(capture.display),
+                thumbnail: details.thumbnail,
+                change_score: details.change_score,
+                stage_timings: if settings.profiling_enabled {
+                    details.stage_timings
+                } else {
+                    Vec::new()
+                },
+            });
+        }
+
+        ScreenshotOutcome::Saved {
+            frame_number,
+            path: PathBuf::from(screenshot_path),
+        }
+    }
+}
+
+/// What's recorded with a saved frame besides its capture
+#[derive(Default)]
+struct SavedFrameDetails {
+    thumbnail: Option<Vec<u8>>,
+    change_score: Option<f64>,
+    stage_timings: Vec<(&'static str, Duration)>,
+}
+
+/// A screenshot along with everything needed to render it as a frame
+struct FrameCapture {
+    screenshot: CapturedScreenshot,
+    captured_at: DateTime<Local>,
+    display: FrameDisplay,
+    /// The part of the screenshot to keep, as fractions of its size
+    crop: Option<(f64, f64, f64, f64)>,
+    /// The pointer's position as fractions of the kept area
+    cursor: Option<(f64, f64)>,
+    masks: Vec<(f64, f64, f64, f64)>,
+    logical_size: Option<(u32, u32)>,
+}
+
+impl FrameCapture {
+    /// A capture of `display` laid out to show its `region`, with the masks and
+    /// sizing in `settings`
+    fn new(
+        screenshot: CapturedScreenshot,
+        captured_at: DateTime<Local>,
+        display: FrameDisplay,
+        region: Rect,
+        cursor: Option<(f64, f64)>,
+        settings: &TimelapseConfig,
+    ) -> FrameCapture {
+        let full_screen = Rect { x: 0, y: 0, width: display.width, height: display.height };
+        let crop = (region != full_screen).then(|| region.fraction_of(full_screen)).flatten();
+        let masks = settings
+            .privacy_masks
+            .iter()
+            .filter(|mask| mask.applies_to(display.id))
+            .filter_map(|mask| mask.rect.fraction_of(region))
+            .collect();
+        let logical_size =
+            settings.capture_logical_resolution.then_some((region.width, region.height));
+
+        FrameCapture {
+            screenshot,
+            captured_at,
+            display,
+            crop,
+            cursor,
+            masks,
+            logical_size,
+        }
+    }
+}
+
+/// Capture the focused screen as `settings` describe, or `None` when they say to
+/// skip this frame (a blocked app is in the foreground, or the window to capture
+/// isn't on screen)
+async fn capture_frame(settings: &TimelapseConfig) -> Result<Option<FrameCapture>, Error> {
+    // Resolve the foreground window first so blocked apps are never captured
+    let fixed_display = match settings.capture_mode {
+        CaptureMode::FixedDisplay(display_id) => Some(display_id),
+        _ => None,
+    };
+    let (focused_screen, active_window) = get_focused_screen(fixed_display).await?;
+    if is_app_blocked(&active_window.app_name, &settings.blocked_apps) {
+        return Ok(None);
+    }
+
+    // The part of the screen this frame shows, relative to its top-left
+    let info = &focused_screen.display_info;
+    let visible = settings.capture_crop.apply(info.width, info.height);
+    let region = match settings.capture_mode {
+        CaptureMode::FocusedScreen | CaptureMode::FixedDisplay(_) => visible,
+        CaptureMode::ActiveWindow => {
+            let visible_rect =
+                (info.x + visible.x, info.y + visible.y, visible.width, visible.height);
+            match window_region(window_rect(&active_window), visible_rect) {
+                Some(region) => Rect { x: region.x + visible.x, y: region.y + visible.y, ..region },
+                None => return Ok(None),
+            }
+        }
+    };
+
+    // The capture doesn't include the pointer, so note where it is to draw it in
+    let cursor = if settings.capture_cursor {
+        let (x, y) = (info.x + region.x, info.y + region.y);
+        cursor_position()
+            .and_then(|position| cursor_fraction(position, (x, y, region.width, region.height)))
+    } else {
+        None
+    };
+
+    let display = FrameDisplay {
+        id: info.id,
+        width: info.width,
+        height: info.height,
+        scale_factor: info.scale_factor,
+    };
+    let captured_at = Local::now();
+    let screenshot = capture_screenshot(focused_screen, active_window).await?;
+
+    Ok(Some(FrameCapture::new(screenshot, captured_at, display, region, cursor, settings)))
+}
+
+/// Render a capture onto a canvas with the size, filter and overlays in
+/// `settings`. `file_path` is only used to describe errors.
+fn render_capture(
+    capture: &FrameCapture,
+    file_path: &str,
+    settings: &TimelapseConfig,
+) -> Result<MagickWand, Error> {
+    let timestamp = settings.timestamp_overlay.then(|| TimestampOverlay {
+        text: capture.captured_at.format(&settings.timestamp_format).to_string(),
+        corner: settings.timestamp_corner,
+    });
+
+    render_frame(
+        &capture.screenshot.image_data,
+        file_path,
+        capture.crop,
+        FrameTarget::from_config(settings),
+        settings.resize_filter,
+        capture.cursor,
+        &capture.masks,
+        timestamp.as_ref(),
+        capture.logical_size,
+    )
+}
+
+/// The capture as taken, for saving when `render_capture` failed with `render_error`,
+/// or `None` if it's all black. Fails with `render_error` instead when privacy masks
+/// or a crop apply, since the unrendered capture would show what they hide.
+fn unrendered_canvas(
+    capture: &FrameCapture,
+    file_path: &str,
+    settings: &TimelapseConfig,
+    render_error: Error,
+) -> Result<Option<MagickWand>, Error> {
+    let is_masked = settings
+        .privacy_masks
+        .iter()
+        .any(|mask| mask.applies_to(capture.display.id));
+    if is_masked || capture.crop.is_some() {
+        warn!(
+            path = %file_path,
+            error = %render_error,
+            "Failed to process capture, discarding it since it's masked or cropped"
+        );
+        return Err(render_error);
+    }
+    warn!(
+        path = %file_path,
+        error = %render_error,
+        "Failed to process capture, saving it unprocessed at full resolution"
+    );
+
+    let wand = MagickWand::new();
+    wand.read_image_blob(&capture.screenshot.image_data)
+        .map_err(|e| Error::UnableToResizeScreenshot {
+            path: file_path.to_string(),
+            reason: format!("Failed to read image: {:?}", e),
+        })?;
+    if is_frame_black(&wand, settings.black_threshold, settings.black_sample_target)? {
+        return Ok(None);
+    }
+    Ok(Some(wand))
+}
+
+/// Summarize `(stage, duration_ms)` rows ordered by stage into one entry per stage.
+/// The 95th percentile is the nearest-rank one.
+fn summarize_stage_timings(timings: Vec<(String, f64)>) -> Vec<StageTimingSummary> {
+    let mut summaries = Vec::new();
+    let mut timings = timings.into_iter().peekable();
+    while let Some((stage, first)) = timings.next() {
+        let mut durations = vec![first];
+        while let Some((_, duration)) = timings.next_if(|(next, _)| *next == stage) {
+            durations.push(duration);
+        }
+        durations.sort_by(f64::total_cmp);
+
+        let frames = durations.len();
+        let p95_rank = (frames * 95).div_ceil(100).max(1);
+        summaries.push(StageTimingSummary {
+            stage,
+            frames,
+            average_ms: durations.iter().sum::<f64>() / frames as f64,
+            p95_ms: durations[p95_rank - 1],
+        });
+    }
+    summaries
+}
+
+/// Times the stages of a capture, logging them when `TIMELAPSE_DEBUG_TIMING` is set
+struct StageTimer {
+    started: Instant,
+    last_lap: Instant,
+    stages: Vec<(&'static str, Duration)>,
+}
+
+impl StageTimer {
+    fn start() -> Self {
+        let now = Instant::now();
+        StageTimer {
+            started: now,
+            last_lap: now,
+            stages: Vec::new(),
+        }
+    }
+
+    fn lap(&mut self, stage: &'static str) {
+        let now = Instant::now();
+        self.stages.push((stage, now - self.last_lap));
+        self.last_lap = now;
+    }
+
+    /// Log the stages if enabled, and return them
+    fn finish(self, screenshot_path: &str) -> Vec<(&'static str, Duration)> {
+        static ENABLED: OnceLock<bool> = OnceLock::new();
+        if *ENABLED.get_or_init(|| std::env::var_os("TIMELAPSE_DEBUG_TIMING").is_some()) {
+            let stages: Vec<String> = self
+                .stages
+                .iter()
+                .map(|(stage, duration)| format!("{} {:?}", stage, duration))
+                .collect();
+            info!(
+                path = screenshot_path,
+                elapsed = ?self.started.elapsed(),
+                stages = %stages.join(", "),
+                "Processed frame"
+            );
+        }
+        self.stages
+    }
+}
+
+/// Get the UTC range `[start, end)` covering a local calendar day
+pub fn local_day_bounds(date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let to_utc = |day: NaiveDate| local_to_utc(day.and_hms_opt(0, 0, 0).unwrap_or_default());
+
+    let next_day = date.succ_opt().unwrap_or(date);
+    (to_utc(date), to_utc(next_day))
+}
+
+/// The UTC time of local time `local`. A time skipped when clocks go forward
+/// resolves to the first local time after the gap, and one repeated when they
+/// go back to its earlier occurrence.
+fn local_to_utc(local: NaiveDateTime) -> DateTime<Utc> {
+    first_valid_time(local, |local| {
+        Local.from_local_datetime(&local).earliest().map(|local| local.with_timezone(&Utc))
+    })
+}
+
+/// `local` as resolved by `resolve`, or if it falls in a gap, the first whole
+/// minute after it that resolves. Gaps are far shorter than a day, so `local` is
+/// only read as UTC when the time zone can't resolve anything around it.
+fn first_valid_time<F>(local: NaiveDateTime, resolve: F) -> DateTime<Utc>
+where
+    F: Fn(NaiveDateTime) -> Option<DateTime<Utc>>,
+{
+    if let Some(utc) = resolve(local) {
+        return utc;
+    }
+    let minute = local.with_second(0).and_then(|local| local.with_nanosecond(0)).unwrap_or(local);
+    (1..=24 * 60)
+        .filter_map(|minutes| minute.checked_add_signed(chrono::Duration::minutes(minutes)))
+        .find_map(resolve)
+        .unwrap_or_else(|| Utc.from_utc_datetime(&local))
+}
+
+/// Path of frame `frame_number` captured on `date`, in whichever layout and
+/// format it was saved
+fn find_frame(timelapse_root_path: &Path, date: NaiveDate, frame_number: u32) -> Option<PathBuf> {
+    existing_day_dirs(timelapse_root_path, date)
+        .into_iter()
+        .flat_map(|day_dir| std::fs::read_dir(day_dir).into_iter().flatten())
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.is_file()
+                && path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse::<u32>().ok())
+                    == Some(frame_number)
+        })
+}
+
+/// Mime type for a frame, based on its extension
+fn mime_type_for(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+
+    match extension.as_deref() {
+        Some("png") => "image/png",
+        Some("webp") => "image/webp",
+        Some("avif") => "image/avif",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Total size of the files under `path`, recursively. Symlinks aren't followed.
+pub fn dir_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Count the regular files directly inside `dir`
+fn count_files(dir: &Path) -> Result<usize, Error> {
+    let mut count = 0;
+    for entry in std::fs::read_dir(dir)? {
+        if entry?.file_type()?.is_file() {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Remove the files and database rows of `date`, like `Photographer::delete_day`
+fn remove_day(
+    timelapse_root_path: &Path,
+    db: &Mutex<ScreenshotDatabase>,
+    date: NaiveDate,
+) -> Result<DeletionSummary, Error> {
+    let day_name = date.format("%Y-%m-%d").to_string();
+    let mut dirs = existing_day_dirs(timelapse_root_path, date);
+    dirs.push(
+        timelapse_root_path
+            .join(".cache")
+            .join(ORIGINALS_CACHE_DIR)
+            .join(&day_name),
+    );
+
+    // Frames extracted from the day's video, at any fps and quality
+    let fps_prefix = format!("{}_fps", day_name);
+    let cache_entries = std::fs::read_dir(timelapse_root_path.join(".cache"));
+    dirs.extend(
+        cache_entries
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name == day_name || name.starts_with(&fps_prefix)
+            })
+            .map(|entry| entry.path()),
+    );
+
+    let mut files_removed = 0;
+    for dir in dirs.iter().filter(|dir| dir.is_dir()) {
+        files_removed += count_files(dir)?;
+        std::fs::remove_dir_all(dir)?;
+    }
+
+    let (start, end) = local_day_bounds(date);
+    let rows_removed = match db.lock() {
+        Ok(db_guard) => {
+            db_guard.delete_thumbnails_for(date)?;
+            db_guard.delete_frame_timings_for(date)?;
+            db_guard.set_day_label(date, None)?;
+            db_guard.delete_screenshots_between(start, end)?
+        }
+        Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+    };
+
+    info!(day = %day_name, files_removed, rows_removed, "Deleted day");
+
+    Ok(DeletionSummary {
+        files_removed,
+        rows_removed,
+    })
+}
+
+/// Record an error in the in-memory log and persist it to the database,
+/// returning the entry so it can also be pushed to the frontend
+fn log_error(
+    error_logs: &Arc<Mutex<ErrorLog>>,
+    db: &Arc<Mutex<ScreenshotDatabase>>,
+    error_message: String,
+) -> ErrorLogEntry {
+    let entry = ErrorLogEntry {
+        timestamp: Utc::now(),
+        error_message,
+    };
+
+    if let Ok(db_guard) = db.lock() {
+        if let Err(e) = db_guard.insert_error(entry.timestamp, &entry.error_message) {
+            warn!(error = %e, "Failed to persist error log");
+        }
+    }
+
+    if let Ok(mut logs) = error_logs.lock() {
+        logs.push(entry.clone());
+    }
+
+    entry
+}
+
+/// Collapse consecutive entries with the same message, keeping their order
+fn group_error_logs(entries: impl IntoIterator<Item = ErrorLogEntry>) -> Vec<ErrorLogGroup> {
+    let mut groups: Vec<ErrorLogGroup> = Vec::new();
+    for entry in entries {
+        match groups.last_mut() {
+            Some(group) if group.error_message == entry.error_message => {
+                group.count += 1;
+                group.last_timestamp = entry.timestamp;
+            }
+            _ => groups.push(ErrorLogGroup {
+                error_message: entry.error_message,
+                count: 1,
+                first_timestamp: entry.timestamp,
+                last_timestamp: entry.timestamp,
+            }),
+        }
+    }
+    groups
+}
+
+/// Convert a persisted `(timestamp, message)` row back into a log entry
+fn parse_error_row((timestamp, error_message): (String, String)) -> Option<ErrorLogEntry> {
+    DateTime::parse_from_rfc3339(&timestamp)
+        .ok()
+        .map(|timestamp| ErrorLogEntry {
+            timestamp: timestamp.with_timezone(&Utc),
+            error_message,
+        })
+}
+
+/// Name for the next frame in `day_dir`: one past the highest frame number either
+/// in the folder or recorded for `date`. The folder is always scanned, since the
+/// writer may not have recorded the latest frames yet, and the database covers
+/// frames whose files are gone. The name is padded like the day's existing frames.
+/// Callers hold `capture_lock`, so two captures can't pick the same number.
+fn next_frame_filename(
+    db: &Arc<Mutex<ScreenshotDatabase>>,
+    day_dir: &Path,
+    date: NaiveDate,
+    output_format: &OutputFormat,
+    settings: &TimelapseConfig,
+) -> Result<String, Error> {
+    let recorded = match db.lock() {
+        Ok(db_guard) => db_guard.next_frame_number(date)?,
+        Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+    };
+    let (on_disk, width) = day_numbering(day_dir, settings.filename_width)?;
+
+    Ok(frame_filename(on_disk.max(recorded), output_format, width))
+}
+
+/// Name of frame `frame_number` in `output_format`, zero-padded to `width` digits
+fn frame_filename(frame_number: u32, output_format: &OutputFormat, width: usize) -> String {
+    format!("{:0width$}.{}", frame_number, output_format.extension(), width = width)
+}
+
+#[cfg(test)]
+fn next_filename(
+    day_dir: &Path,
+    output_format: &OutputFormat,
+    width: usize,
+) -> Result<String, Error> {
+    let (frame_number, width) = day_numbering(day_dir, width)?;
+    Ok(frame_filename(frame_number, output_format, width))
+}
+
+/// The number of the next frame in `day_dir` and the width to pad it to: one
+/// past its highest frame, padded like that frame's name so the day's names keep
+/// sorting in capture order, or frame 1 padded to `width` in an empty folder
+fn day_numbering(day_dir: &Path, width: usize) -> Result<(u32, usize), Error> {
+    Ok(match last_frame(day_dir)? {
+        Some((frame_number, day_width)) => (frame_number + 1, day_width),
+        None => (1, width),
+    })
+}
+
+/// Highest frame number among the files in `day_dir` and the number of digits in
+/// its name, or `None` if there are none
+fn last_frame(day_dir: &Path) -> Result<Option<(u32, usize)>, Error> {
+    let entries = std::fs::read_dir(day_dir)?;
+    let files = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_type()
+                .map(|file_type| file_type.is_file())
+                .unwrap_or(false)
+        })
+        .filter_map(|entry| entry.file_name().to_str().map(|s| s.to_string()));
+
+    // Numbering continues across formats and widths, so switching either mid-day
+    // doesn't restart at 00001
+    Ok(files
+        .filter_map(|filename| {
+            let digits = Path::new(&filename).file_stem()?.len();
+            Some((frame_number_from_filename(&filename)?, digits))
+        })
+        .max())
+}
+
+/// Write the frames in `day_dirs` and a manifest of their metadata into a ZIP,
+/// returning the number of frames written
+fn write_day_zip(
+    file: std::fs::File,
+    day_dirs: &[PathBuf],
+    manifest: &[ScreenshotContext],
+) -> Result<usize, Error> {
+    let mut frames = Vec::new();
+    for day_dir in day_dirs {
+        for entry in std::fs::read_dir(day_dir)? {
+            let path = entry?.path();
+            let is_frame = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(frame_number_from_filename)
+                .is_some();
+            if is_frame && path.is_file() {
+                frames.push(path);
+            }
+        }
+    }
+    frames.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+
+    let mut zip = zip::ZipWriter::new(std::io::BufWriter::new(file));
+    // Frames are already compressed, so deflating them again only costs time
+    let stored = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Stored)
+        .large_file(true);
+    let mut names = std::collections::HashSet::new();
+    for frame in &frames {
+        zip.start_file(unique_entry_name(frame, &mut names), stored)?;
+        std::io::copy(&mut std::fs::File::open(frame)?, &mut zip)?;
+    }
+
+    zip.start_file("manifest.json", zip::write::SimpleFileOptions::default())?;
+    serde_json::to_writer_pretty(&mut zip, manifest)?;
+    zip.finish()?.flush()?;
+
+    Ok(frames.len())
+}
+
+/// `frame`'s file name, or with `-2`, `-3` and so on added to the stem if it's
+/// already in `names`, as when a day has frames in both layouts
+fn unique_entry_name(frame: &Path, names: &mut std::collections::HashSet<String>) -> String {
+    let stem = frame.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+    let extension = frame.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    let mut name = format!("{}.{}", stem, extension);
+    let mut suffix = 1;
+    while !names.insert(name.clone()) {
+        suffix += 1;
+        name = format!("{}-{}.{}", stem, suffix, extension);
+    }
+    name
+}
+
+/// Frames in `day_dirs` with their frame numbers, in capture order
+fn numbered_frames(day_dirs: &[PathBuf]) -> Result<Vec<(u32, PathBuf)>, Error> {
+    let mut frames = Vec::new();
+    for day_dir in day_dirs {
+        for entry in std::fs::read_dir(day_dir)? {
+            let path = entry?.path();
+            let frame_number = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(frame_number_from_filename);
+            if let Some(frame_number) = frame_number.filter(|_| path.is_file()) {
+                frames.push((frame_number, path));
+            }
+        }
+    }
+    frames.sort();
+    Ok(frames)
+}
+
+/// Copy `frames` into `output_dir` as `0001.png`, `0002.png` and so on. Frames
+/// that all share a format keep it; a day that switched formats partway is
+/// converted to PNG throughout, since editors import a sequence in one format.
+/// Names get wider than 4 digits only when the count needs it, so they always
+/// sort in sequence.
+fn copy_renumbered(frames: &[(u32, PathBuf)], output_dir: &Path) -> Result<usize, Error> {
+    fn extension_of(frame: &Path) -> &str {
+        frame.extension().and_then(|ext| ext.to_str()).unwrap_or_default()
+    }
+    let first_extension = frames.first().map(|(_, frame)| extension_of(frame));
+    let is_mixed = frames.iter().any(|(_, frame)| Some(extension_of(frame)) != first_extension);
+    let png = OutputFormat::Png.extension();
+
+    let width = frames.len().to_string().len().max(4);
+    for (index, (_, frame)) in frames.iter().enumerate() {
+        let extension = if is_mixed { png } else { extension_of(frame) };
+        let name = format!("{:0width$}.{}", index + 1, extension, width = width);
+        let output = output_dir.join(name);
+        if extension == extension_of(frame) {
+            std::fs::copy(frame, output)?;
+        } else {
+            write_file_atomically(&output, &convert_to_png(frame)?)?;
+        }
+    }
+    Ok(frames.len())
+}
+
+/// Decode the frame at `path` and encode it as PNG
+fn convert_to_png(path: &Path) -> Result<Vec<u8>, Error> {
+    let describe = |e: magick_rust::MagickError| Error::UnableToResizeScreenshot {
+        path: path.display().to_string(),
+        reason: format!("Failed to convert frame to PNG: {:?}", e),
+    };
+    let wand = MagickWand::new();
+    wand.read_image_blob(std::fs::read(path)?).map_err(describe)?;
+    wand.write_image_blob(OutputFormat::Png.magick_format()).map_err(describe)
+}
+
+/// Frame number of a file named like `00042.png`, whatever its extension
+fn frame_number_from_filename(filename: &str) -> Option<u32> {
+    Path::new(filename)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.parse::<u32>().ok())
+}
+
+/// A captured frame along with the foreground window it was taken for
+struct CapturedScreenshot {
+    image_data: Vec<u8>,
+    app_name: String,
+    window_title: String,
+}
+
+async fn capture_screenshot(
+    focused_screen: Screen,
+    active_window: ActiveWindow,
+) -> Result<CapturedScreenshot, Error> {
+    // Capture screenshot using system API
+    let image = focused_screen
+        .capture()
+        .map_err(|err| Error::UnableToCreateScreenshot {
+            reason: err.to_string(),
+        })?;
+
+    // The Image struct contains PNG data in its buffer
+    // We need to save it as a file
+    let buffer = image.buffer();
+    // std::fs::write(screenshot_path, buffer).map_err(|_| Error::UnableToCreateScreenshot)?;
+
+    // println!("Screenshot saved to {}", screenshot_path);
+    Ok(CapturedScreenshot {
+        image_data: buffer.clone(),
+        app_name: active_window.app_name,
+        window_title: active_window.title,
+    })
+}
+
+/// Every display currently connected
+pub fn list_displays() -> Result<Vec<DisplaySummary>, Error> {
+    let screens = Screen::all().map_err(|err| Error::UnableToCreateScreenshot {
+        reason: err.to_string(),
+    })?;
+
+    Ok(screens
+        .iter()
+        .map(|screen| {
+            let info = &screen.display_info;
+            DisplaySummary {
+                id: info.id,
+                x: info.x,
+                y: info.y,
+                width: info.width,
+                height: info.height,
+                scale_factor: info.scale_factor,
+                is_primary: info.is_primary,
+            }
+        })
+        .collect())
+}
+
+/// The screen to capture and the foreground window: the screen with id
+/// `fixed_display` when given and connected, or else the one showing most of the
+/// foreground window
+async fn get_focused_screen(
+    fixed_display: Option<u32>,
+) -> Result<(Screen, ActiveWindow), Error> {
+    // Get the active window to determine which screen is focused
+    let active_window = get_active_window().map_err(|_| Error::UnableToCreateScreenshot {
+        reason: "Can't get active window".to_owned(),
+    })?;
+
+    // Enumerate screens on every capture so unplugged displays drop out right away
+    let mut screens = Screen::all().map_err(|err| Error::UnableToCreateScreenshot {
+        reason: err.to_string(),
+    })?;
+
+    let window_rect = window_rect(&active_window);
+    let screen_rects: Vec<_> = screens
+        .iter()
+        .map(|screen| {
+            (
+                screen.display_info.x,
+                screen.display_info.y,
+                screen.display_info.width,
+                screen.display_info.height,
+            )
+        })
+        .collect();
+
+    // Pick the fixed display, or the screen showing most of the window. If that
+    // display or the window isn't on any current screen (e.g. it was just
+    // unplugged), fall back to the primary.
+    let index = match fixed_display {
+        Some(display_id) => screens.iter().position(|screen| screen.display_info.id == display_id),
+        None => best_overlap_screen(window_rect, &screen_rects),
+    };
+    let index = index
+        .or_else(|| screens.iter().position(|screen| screen.display_info.is_primary))
+        .or(if screens.is_empty() { None } else { Some(0) })
+        .ok_or_else(|| Error::UnableToCreateScreenshot {
+            reason: "No screens available".to_owned(),
+        })?;
+
+    Ok((screens.swap_remove(index), active_window))
+}
+
+/// Ids of `displays`, sorted so two polls can be compared
+fn sorted_display_ids(displays: &[DisplaySummary]) -> Vec<u32> {
+    let mut display_ids: Vec<u32> = displays.iter().map(|display| display.id).collect();
+    display_ids.sort_unstable();
+    display_ids
+}
+
+/// Whether the sorted `display_ids` differ from the `known` ones from the last
+/// poll. The first poll has nothing to compare against, so it isn't a change.
+fn displays_changed(known: Option<&[u32]>, display_ids: &[u32]) -> bool {
+    known.is_some_and(|known| known != display_ids)
+}
+
+/// Refuse a `capture_mode` fixed to a display that isn't among `display_ids`
+fn check_capture_display(capture_mode: CaptureMode, display_ids: &[u32]) -> Result<(), Error> {
+    if let Some(display_id) = missing_fixed_display(capture_mode, display_ids) {
+        return Err(Error::InvalidSetting {
+            reason: format!("no display with id {} is connected", display_id),
+        });
+    }
+    Ok(())
+}
+
+/// The display `capture_mode` fixes capture to, if it isn't among `display_ids`
+fn missing_fixed_display(capture_mode: CaptureMode, display_ids: &[u32]) -> Option<u32> {
+    match capture_mode {
+        CaptureMode::FixedDisplay(display_id) if !display_ids.contains(&display_id) => {
+            Some(display_id)
+        }
+        _ => None,
+    }
+}
+
+/// Sleep for `duration`, waking early once `running` is cleared so stopping
+/// doesn't wait out a long backoff
+async fn sleep_while_running(duration: Duration, running: &AtomicBool) {
+    let deadline = Instant::now() + duration;
+    while running.load(Ordering::SeqCst) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        sleep(remaining.min(STOP_CHECK_INTERVAL)).await;
+    }
+}
+
+/// What's left of `interval` at `now` for a tick that started at `tick_started`,
+/// or `None` if the tick took the whole interval or longer
+fn remaining_interval(interval: Duration, tick_started: Instant, now: Instant) -> Option<Duration> {
+    interval
+        .checked_sub(now.saturating_duration_since(tick_started))
+        .filter(|remaining| !remaining.is_zero())
+}
+
+/// How long to wait after `consecutive_failures` failed captures in a row
+fn error_backoff(consecutive_failures: u32) -> Duration {
+    let exponent = consecutive_failures.saturating_sub(1).min(31);
+    let secs = ERROR_BACKOFF_INITIAL_SECS.saturating_mul(1 << exponent);
+    Duration::from_secs(secs.min(ERROR_BACKOFF_MAX_SECS))
+}
+
+/// Seconds to wait before the next capture. The battery interval only applies when
+/// battery saver is enabled and the machine is known to be discharging, and never
+/// shortens a longer `base_interval`.
+async fn capture_interval_secs(base_interval: u64, battery_interval_secs: Option<u64>) -> u64 {
+    match battery_interval_secs {
+        Some(interval) if is_on_battery().await == Some(true) => interval.max(base_interval),
+        _ => base_interval,
+    }
+}
+
+/// Seconds since the last keyboard or mouse input, or `None` if it can't be read
+#[cfg(target_os = "macos")]
+fn idle_seconds() -> Option<f64> {
+    // kCGEventSourceStateHIDSystemState and kCGAnyInputEventType
+    const HID_SYSTEM_STATE: i32 = 1;
+    const ANY_INPUT_EVENT: u32 = !0;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(source: i32, event_type: u32) -> f64;
+    }
+
+    // SAFETY: takes plain values and only reads global input state
+    let idle = unsafe { CGEventSourceSecondsSinceLastEventType(HID_SYSTEM_STATE, ANY_INPUT_EVENT) };
+    idle.is_finite().then_some(idle)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn idle_seconds() -> Option<f64> {
+    None
+}
+
+/// Whether `idle` seconds without input mean the user is away or the screen is locked
+fn is_user_away(idle: Option<f64>) -> bool {
+    idle.is_some_and(|idle| idle >= AWAY_IDLE_SECS)
+}
+
+/// Ask `pmset` for the current power source. Returns `None` if it can't be
+/// determined (e.g. `pmset` isn't available on this platform).
+async fn is_on_battery() -> Option<bool> {
+    let output = tokio::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .await
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    parse_power_source(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the first line of `pmset -g batt`, e.g. "Now drawing from 'Battery Power'"
+fn parse_power_source(pmset_output: &str) -> Option<bool> {
+    let first_line = pmset_output.lines().next()?;
+    if first_line.contains("'Battery Power'") {
+        Some(true)
+    } else if first_line.contains("'AC Power'") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Compile `date`'s video in the background after the day rolls over, unless it
+/// was already compiled, and tell the frontend when it's done
+fn spawn_day_compile(
+    timelapse_root_path: PathBuf,
+    date: NaiveDate,
+    error_logs: Arc<Mutex<ErrorLog>>,
+    db: Arc<Mutex<ScreenshotDatabase>>,
+    app_handle: Option<AppHandle>,
+) {
+    if day_video_path(&timelapse_root_path, date).exists() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let result = tokio::task::spawn_blocking(move || {
+            compile_day_video(
+                &timelapse_root_path,
+                date,
+                DEFAULT_VIDEO_FPS,
+                &VideoEncoding::default(),
+            )
+        })
+        .await
+        .unwrap_or_else(|e| Err(Error::VideoCompilationFailed { reason: e.to_string() }));
+
+        match result {
+            Ok(CompiledVideo { video_path, .. }) => {
+                info!(%date, ?video_path, "Compiled day after rollover");
+                if let Some(app_handle) = &app_handle {
+                    let payload = DayCompiled {
+                        date: date.format("%Y-%m-%d").to_string(),
+                        video_path: video_path.to_string_lossy().to_string(),
+                    };
+                    if let Err(e) = app_handle.emit(DAY_COMPILED_EVENT, payload) {
+                        warn!(event = DAY_COMPILED_EVENT, error = %e, "Failed to emit event");
+                    }
+                }
+            }
+            // Days with no frames (e.g. the machine was off) have nothing to compile
+            Err(Error::NoCapturesForDay { .. }) => {}
+            Err(error) => {
+                error!(%date, error = %error, "Auto-compile failed");
+                log_error(&error_logs, &db, error.to_string());
+            }
+        }
+    });
+}
+
+/// Where a day's compiled video lives
+pub fn day_video_path(timelapse_root_path: &Path, date: NaiveDate) -> PathBuf {
+    timelapse_root_path.join(format!("{}.mov", date.format("%Y-%m-%d")))
+}
+
+/// Compile a day's frames into `<date>.mov` in the Timelapse root with ffmpeg,
+/// overwriting any existing video. Blocks until ffmpeg exits.
+pub fn compile_day_video(
+    timelapse_root_path: &Path,
+    date: NaiveDate,
+    fps: u32,
+    encoding: &VideoEncoding,
+) -> Result<CompiledVideo, Error> {
+    let frame_paths = day_frame_paths(timelapse_root_path, date)?;
+    let cache_dir = timelapse_root_path.join(".cache");
+    std::fs::create_dir_all(&cache_dir)?;
+    compile_frames_video(
+        &frame_paths,
+        &cache_dir.join(format!("{}.txt", date.format("%Y-%m-%d"))),
+        &day_video_path(timelapse_root_path, date),
+        fps,
+        encoding,
+    )
+}
+
+/// Every frame captured on `date`, from the day's folders in both layouts, in
+/// capture order. Frame numbers have gaps where black frames were deleted and a
+/// day can switch formats, so frames are listed rather than matched by a pattern.
+fn day_frame_paths(timelapse_root_path: &Path, date: NaiveDate) -> Result<Vec<PathBuf>, Error> {
+    let frames = numbered_frames(&existing_day_dirs(timelapse_root_path, date))?;
+    if frames.is_empty() {
+        return Err(Error::NoCapturesForDay { date });
+    }
+    Ok(frames.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Compile `frame_paths`, in the given order, into `output_path` at `fps` with
+/// ffmpeg. The frames don't need to be numbered in sequence or share a folder:
+/// they're listed in an ffmpeg concat file at `list_path`, removed afterwards.
+/// Blocks until ffmpeg exits.
+pub fn compile_frames_video(
+    frame_paths: &[PathBuf],
+    list_path: &Path,
+    output_path: &Path,
+    fps: u32,
+    encoding: &VideoEncoding,
+) -> Result<CompiledVideo, Error> {
+    if fps == 0 {
+        return Err(Error::InvalidSetting {
+            reason: "fps must be greater than 0".to_string(),
+        });
+    }
+    std::fs::write(list_path, concat_list(frame_paths, fps))?;
+
+    info!(
+        frames = frame_paths.len(),
+        ?output_path,
+        fps,
+        codec = %encoding.codec,
+        "Compiling video"
+    );
+
+    let args = compile_args(list_path, output_path, fps, encoding);
+    let result = run_ffmpeg(std::process::Command::new("ffmpeg").args(&args));
+    let _ = std::fs::remove_file(list_path);
+    result?;
+
+    info!(?output_path, "Video compilation complete");
+
+    Ok(CompiledVideo {
+        video_path: output_path.to_path_buf(),
+        ffmpeg_command: command_line("ffmpeg", &args),
+    })
+}
+
+/// An ffmpeg concat list showing each of `frame_paths` for one frame at `fps`.
+/// The last frame is listed twice, since ffmpeg ignores the final duration.
+fn concat_list(frame_paths: &[PathBuf], fps: u32) -> String {
+    let mut list = String::from("ffconcat version 1.0\n");
+    let quote = |path: &PathBuf| path.to_string_lossy().replace('\'', "'\\''");
+    for path in frame_paths {
+        list.push_str(&format!("file '{}'\nduration {}\n", quote(path), 1.0 / fps as f64));
+    }
+    if let Some(last) = frame_paths.last() {
+        list.push_str(&format!("file '{}'\n", quote(last)));
+    }
+    list
+}
+
+/// Frame numbers of local day `date` whose change score is above `min_change`
+fn keyframes(
+    db: &Mutex<ScreenshotDatabase>,
+    date: NaiveDate,
+    min_change: f64,
+) -> Result<Vec<u32>, Error> {
+    check_fraction("minimum change", min_change)?;
+    let (start, end) = local_day_bounds(date);
+    let scores = match db.lock() {
+        Ok(db_guard) => db_guard.get_change_scores(start, end)?,
+        Err(_) => return Err(Error::DatabaseError(rusqlite::Error::InvalidQuery)),
+    };
+    Ok(scores
+        .into_iter()
+        .filter(|score| score.score > min_change)
+        .map(|score| score.frame_number)
+        .collect())
+}
+
+/// ffmpeg output arguments for the codec and quality in `encoding`
+fn encoding_args(encoding: &VideoEncoding) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "-c:v".into(),
+        encoding.codec.clone(),
+        "-crf".into(),
+        encoding.crf.to_string(),
+    ];
+    // QuickTime only plays HEVC tagged as hvc1
+    if encoding.codec == "libx265" {
+        args.extend(["-tag:v".into(), "hvc1".into()]);
+    }
+    args.extend([
+        "-pix_fmt".into(),
+        "yuv420p".into(), // Required for playback in QuickTime
+    ]);
+    args
+}
+
+/// ffmpeg arguments that encode the frames in concat list `list_path` into
+/// `output_path`
+fn compile_args(
+    list_path: &Path,
+    output_path: &Path,
+    fps: u32,
+    encoding: &VideoEncoding,
+) -> Vec<String> {
+    let mut args = concat_input_args(list_path);
+    args.extend(encoding_args(encoding));
+    args.extend([
+        "-r".into(),
+        fps.to_string(),
+        "-y".into(),
+        output_path.to_string_lossy().to_string(),
+    ]);
+    args
+}
+
+/// ffmpeg arguments that read frames from concat list `list_path`
+fn concat_input_args(list_path: &Path) -> Vec<String> {
+    vec![
+        "-f".into(),
+        "concat".into(),
+        // The list holds absolute paths, which the concat demuxer rejects as unsafe
+        "-safe".into(),
+        "0".into(),
+        "-i".into(),
+        list_path.to_string_lossy().to_string(),
+    ]
+}
+
+/// `program` and `args` as they'd be typed in a shell, quoting any argument
+/// with whitespace or a glob in it
+fn command_line(program: &str, args: &[String]) -> String {
+    let mut line = program.to_string();
+    for arg in args {
+        line.push(' ');
+        if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || c == '*' || c == '\'') {
+            line.push_str(&format!("'{}'", arg.replace('\'', "'\\''")));
+        } else {
+            line.push_str(arg);
+        }
+    }
+    line
+}
+
+/// Estimate the length and size of the video `compile_day_video` would make from
+/// `date`'s frames at `fps`, from the same frames it would read
+pub fn estimate_video(
+    timelapse_root_path: &Path,
+    date: NaiveDate,
+    fps: u32,
+) -> Result<VideoEstimate, Error> {
+    if fps == 0 {
+        return Err(Error::InvalidSetting {
+            reason: "fps must be greater than 0".to_string(),
+        });
+    }
+
+    let frame_paths = day_frame_paths(timelapse_root_path, date)?;
+    let frame_count = frame_paths.len();
+    let mut total_bytes = 0;
+    for path in &frame_paths {
+        total_bytes += std::fs::metadata(path)?.len();
+    }
+
+    Ok(VideoEstimate {
+        frame_count,
+        duration_secs: frame_count as f64 / f64::from(fps),
+        estimated_bytes: (total_bytes as f64 * VIDEO_SIZE_FACTOR) as u64,
+    })
+}
+
+/// Path of the preview GIF for `date` in the Timelapse root
+pub fn day_gif_path(timelapse_root_path: &Path, date: NaiveDate) -> PathBuf {
+    timelapse_root_path.join(format!("{}.gif", date.format("%Y-%m-%d")))
+}
+
+/// Render a day's frames into a small `<date>.gif` preview in the Timelapse root,
+/// keeping every `frame_skip`-th frame scaled down to at most `max_width` pixels
+/// wide. Stops after `MAX_GIF_FRAMES` frames. Blocks until ffmpeg exits.
+pub fn generate_day_gif(
+    timelapse_root_path: &Path,
+    date: NaiveDate,
+    max_width: u32,
+    frame_skip: u32,
+) -> Result<PathBuf, Error> {
+    if max_width == 0 {
+        return Err(Error::InvalidSetting {
+            reason: "GIF width must be greater than 0".to_string(),
+        });
+    }
+
+    let frame_paths = day_frame_paths(timelapse_root_path, date)?;
+    let output_path = day_gif_path(timelapse_root_path, date);
+
+    let cache_dir = timelapse_root_path.join(".cache");
+    std::fs::create_dir_all(&cache_dir)?;
+    let list_path = cache_dir.join(format!("{}_gif.txt", date.format("%Y-%m-%d")));
+    std::fs::write(&list_path, concat_list(&frame_paths, GIF_FPS))?;
+
+    let result = run_ffmpeg(
+        std::process::Command::new("ffmpeg")
+            .args(concat_input_args(&list_path))
+            .arg("-filter_complex")
+            .arg(gif_filter_graph(max_width, frame_skip))
+            .arg("-fps_mode")
+            .arg("vfr") // Don't pad the gaps left by skipped frames with duplicates
+            .arg("-frames:v")
+            .arg(MAX_GIF_FRAMES.to_string())
+            .arg("-y")
+            .arg(&output_path),
+    );
+    let _ = std::fs::remove_file(&list_path);
+    result?;
+
+    Ok(output_path)
+}
+
+/// ffmpeg filter graph that keeps every `frame_skip`-th frame, scales it down to
+/// `max_width` and quantizes it with a palette generated from the whole clip
+fn gif_filter_graph(max_width: u32, frame_skip: u32) -> String {
+    format!(
+        "select='not(mod(n\\,{}))',scale='min({}\\,iw)':-1:flags=lanczos,\
+         split[a][b];[a]palettegen[p];[b][p]paletteuse",
+        frame_skip.max(1),
+        max_width
+    )
+}
+
+/// Run an ffmpeg command to completion, reporting a missing binary or a
+/// non-zero exit as `VideoCompilationFailed`
+fn run_ffmpeg(command: &mut std::process::Command) -> Result<(), Error> {
+    let output = command.output().map_err(|e| Error::VideoCompilationFailed {
+        reason: format!(
+            "Failed to execute ffmpeg: {}. Make sure ffmpeg is installed and in PATH.",
+            e
+        ),
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::VideoCompilationFailed {
+            reason: format!("ffmpeg failed: {}", stderr),
+        });
+    }
+
+    Ok(())
+}
+
+/// Whether `hour` falls in any of the schedule's ranges. An empty schedule
+/// always matches, and a range whose start equals its end covers the whole day.
+fn is_within_schedule(schedule: &[(u8, u8)], hour: u8) -> bool {
+    schedule.is_empty()
+        || schedule
+            .iter()
+            .any(|&(start, end)| is_within_hours(start, end, hour))
+}
+
+/// Whether `hour` falls in the range from `start` up to but not including `end`,
+/// wrapping past midnight when the end is before the start
+fn is_within_hours(start: u8, end: u8, hour: u8) -> bool {
+    if start < end {
+        (start..end).contains(&hour)
+    } else {
+        hour >= start || hour < end
+    }
+}
+
+/// The capture interval for `hour`: the first matching range's interval, or the
+/// normal interval outside them all
+fn scheduled_interval_secs(schedule: &[(u8, u8, u64)], hour: u8) -> u64 {
+    schedule
+        .iter()
+        .find(|&&(start, end, _)| is_within_hours(start, end, hour))
+        .map_or(CAPTURE_INTERVAL_SECS, |&(_, _, interval_secs)| interval_secs)
+}
+
+/// Move `current_day` on to `today`, returning the day that just finished if
+/// `settings` ask for its video to be compiled
+fn day_to_compile(
+    current_day: &mut NaiveDate,
+    today: NaiveDate,
+    settings: &TimelapseConfig,
+) -> Option<NaiveDate> {
+    if *current_day == today {
+        return None;
+    }
+    let finished_day = std::mem::replace(current_day, today);
+    settings.auto_compile.then_some(finished_day)
+}
+
+/// Time left until the next minute boundary after `now`
+fn until_next_minute(now: DateTime<Local>) -> Duration {
+    let elapsed = Duration::new(u64::from(now.second()), now.nanosecond() % 1_000_000_000);
+    Duration::from_secs(60).saturating_sub(elapsed)
+}
+
+fn is_app_blocked(app_name: &str, blocked_apps: &[String]) -> bool {
+    let app_name = app_name.trim().to_lowercase();
+    blocked_apps
+        .iter()
+        .any(|blocked| blocked.to_lowercase() == app_name)
+}
+
+/// Area of the intersection between a window and a screen, 0 if they don't touch
+fn overlap_area(window: (i32, i32, i32, i32), screen: (i32, i32, u32, u32)) -> i64 {
+    let (wx, wy, ww, wh) = window;
+    let (sx, sy, sw, sh) = screen;
+
+    let left = i64::from(wx).max(i64::from(sx));
+    let top = i64::from(wy).max(i64::from(sy));
+    let right = (i64::from(wx) + i64::from(ww)).min(i64::from(sx) + i64::from(sw));
+    let bottom = (i64::from(wy) + i64::from(wh)).min(i64::from(sy) + i64::from(sh));
+
+    if right <= left || bottom <= top {
+        return 0;
+    }
+    (right - left) * (bottom - top)
+}
+
+/// Index of the screen with the largest overlap with the window, or `None` if the
+/// window doesn't overlap any of them. Ties go to the first screen.
+fn best_overlap_screen(
+    window: (i32, i32, i32, i32),
+    screens: &[(i32, i32, u32, u32)],
+) -> Option<usize> {
+    let mut best: Option<(usize, i64)> = None;
+    for (index, &screen) in screens.iter().enumerate() {
+        if !window_overlaps_screen(window, screen) {
+            continue;
+        }
+        let area = overlap_area(window, screen);
+        if best.is_none_or(|(_, best_area)| area > best_area) {
+            best = Some((index, area));
+        }
+    }
+    best.map(|(index, _)| index)
+}
+
+/// The window's position as `(x, y, width, height)` in global screen coordinates
+fn window_rect(window: &ActiveWindow) -> (i32, i32, i32, i32) {
+    (
+        window.position.x as i32,
+        window.position.y as i32,
+        window.position.width as i32,
+        window.position.height as i32,
+    )
+}
+
+/// The part of the window on the screen, relative to the screen's top-left, or
+/// `None` if none of it is
+fn window_region(window: (i32, i32, i32, i32), screen: (i32, i32, u32, u32)) -> Option<Rect> {
+    let (wx, wy, ww, wh) = window;
+    let (sx, sy, sw, sh) = screen;
+
+    // Clamp to the screen's edges, since windows can hang off them
+    let left = i64::from(wx).max(i64::from(sx));
+    let top = i64::from(wy).max(i64::from(sy));
+    let right = (i64::from(wx) + i64::from(ww)).min(i64::from(sx) + i64::from(sw));
+    let bottom = (i64::from(wy) + i64::from(wh)).min(i64::from(sy) + i64::from(sh));
+    if right <= left || bottom <= top {
+        return None;
+    }
+
+    Some(Rect {
+        x: (left - i64::from(sx)) as i32,
+        y: (top - i64::from(sy)) as i32,
+        width: (right - left) as u32,
+        height: (bottom - top) as u32,
+    })
+}
+
+fn window_overlaps_screen(window: (i32, i32, i32, i32), screen: (i32, i32, u32, u32)) -> bool {
+    overlap_area(window, screen) > 0
+}
+
+/// The canvas `render_frame` fits a capture into
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FrameTarget {
+    /// The capture's own size
+    Native,
+    /// A fixed `(width, height)`
+    Size(u32, u32),
+    /// A `(width, height)` ratio, keeping the capture's longer edge
+    AspectRatio(u32, u32),
+}
+
+impl FrameTarget {
+    fn from_config(config: &TimelapseConfig) -> Self {
+        match (config.aspect_ratio, config.output_size) {
+            (Some((width, height)), _) => FrameTarget::AspectRatio(width, height),
+            (None, Some((width, height))) => FrameTarget::Size(width, height),
+            (None, None) => FrameTarget::Native,
+        }
+    }
+
+    /// Canvas dimensions for a `width` by `height` capture
+    fn dimensions(&self, width: f64, height: f64) -> (f64, f64) {
+        match *self {
+            FrameTarget::Native => (width, height),
+            FrameTarget::Size(width, height) => (width as f64, height as f64),
+            FrameTarget::AspectRatio(ratio_width, ratio_height) => {
+                let longer_edge = width.max(height);
+                let (ratio_width, ratio_height) = (ratio_width as f64, ratio_height as f64);
+                // Even dimensions, since H.264 with yuv420p rejects odd ones
+                let even = |dimension: f64| ((dimension / 2.0).round() * 2.0).max(2.0);
+                if ratio_width >= ratio_height {
+                    (even(longer_edge), even(longer_edge * ratio_height / ratio_width))
+                } else {
+                    (even(longer_edge * ratio_width / ratio_height), even(longer_edge))
+                }
+            }
+        }
+    }
+}
+
+/// Text burned into a corner of a frame
+struct TimestampOverlay {
+    text: String,
+    corner: OverlayCorner,
+}
+
+/// Fit the captured image into the `target` canvas, letterboxing to preserve its
+/// aspect ratio. `crop` is the `(x, y, width, height)` part of the capture to keep,
+/// as fractions of its size. `cursor` is the pointer's position as a fraction of
+/// the kept area's width and height, marked on the frame when given. `file_path`
+/// is only used to describe errors.
+#[allow(clippy::too_many_arguments)]
+fn render_frame(
+    data: &[u8],
+    file_path: &str,
+    crop: Option<(f64, f64, f64, f64)>,
+    target: FrameTarget,
+    resize_filter: ResizeFilter,
+    cursor: Option<(f64, f64)>,
+    masks: &[(f64, f64, f64, f64)],
+    timestamp: Option<&TimestampOverlay>,
+    logical_size: Option<(u32, u32)>,
+) -> Result<MagickWand, Error> {
+    let wand = MagickWand::new();
+
+    // Read the image
+    wand.read_image_blob(data)
+        .map_err(|e| Error::UnableToResizeScreenshot {
+            path: file_path.to_string(),
+            reason: format!("Failed to read image: {:?}", e),
+        })?;
+
+    if let Some(crop) = crop {
+        let (x, y, width, height) =
+            crop_pixels(crop, wand.get_image_width(), wand.get_image_height());
+        wand.crop_image(width, height, x as isize, y as isize)
+            .map_err(|e| Error::UnableToResizeScreenshot {
+                path: file_path.to_string(),
+                reason: format!("Failed to crop to window: {:?}", e),
+            })?;
+    }
+
+    // Drop the extra pixels of a high-DPI capture before any other scaling
+    if let Some((logical_width, logical_height)) = logical_size {
+        let is_larger = wand.get_image_width() > logical_width as usize
+            || wand.get_image_height() > logical_height as usize;
+        if is_larger && logical_width > 0 && logical_height > 0 {
+            wand.resize_image(
+                logical_width as usize,
+                logical_height as usize,
+                resize_filter.magick_filter(),
+            )
+            .map_err(|e| Error::UnableToResizeScreenshot {
+                path: file_path.to_string(),
+                reason: format!("Failed to resize to logical resolution: {:?}", e),
+            })?;
+        }
+    }
+
+    // Get original dimensions
+    let orig_width = wand.get_image_width() as f64;
+    let orig_height = wand.get_image_height() as f64;
+
+    // Target dimensions
+    let (target_width, target_height) = target.dimensions(orig_width, orig_height);
+
+    // Calculate scaling to fit within target dimensions while maintaining aspect ratio
+    let scale_x = target_width / orig_width;
+    let scale_y = target_height / orig_height;
+    let scale = scale_x.min(scale_y);
+
+    // Calculate new dimensions
+    let new_width = (orig_width * scale) as usize;
+    let new_height = (orig_height * scale) as usize;
+
+    // Resize the image maintaining aspect ratio
+    wand.resize_image(new_width, new_height, resize_filter.magick_filter())
+        .map_err(|e| Error::UnableToResizeScreenshot {
+            path: file_path.to_string(),
+            reason: format!("Failed to resize image: {:?}", e),
+        })?;
+
+    // Create a new black canvas of target size
+    let mut canvas = MagickWand::new();
+    canvas
+        .new_image(
+            target_width as usize,
+            target_height as usize,
+            &magick_rust::PixelWand::new(),
+        )
+        .map_err(|e| Error::UnableToResizeScreenshot {
+            path: file_path.to_string(),
+            reason: format!("Failed to create canvas: {:?}", e),
+        })?;
+
+    // Calculate position to center the resized image
+    let x_offset = ((target_width - new_width as f64) / 2.0) as isize;
+    let y_offset = ((target_height - new_height as f64) / 2.0) as isize;
+
+    // Composite the resized image onto the black canvas
+    canvas
+        .compose_images(
+            &wand,
+            magick_rust::CompositeOperator::Over,
+            true,
+            x_offset,
+            y_offset,
+        )
+        .map_err(|e| Error::UnableToResizeScreenshot {
+            path: file_path.to_string(),
+            reason: format!("Failed to composite image: {:?}", e),
+        })?;
+
+    if let Some((fraction_x, fraction_y)) = cursor {
+        let x = x_offset as f64 + fraction_x * new_width as f64;
+        let y = y_offset as f64 + fraction_y * new_height as f64;
+        draw_cursor_marker(&mut canvas, x, y, new_width.max(new_height) as f64)
+            .map_err(|e| Error::UnableToResizeScreenshot {
+                path: file_path.to_string(),
+                reason: format!("Failed to draw cursor: {:?}", e),
+            })?;
+    }
+
+    if let Some(timestamp) = timestamp {
+        let extent = target_width.max(target_height);
+        draw_timestamp(&mut canvas, timestamp, extent).map_err(|e| {
+            Error::UnableToResizeScreenshot {
+                path: file_path.to_string(),
+                reason: format!("Failed to draw timestamp: {:?}", e),
+            }
+        })?;
+    }
+
+    // Masks go on last so nothing drawn above can show through them
+    for (fraction_x, fraction_y, fraction_width, fraction_height) in masks {
+        let x = x_offset as f64 + fraction_x * new_width as f64;
+        let y = y_offset as f64 + fraction_y * new_height as f64;
+        let width = fraction_width * new_width as f64;
+        let height = fraction_height * new_height as f64;
+        draw_mask(&mut canvas, x, y, width, height).map_err(|e| {
+            Error::UnableToResizeScreenshot {
+                path: file_path.to_string(),
+                reason: format!("Failed to draw privacy mask: {:?}", e),
+            }
+        })?;
+    }
+
+    Ok(canvas)
+}
+
+/// `crop` fractions as a pixel `(x, y, width, height)` inside a `width` by `height`
+/// image, at least one pixel in size
+fn crop_pixels(
+    crop: (f64, f64, f64, f64),
+    width: usize,
+    height: usize,
+) -> (usize, usize, usize, usize) {
+    let (fraction_x, fraction_y, fraction_width, fraction_height) = crop;
+    let to_pixels = |fraction: f64, extent: usize| (fraction * extent as f64).round() as usize;
+    let x = to_pixels(fraction_x, width).min(width.saturating_sub(1));
+    let y = to_pixels(fraction_y, height).min(height.saturating_sub(1));
+    let crop_width = to_pixels(fraction_width, width).clamp(1, (width - x).max(1));
+    let crop_height = to_pixels(fraction_height, height).clamp(1, (height - y).max(1));
+    (x, y, crop_width, crop_height)
+}
+
+/// Scale a frame down to a JPEG `THUMBNAIL_WIDTH` pixels wide. `file_path` is
+/// only used to describe errors.
+fn make_thumbnail(frame: &MagickWand, file_path: &str) -> Result<Vec<u8>, Error> {
+    let thumbnail = frame.clone();
+    let (width, height) = (thumbnail.get_image_width(), thumbnail.get_image_height());
+    let thumbnail_height = (height * THUMBNAIL_WIDTH / width.max(1)).max(1);
+
+    thumbnail
+        .resize_image(THUMBNAIL_WIDTH, thumbnail_height, magick_rust::FilterType::Triangle)
+        .and_then(|_| thumbnail.set_image_format("JPEG"))
+        .and_then(|_| thumbnail.set_image_compression_quality(80))
+        .and_then(|_| thumbnail.write_image_blob("JPEG"))
+        .map_err(|e| Error::UnableToResizeScreenshot {
+            path: file_path.to_string(),
+            reason: format!("Failed to generate thumbnail: {:?}", e),
+        })
+}
+
+/// Fill the `width` by `height` rectangle at `(x, y)` with black
+fn draw_mask(
+    canvas: &mut MagickWand,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> Result<(), magick_rust::MagickError> {
+    let mut fill = magick_rust::PixelWand::new();
+    fill.set_color("black")?;
+
+    let mut drawing = magick_rust::DrawingWand::new();
+    drawing.set_fill_color(&fill);
+    drawing.draw_rectangle(x, y, x + width, y + height);
+
+    canvas.draw_image(&drawing)
+}
+
+/// Write the overlay's text in white on a translucent black box, inset from its
+/// corner and sized relative to the image so it stays legible after downscaling
+fn draw_timestamp(
+    canvas: &mut MagickWand,
+    timestamp: &TimestampOverlay,
+    image_extent: f64,
+) -> Result<(), magick_rust::MagickError> {
+    let font_size = (image_extent / 80.0).max(12.0);
+
+    let mut fill = magick_rust::PixelWand::new();
+    fill.set_color("white")?;
+    let mut under = magick_rust::PixelWand::new();
+    under.set_color("rgba(0, 0, 0, 0.5)")?;
+
+    let mut drawing = magick_rust::DrawingWand::new();
+    drawing.set_fill_color(&fill);
+    drawing.set_text_under_color(&under);
+    drawing.set_font_size(font_size);
+    drawing.set_gravity(timestamp.corner.gravity());
+
+    let margin = font_size / 2.0;
+    canvas.annotate_image(&drawing, margin, margin, 0.0, &timestamp.text)
+}
+
+/// Draw a white dot with a black outline centred on `(x, y)`, sized relative to
+/// the image so it stays visible after downscaling
+fn draw_cursor_marker(
+    canvas: &mut MagickWand,
+    x: f64,
+    y: f64,
+    image_extent: f64,
+) -> Result<(), magick_rust::MagickError> {
+    let radius = (image_extent / 250.0).max(4.0);
+
+    let mut fill = magick_rust::PixelWand::new();
+    fill.set_color("white")?;
+    let mut stroke = magick_rust::PixelWand::new();
+    stroke.set_color("black")?;
+
+    let mut drawing = magick_rust::DrawingWand::new();
+    drawing.set_fill_color(&fill);
+    drawing.set_stroke_color(&stroke);
+    drawing.set_stroke_width((radius / 3.0).max(1.0));
+    drawing.draw_circle(x, y, x + radius, y);
+
+    canvas.draw_image(&drawing)
+}
+
+/// The pointer's position in global screen coordinates, or `None` if it can't be read
+#[cfg(target_os = "macos")]
+fn cursor_position() -> Option<(f64, f64)> {
+    use core_graphics::event::CGEvent;
+    use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+    let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState).ok()?;
+    let location = CGEvent::new(source).ok()?.location();
+    Some((location.x, location.y))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn cursor_position() -> Option<(f64, f64)> {
+    None
+}
+
+/// Where `cursor` sits on `screen` as a fraction of its width and height, or
+/// `None` if it's on another screen
+fn cursor_fraction(cursor: (f64, f64), screen: (i32, i32, u32, u32)) -> Option<(f64, f64)> {
+    let (cx, cy) = cursor;
+    let (sx, sy, sw, sh) = screen;
+    if sw == 0 || sh == 0 {
+        return None;
+    }
+
+    let fraction_x = (cx - f64::from(sx)) / f64::from(sw);
+    let fraction_y = (cy - f64::from(sy)) / f64::from(sh);
+    let on_screen = |fraction: f64| (0.0..1.0).contains(&fraction);
+    (on_screen(fraction_x) && on_screen(fraction_y)).then_some((fraction_x, fraction_y))
+}
+
+/// Encode a rendered frame in `output_format`, at `png_compression_level` if it's
+/// PNG. `file_path` is only used to describe errors.
+fn encode_frame(
+    canvas: &mut MagickWand,
+    file_path: &str,
+    output_format: &OutputFormat,
+    png_compression_level: u8,
+) -> Result<Vec<u8>, Error> {
+    // Configure the encoder
+    canvas
+        .set_image_format(output_format.magick_format())
+        .map_err(|e| Error::UnableToResizeScreenshot {
+            path: file_path.to_string(),
+            reason: format!("Failed to set image format: {:?}", e),
+        })?;
+    if let OutputFormat::WebP { lossless, quality } = output_format {
+        let encoder_options = canvas
+            .set_option("webp:lossless", if *lossless { "true" } else { "false" })
+            .and_then(|_| canvas.set_image_compression_quality(*quality as usize));
+        encoder_options.map_err(|e| Error::UnableToResizeScreenshot {
+            path: file_path.to_string(),
+            reason: format!("Failed to configure WebP encoder: {:?}", e),
+        })?;
+    }
+    if *output_format == OutputFormat::Png {
+        canvas
+            .set_image_compression_quality(png_quality(png_compression_level))
+            .map_err(|e| Error::UnableToResizeScreenshot {
+                path: file_path.to_string(),
+                reason: format!("Failed to configure PNG encoder: {:?}", e),
+            })?;
+    }
+    if let OutputFormat::Avif { quality } = output_format {
+        canvas
+            .set_image_compression_quality(*quality as usize)
+            .map_err(|e| Error::UnableToResizeScreenshot {
+                path: file_path.to_string(),
+                reason: format!("Failed to configure AVIF encoder: {:?}", e),
+            })?;
+    }
+
+    // Encode in memory; the caller writes under a temporary name and renames so
+    // an interrupted write never leaves a truncated frame in the sequence
+    canvas
+        .write_image_blob(output_format.magick_format())
+        .map_err(|e| Error::UnableToResizeScreenshot {
+            path: file_path.to_string(),
+            reason: format!("Failed to encode image: {:?}", e),
+        })
+}
+
+/// ImageMagick's PNG "quality" for a zlib `level`. Its tens digit is the zlib
+/// level, which sets how hard deflate works, and its ones digit the row filter
+/// applied before deflating. Filter 5 picks a filter per row adaptively, as
+/// ImageMagick does when no quality is set, so level 7 gives its default of 75.
+fn png_quality(level: u8) -> usize {
+    usize::from(level.min(MAX_PNG_COMPRESSION_LEVEL)) * 10 + 5
+}
+
+/// Run `write` up to `attempts` times, pausing `delay` between failures, so a
+/// transient problem (e.g. a briefly full disk) doesn't cost the frame. Returns
+/// the last error once every attempt has failed.
+async fn write_with_retry<F>(attempts: u32, delay: Duration, mut write: F) -> Result<(), Error>
+where
+    F: FnMut() -> Result<(), Error>,
+{
+    let mut attempt = 1;
+    loop {
+        match write() {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < attempts => {
+                warn!(attempt, attempts, error = %e, "Frame write attempt failed");
+                attempt += 1;
+                sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Where the full-resolution capture behind frame `frame_number` of `date` is kept
+fn original_path(timelapse_root_path: &Path, date: NaiveDate, frame_number: u32) -> PathBuf {
+    timelapse_root_path
+        .join(".cache")
+        .join(ORIGINALS_CACHE_DIR)
+        .join(date.format("%Y-%m-%d").to_string())
+        .join(format!("{:05}.png", frame_number))
+}
+
+/// Remove the directory entry at `path` without following it, so a symlink in a
+/// day directory is removed rather than whatever it points to. Returns whether
+/// there was anything to remove.
+fn remove_entry(path: &Path) -> Result<bool, Error> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => Ok(false),
+        Ok(_) => {
+            std::fs::remove_file(path)?;
+            Ok(true)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The capture's PNG data with the privacy masks in `settings` drawn in at full
+/// resolution, for keeping as its original. `file_path` is only used to describe
+/// errors.
+fn masked_original(
+    capture: &FrameCapture,
+    settings: &TimelapseConfig,
+    file_path: &str,
+) -> Result<Vec<u8>, Error> {
+    let display = capture.display;
+    let full_screen = Rect { x: 0, y: 0, width: display.width, height: display.height };
+    let masks: Vec<_> = settings
+        .privacy_masks
+        .iter()
+        .filter(|mask| mask.applies_to(display.id))
+        .filter_map(|mask| mask.rect.fraction_of(full_screen))
+        .collect();
+    if masks.is_empty() {
+        return Ok(capture.screenshot.image_data.clone());
+    }
+
+    let describe = |e: magick_rust::MagickError| Error::UnableToResizeScreenshot {
+        path: file_path.to_string(),
+        reason: format!("Failed to mask original: {:?}", e),
+    };
+    let mut wand = MagickWand::new();
+    wand.read_image_blob(&capture.screenshot.image_data).map_err(describe)?;
+    let (width, height) = (wand.get_image_width() as f64, wand.get_image_height() as f64);
+    for (fraction_x, fraction_y, fraction_width, fraction_height) in masks {
+        draw_mask(
+            &mut wand,
+            fraction_x * width,
+            fraction_y * height,
+            fraction_width * width,
+            fraction_height * height,
+        )
+        .map_err(describe)?;
+    }
+    wand.write_image_blob("PNG").map_err(describe)
+}
+
+/// Days with kept originals, whether or not their frames are still there
+fn original_days(timelapse_root_path: &Path) -> Vec<NaiveDate> {
+    let originals_dir = timelapse_root_path.join(".cache").join(ORIGINALS_CACHE_DIR);
+    std::fs::read_dir(originals_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            NaiveDate::parse_from_str(entry.file_name().to_str()?, "%Y-%m-%d").ok()
+        })
+        .collect()
+}
+
+/// Write a capture's PNG data to `path`, creating its day folder if needed
+fn keep_original(path: &Path, data: &[u8]) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_file_atomically(path, data)
+}
+
+/// Pixel width and height of encoded image `data`. `file_path` is only used to
+/// describe errors.
+fn image_size(data: &[u8], file_path: &str) -> Result<(u32, u32), Error> {
+    let wand = MagickWand::new();
+    wand.read_image_blob(data)
+        .map_err(|e| Error::UnableToResizeScreenshot {
+            path: file_path.to_string(),
+            reason: format!("Failed to read image: {:?}", e),
+        })?;
+    Ok((wand.get_image_width() as u32, wand.get_image_height() as u32))
+}
+
+/// Temporary name a frame is written under before being renamed into place.
+/// The leading dot keeps it out of frame globs and frame numbering.
+fn partial_frame_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.partial", file_name))
+}
+
+fn write_file_atomically(path: &Path, data: &[u8]) -> Result<(), Error> {
+    let partial_path = partial_frame_path(path);
+    if let Err(e) = std::fs::write(&partial_path, data) {
+        let _ = std::fs::remove_file(&partial_path);
+        return Err(e.into());
+    }
+    std::fs::rename(&partial_path, path)?;
+    Ok(())
+}
+
+/// Delete frames left half-written in `day_dir`, returning how many were removed
+fn remove_partial_frames(day_dir: &Path) -> std::io::Result<usize> {
+    if !day_dir.is_dir() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    for entry in std::fs::read_dir(day_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') && name.ends_with(".partial") {
+            std::fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Check whether the mean brightness of the image is below `threshold`.
+/// A threshold of 0.0 never matches, which disables black-frame deletion.
+fn is_frame_black(wand: &MagickWand, threshold: f64, sample_target: usize) -> Result<bool, Error> {
+    // Consider image "all black" if mean brightness is very close to 0
+    // Using a small threshold to account for potential compression artifacts
+    Ok(mean_brightness(wand, sample_target)? < threshold)
+}
+
+/// Mean luminance (0.0 to 1.0) of roughly `sample_target` pixels spread over the image
+fn mean_brightness(wand: &MagickWand, sample_target: usize) -> Result<f64, Error> {
+    let width = wand.get_image_width();
+    let height = wand.get_image_height();
+
+    // Sample a grid of pixels across the image
+    let sample_size = sample_step(width, height, sample_target);
+    let mut total_brightness = 0.0;
+    let mut pixel_count = 0;
+
+    for y in (0..height).step_by(sample_size) {
+        for x in (0..width).step_by(sample_size) {
+            match wand.get_image_pixel_color(x as isize, y as isize) {
+                Some(pixel) => {
+                    // Get RGB values and calculate brightness
+                    let red = pixel.get_red();
+                    let green = pixel.get_green();
+                    let blue = pixel.get_blue();
+
+                    // Calculate luminance (brightness)
+                    let brightness = 0.299 * red + 0.587 * green + 0.114 * blue;
+                    total_brightness += brightness;
+                    pixel_count += 1;
+                }
+                None => {
+                    // Skip pixels that can't be read
+                    continue;
+                }
+            }
+        }
+    }
+
+    if pixel_count == 0 {
+        return Err(Error::UnableToCheckIfImageIsBlack {
+            reason: "No pixels could be sampled".to_string(),
+        });
+    }
+
+    // PixelWand values are typically in the range 0.0 to 1.0
+    Ok(total_brightness / pixel_count as f64)
+}
+
+/// Grid step that samples roughly `sample_target` pixels from a `width` x
+/// `height` image, never less than every pixel
+fn sample_step(width: usize, height: usize, sample_target: usize) -> usize {
+    let pixels_per_sample = (width * height) as f64 / sample_target.max(1) as f64;
+    (pixels_per_sample.sqrt() as usize).max(1)
+}
+
+/// Downscale an image to a small grayscale grid used to compare consecutive frames
+fn frame_signature(frame: &MagickWand) -> Result<Vec<f64>, Error> {
+    // Downscale a copy so the frame itself is written at full size
+    let wand = frame.clone();
+
+    // Box filtering averages each block, which smooths out compression noise
+    wand.resize_image(SIGNATURE_WIDTH, SIGNATURE_HEIGHT, magick_rust::FilterType::Box)
+        .map_err(|e| Error::UnableToCompareFrames {
+            reason: format!("Failed to downscale image: {:?}", e),
+        })?;
+
+    let mut signature = Vec::with_capacity(SIGNATURE_WIDTH * SIGNATURE_HEIGHT);
+    for y in 0..SIGNATURE_HEIGHT {
+        for x in 0..SIGNATURE_WIDTH {
+            if let Some(pixel) = wand.get_image_pixel_color(x as isize, y as isize) {
+                signature.push(
+                    0.299 * pixel.get_red() + 0.587 * pixel.get_green() + 0.114 * pixel.get_blue(),
+                );
+            }
+        }
+    }
+
+    Ok(signature)
+}
+
+/// Mean absolute difference between two frame signatures, in the range 0.0 to 1.0.
+/// Signatures of different sizes are treated as completely different.
+fn signature_difference(a: &[f64], b: &[f64]) -> f64 {
+    if a.is_empty() || a.len() != b.len() {
+        return 1.0;
+    }
+
+    let total: f64 = a.iter().zip(b).map(|(a, b)| (a - b).abs()).sum();
+    total / a.len() as f64
+}
+
+/// Score how much `frame` changed from the last frame scored, as the normalized RMSE
+/// between scaled-down copies, and keep it to score the next frame against. `None`
+/// for the first frame, or when the two differ in size or can't be compared.
+fn score_change(frame: MagickWand, previous_frame: &Mutex<Option<MagickWand>>) -> Option<f64> {
+    let scaled = frame;
+    let width = scaled.get_image_width();
+    let height = scaled.get_image_height();
+    if width > CHANGE_SCORE_WIDTH {
+        let scaled_height = (height * CHANGE_SCORE_WIDTH / width).max(1);
+        if let Err(e) =
+            scaled.resize_image(CHANGE_SCORE_WIDTH, scaled_height, magick_rust::FilterType::Box)
+        {
+            warn!(error = ?e, "Failed to scale frame for change score");
+            return None;
+        }
+    }
+
+    let mut previous = previous_frame.lock().ok()?;
+    let score = previous.as_ref().and_then(|previous| {
+        let same_size = previous.get_image_width() == scaled.get_image_width()
+            && previous.get_image_height() == scaled.get_image_height();
+        same_size.then(|| {
+            let (distortion, _) =
+                scaled.compare_images(previous, magick_rust::MetricType::RootMeanSquared);
+            distortion.clamp(0.0, 1.0)
+        })
+    });
+    *previous = Some(scaled);
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Datelike;
+    use std::fs;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    /// A gray `width` by `height` PNG
+    fn test_png(width: usize, height: usize) -> Vec<u8> {
+        let mut gray = magick_rust::PixelWand::new();
+        gray.set_color("gray").unwrap();
+        let wand = MagickWand::new();
+        wand.new_image(width, height, &gray).unwrap();
+        wand.write_image_blob("PNG").unwrap()
+    }
+
+    #[test]
+    fn test_photographer_new() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf());
+        assert!(photographer.is_ok());
+
+        let photographer = photographer.unwrap();
+        assert!(!photographer.running.load(Ordering::SeqCst));
+        assert_eq!(photographer.get_error_logs().len(), 0);
+    }
+
+    #[test]
+    fn test_photographer_start_stop() {
+        let photographer = Photographer::new().unwrap();
+
+        // Initially should not be running
+        assert!(!photographer.running.load(Ordering::SeqCst));
+
+        // Start the photographer
+        let running_handle = photographer.start();
+        assert!(running_handle.load(Ordering::SeqCst));
+
+        // Stop the photographer
+        photographer.stop();
+        assert!(!photographer.running.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_photographer_pause_resume() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        assert!(!photographer.is_paused());
+
+        photographer.pause();
+        assert!(photographer.is_paused());
+
+        photographer.resume();
+        assert!(!photographer.is_paused());
+    }
+
+    #[test]
+    fn test_photographer_error_logs() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        // Initially should have no error logs
+        assert_eq!(photographer.get_error_logs().len(), 0);
+
+        // Log an error (simulating what would happen during operation)
+        let entry = log_error(&photographer.error_logs, &photographer.db, "Test error".to_string());
+        assert_eq!(entry.error_message, "Test error");
+
+        // Verify we can retrieve the error log
+        let logs = photographer.get_error_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].error_message, "Test error");
+
+        // Clear error logs
+        photographer.clear_error_logs();
+        assert_eq!(photographer.get_error_logs().len(), 0);
+    }
+
+    #[test]
+    fn test_get_error_logs_paginated() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        for i in 0..5 {
+            log_error(&photographer.error_logs, &photographer.db, format!("Error {}", i));
+        }
+
+        let page = photographer.get_error_logs_paginated(0, 2);
+        assert_eq!(page.total, 5);
+        assert_eq!(page.entries.len(), 2);
+        assert_eq!(page.entries[0].error_message, "Error 4");
+        assert_eq!(page.entries[1].error_message, "Error 3");
+
+        let page = photographer.get_error_logs_paginated(3, 10);
+        assert_eq!(page.total, 5);
+        assert_eq!(page.entries.len(), 2);
+        assert_eq!(page.entries[1].error_message, "Error 0");
+    }
+
+    #[test]
+    fn test_get_error_logs_grouped() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        assert!(photographer.get_error_logs_grouped().is_empty());
+
+        for message in ["No window", "No window", "No window", "Disk full", "No window"] {
+            log_error(&photographer.error_logs, &photographer.db, message.to_string());
+        }
+
+        let groups = photographer.get_error_logs_grouped();
+        let summary: Vec<_> = groups
+            .iter()
+            .map(|group| (group.error_message.as_str(), group.count))
+            .collect();
+        assert_eq!(summary, vec![("No window", 3), ("Disk full", 1), ("No window", 1)]);
+        assert!(groups[0].first_timestamp <= groups[0].last_timestamp);
+        assert_eq!(groups[1].first_timestamp, groups[1].last_timestamp);
+    }
+
+    #[test]
+    fn test_photographer_error_logs_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        // Add more than 10000 error logs
+        {
+            let mut logs = photographer.error_logs.lock().unwrap();
+            for i in 0..10002 {
+                logs.push(ErrorLogEntry {
+                    timestamp: Utc::now(),
+                    error_message: format!("Error {}", i),
+                });
+            }
+        }
+
+        // Should be limited to 10000
+        let logs = photographer.error_logs.lock().unwrap().clone();
+        assert_eq!(logs.entries.len(), 10000);
+        // First error should be "Error 2" (0 and 1 should have been removed)
+        assert_eq!(logs.entries[0].error_message, "Error 2");
+    }
+
+    #[test]
+    fn test_set_max_error_logs() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        for i in 0..5 {
+            log_error(&photographer.error_logs, &photographer.db, format!("Error {}", i));
+        }
+
+        // Shrinking the cap drops the oldest entries straight away
+        photographer.set_max_error_logs(3).unwrap();
+        let logs = photographer.error_logs.lock().unwrap().clone();
+        let messages: Vec<_> =
+            logs.entries.iter().map(|entry| entry.error_message.as_str()).collect();
+        assert_eq!(messages, vec!["Error 2", "Error 3", "Error 4"]);
+        assert_eq!(photographer.get_error_logs().len(), 3);
+
+        assert!(matches!(photographer.set_max_error_logs(0), Err(Error::InvalidSetting { .. })));
+        assert_eq!(photographer.get_config().max_error_logs, 3);
+
+        // The cap is saved with the rest of the config
+        let reopened = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(reopened.error_logs.lock().unwrap().capacity, 3);
+    }
+
+    #[test]
+    fn test_error_logs_persist_across_photographers() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+
+        {
+            let photographer = Photographer::with_root_path(root.clone()).unwrap();
+            log_error(&photographer.error_logs, &photographer.db, "Persisted error".to_string());
+        }
+
+        // A fresh photographer (e.g. after an app restart) should still see the error
+        let photographer = Photographer::with_root_path(root).unwrap();
+        let logs = photographer.get_error_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].error_message, "Persisted error");
+    }
+
+    #[test]
+    fn test_export_day_zip() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("Timelapse");
+        let photographer = Photographer::with_root_path(root.clone()).unwrap();
+
+        let now = Local::now();
+        let today = now.date_naive();
+        let day_dir = root.join(today.format("%Y-%m-%d").to_string());
+        fs::create_dir_all(&day_dir).unwrap();
+        fs::write(day_dir.join("00001.png"), vec![1u8; 100]).unwrap();
+        fs::write(day_dir.join("00002.png"), vec![2u8; 100]).unwrap();
+        fs::write(day_dir.join(".DS_Store"), b"not a frame").unwrap();
+        // The same frame name left over from the other layout
+        let monthly_dir = DirLayout::Monthly.day_dir(&root, today);
+        fs::create_dir_all(&monthly_dir).unwrap();
+        fs::write(monthly_dir.join("00001.png"), vec![3u8; 100]).unwrap();
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            db_guard.insert_screenshot(1, now.with_timezone(&Utc), now, None, None, None).unwrap();
+        }
+
+        let output_path = temp_dir.path().join("day.zip");
+        let summary = photographer.export_day_zip(today, output_path.clone())().unwrap();
+        assert_eq!(summary.file_count, 3);
+        assert_eq!(summary.compressed_bytes, fs::metadata(&output_path).unwrap().len());
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&output_path).unwrap()).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["00001-2.png", "00001.png", "00002.png", "manifest.json"]);
+        let mut entry = Vec::new();
+        archive.by_name("00001-2.png").unwrap().read_to_end(&mut entry).unwrap();
+        assert_eq!(entry, vec![3u8; 100]);
+        let manifest: Vec<serde_json::Value> =
+            serde_json::from_reader(archive.by_name("manifest.json").unwrap()).unwrap();
+        assert_eq!(manifest.len(), 1);
+        assert_eq!(manifest[0]["frame_number"], 1);
+
+        let empty_day = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let result = photographer.export_day_zip(empty_day, temp_dir.path().join("empty.zip"))();
+        assert!(matches!(result, Err(Error::NoCapturesForDay { .. })));
+
+        let unwritable = temp_dir.path().join("missing").join("day.zip");
+        let result = photographer.export_day_zip(today, unwritable)();
+        assert!(matches!(result, Err(Error::OutputNotWritable { .. })));
+    }
+
+    #[test]
+    fn test_export_renumbered_sequence() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("Timelapse");
+        let photographer = Photographer::with_root_path(root.clone()).unwrap();
+
+        let today = Local::now().date_naive();
+        let day_dir = root.join(today.format("%Y-%m-%d").to_string());
+        fs::create_dir_all(&day_dir).unwrap();
+        // Gaps from deleted frames, a wider name and a stray file
+        fs::write(day_dir.join("00002.png"), "two").unwrap();
+        fs::write(day_dir.join("00007.png"), "seven").unwrap();
+        fs::write(day_dir.join("000010.png"), "ten").unwrap();
+        fs::write(day_dir.join("00009.png"), "nine").unwrap();
+        fs::write(day_dir.join(".DS_Store"), "not a frame").unwrap();
+
+        let output_dir = temp_dir.path().join("sequence");
+        let export = photographer.export_renumbered_sequence(today, output_dir.clone())().unwrap();
+        assert_eq!(export.frame_count, 4);
+        assert_eq!(export.output_dir, output_dir);
+
+        let mut names: Vec<String> = fs::read_dir(&output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["0001.png", "0002.png", "0003.png", "0004.png"]);
+        assert_eq!(fs::read_to_string(output_dir.join("0003.png")).unwrap(), "nine");
+        assert_eq!(fs::read_to_string(output_dir.join("0004.png")).unwrap(), "ten");
+
+        // Exporting over an earlier sequence is refused rather than mixed in
+        let result = photographer.export_renumbered_sequence(today, output_dir.clone())();
+        assert!(matches!(result, Err(Error::OutputNotWritable { .. })));
+
+        let empty_day = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let result = photographer.export_renumbered_sequence(empty_day, output_dir)();
+        assert!(matches!(result, Err(Error::NoCapturesForDay { .. })));
+    }
+
+    #[test]
+    fn test_export_renumbered_sequence_converts_mixed_formats() {
+        let temp_dir = TempDir::new().unwrap();
+        let day_dir = temp_dir.path().join("2024-03-15");
+        fs::create_dir_all(&day_dir).unwrap();
+        let frame = test_png(100, 80);
+        fs::write(day_dir.join("00001.png"), &frame).unwrap();
+        fs::write(day_dir.join("00002.webp"), &frame).unwrap();
+        fs::write(day_dir.join("00003.png"), &frame).unwrap();
+
+        let output_dir = temp_dir.path().join("sequence");
+        fs::create_dir_all(&output_dir).unwrap();
+        let frames = numbered_frames(&[day_dir]).unwrap();
+        assert_eq!(copy_renumbered(&frames, &output_dir).unwrap(), 3);
+
+        let mut names: Vec<String> = fs::read_dir(&output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["0001.png", "0002.png", "0003.png"]);
+        assert_eq!(fs::read(output_dir.join("0001.png")).unwrap(), frame);
+    }
+
+    #[test]
+    fn test_first_valid_time() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let at = |hour, min, sec| date.and_hms_opt(hour, min, sec).unwrap();
+        // Clocks jump from 02:00 to 03:00, and local times read as UTC otherwise
+        let resolve = |local: NaiveDateTime| {
+            (local < at(2, 0, 0) || local >= at(3, 0, 0)).then(|| Utc.from_utc_datetime(&local))
+        };
+
+        let utc = |local| Utc.from_utc_datetime(&local);
+        assert_eq!(first_valid_time(at(1, 59, 0), resolve), utc(at(1, 59, 0)));
+        assert_eq!(first_valid_time(at(2, 0, 0), resolve), utc(at(3, 0, 0)));
+        assert_eq!(first_valid_time(at(2, 34, 15), resolve), utc(at(3, 0, 0)));
+        assert_eq!(first_valid_time(at(3, 0, 0), resolve), utc(at(3, 0, 0)));
+
+        // Nothing resolves at all
+        assert_eq!(first_valid_time(at(2, 30, 0), |_| None), utc(at(2, 30, 0)));
+    }
+
+    #[test]
+    fn test_frame_at_time() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 12).unwrap();
+        let at = |hour, min| {
+            let time = NaiveTime::from_hms_opt(hour, min, 0).unwrap();
+            Local.from_local_datetime(&date.and_time(time)).earliest().unwrap()
+        };
+        assert_eq!(photographer.frame_at_time(date, at(12, 0).time()).unwrap(), None);
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            for (frame_number, local_time) in [(1, at(9, 0)), (2, at(10, 0)), (3, at(11, 0))] {
+                let created_at = local_time.with_timezone(&Utc);
+                db_guard
+                    .insert_screenshot(frame_number, created_at, local_time, None, None, None)
+                    .unwrap();
+            }
+        }
+
+        let frame_at = |hour, min| {
+            let time = NaiveTime::from_hms_opt(hour, min, 0).unwrap();
+            photographer.frame_at_time(date, time).unwrap().map(|(frame_number, _)| frame_number)
+        };
+        assert_eq!(frame_at(9, 20), Some(1));
+        assert_eq!(frame_at(9, 40), Some(2));
+        assert_eq!(frame_at(10, 0), Some(2));
+        // Clamped to the day's first and last frames
+        assert_eq!(frame_at(0, 0), Some(1));
+        assert_eq!(frame_at(23, 59), Some(3));
+
+        let (_, local_time) = photographer.frame_at_time(date, at(10, 5).time()).unwrap().unwrap();
+        assert_eq!(local_time, at(10, 0).to_rfc3339());
+    }
+
+    #[test]
+    fn test_database_disabled() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let config = TimelapseConfig { database_enabled: false, ..TimelapseConfig::default() };
+        save_config(&root, &config).unwrap();
+
+        let photographer = Photographer::with_root_path(root.clone()).unwrap();
+        assert!(!root.join("screenshots.db").exists());
+
+        let today = Local::now().date_naive();
+        assert!(matches!(photographer.get_day_frames(today), Err(Error::DatabaseDisabled)));
+        assert!(matches!(photographer.get_screenshot_context(1), Err(Error::DatabaseDisabled)));
+        assert!(matches!(photographer.rebuild_database(|_| {})(), Err(Error::DatabaseDisabled)));
+
+        // Browsing days only needs the files
+        let day_dir = root.join(today.format("%Y-%m-%d").to_string());
+        fs::create_dir_all(&day_dir).unwrap();
+        fs::write(day_dir.join("00001.png"), "test").unwrap();
+        assert_eq!(photographer.list_capture_days().unwrap()[0].frame_count, 1);
+
+        // Exporting a day needs its metadata for the manifest
+        let zip_path = root.join("export.zip");
+        assert!(matches!(
+            photographer.export_day_zip(today, zip_path.clone())(),
+            Err(Error::DatabaseDisabled)
+        ));
+        assert!(!zip_path.exists());
+
+        // Turning it back on waits for the next launch
+        photographer.set_database_enabled(true).unwrap();
+        assert!(photographer.get_day_frames(today).is_err());
+        let photographer = Photographer::with_root_path(root.clone()).unwrap();
+        assert!(photographer.get_day_frames(today).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_day_stats() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let photographer = Photographer::with_root_path(root.clone()).unwrap();
+
+        let now = Local::now();
+        let today = now.date_naive();
+        let day_dir = root.join(today.format("%Y-%m-%d").to_string());
+        fs::create_dir_all(&day_dir).unwrap();
+        fs::write(day_dir.join("00001.png"), vec![0u8; 100]).unwrap();
+        fs::write(day_dir.join("00002.png"), vec![0u8; 50]).unwrap();
+
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            db_guard.insert_screenshot(1, now.with_timezone(&Utc), now, None, None, None).unwrap();
+            db_guard.insert_screenshot(2, now.with_timezone(&Utc), now, None, None, None).unwrap();
+        }
+
+        let stats = photographer.get_day_stats(today).unwrap();
+        assert_eq!(stats.frame_count, 2);
+        assert_eq!(stats.total_bytes, 150);
+        assert!(stats.first_frame_time.is_some());
+        assert!(stats.last_frame_time.is_some());
+    }
+
+    #[test]
+    fn test_get_day_stats_empty_day() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let stats = photographer.get_day_stats(date).unwrap();
+        assert_eq!(stats.frame_count, 0);
+        assert_eq!(stats.total_bytes, 0);
+        assert!(stats.first_frame_time.is_none());
+    }
+
+    #[test]
+    fn test_get_activity_heatmap() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        let now = Local::now();
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            db_guard.insert_screenshot(1, now.with_timezone(&Utc), now, None, None, None).unwrap();
+            db_guard.insert_screenshot(2, now.with_timezone(&Utc), now, None, None, None).unwrap();
+        }
+
+        let today = now.date_naive();
+        let heatmap = photographer.get_activity_heatmap(today, today).unwrap();
+        assert_eq!(heatmap.counts.len(), 7);
+        assert!(heatmap.counts.iter().all(|row| row.len() == 24));
+        let weekday = now.weekday().num_days_from_sunday() as usize;
+        assert_eq!(heatmap.counts[weekday][now.hour() as usize], 2);
+        assert_eq!(heatmap.max_count, 2);
+        assert_eq!(heatmap.total, 2);
+
+        let earlier = today - chrono::Duration::days(7);
+        let heatmap = photographer.get_activity_heatmap(earlier, earlier).unwrap();
+        assert_eq!(heatmap.total, 0);
+
+        assert!(photographer.get_activity_heatmap(today, earlier).is_err());
+    }
+
+    #[test]
+    fn test_export_database_csv() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().join("Timelapse")).unwrap();
+
+        let now = Local::now();
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            db_guard.insert_screenshot(1, now.with_timezone(&Utc), now, None, None, None).unwrap();
+            db_guard.insert_screenshot(2, now.with_timezone(&Utc), now, None, None, None).unwrap();
+        }
+
+        // The parent directory doesn't exist yet
+        let output_path = temp_dir.path().join("exports").join("screenshots.csv");
+        let count = photographer.export_database_csv(&output_path).unwrap();
+        assert_eq!(count, 2);
+
+        let csv = fs::read_to_string(&output_path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "frame_number,created_at,local_time");
+        assert!(lines[1].starts_with("1,"));
+        assert!(lines[2].starts_with("2,"));
+    }
+
+    #[test]
+    fn test_delete_day() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let photographer = Photographer::with_root_path(root.clone()).unwrap();
+
+        let now = Local::now();
+        let today = now.date_naive();
+        let day_name = today.format("%Y-%m-%d").to_string();
+
+        let day_dir = root.join(&day_name);
+        fs::create_dir_all(&day_dir).unwrap();
+        fs::write(day_dir.join("00001.png"), "test").unwrap();
+        fs::write(day_dir.join("00002.png"), "test").unwrap();
+
+        let cache_dir = root.join(".cache").join(&day_name);
+        fs::create_dir_all(&cache_dir).unwrap();
+        fs::write(cache_dir.join("frame000001.jpg"), "test").unwrap();
+        let fps_cache_dir = root.join(".cache").join(format!("{}_fps15", day_name));
+        fs::create_dir_all(&fps_cache_dir).unwrap();
+        fs::write(fps_cache_dir.join("frame000001.jpg"), "test").unwrap();
+
+        // Another day's frames should be left alone
+        let other_dir = root.join("2020-01-01");
+        fs::create_dir_all(&other_dir).unwrap();
+        fs::write(other_dir.join("00001.png"), "test").unwrap();
+
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            db_guard.insert_screenshot(1, now.with_timezone(&Utc), now, None, None, None).unwrap();
+            db_guard.insert_screenshot(2, now.with_timezone(&Utc), now, None, None, None).unwrap();
+        }
+
+        let summary = photographer.delete_day(today)().unwrap();
+        assert_eq!(summary.files_removed, 4);
+        assert_eq!(summary.rows_removed, 2);
+        assert!(!day_dir.exists());
+        assert!(!cache_dir.exists());
+        assert!(!fps_cache_dir.exists());
+        assert!(other_dir.join("00001.png").exists());
+    }
+
+    #[test]
+    fn test_delete_day_removes_queued_rows() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        let now = Local::now();
+
+        // Queued by the loop just before the delete, not yet written
+        let writer = photographer.writer.as_ref().unwrap();
+        for frame_number in 1..=20 {
+            writer.insert(ScreenshotRecord {
+                frame_number,
+                created_at: now.with_timezone(&Utc),
+                local_time: now,
+                app_name: None,
+                window_title: None,
+                display: None,
+                thumbnail: None,
+                change_score: None,
+                stage_timings: Vec::new(),
+            });
+        }
+
+        let summary = photographer.delete_day(now.date_naive())().unwrap();
+        assert_eq!(summary.rows_removed, 20);
+        writer.blocking_flush();
+        assert!(photographer.get_day_frames(now.date_naive()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_day_missing() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        let summary = photographer
+            .delete_day(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap())()
+            .unwrap();
+        assert_eq!(summary.files_removed, 0);
+        assert_eq!(summary.rows_removed, 0);
+    }
+
+    #[test]
+    fn test_purge_frames_between() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let photographer = Photographer::with_root_path(root.clone()).unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let day_dir = root.join("2024-03-15");
+        fs::create_dir_all(&day_dir).unwrap();
+
+        // Frames at 01:00, 09:00, 13:00 and 23:00 local time
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            for (frame_number, hour) in [(1, 1), (2, 9), (3, 13), (4, 23)] {
+                let local = date
+                    .and_hms_opt(hour, 30, 0)
+                    .unwrap()
+                    .and_local_timezone(Local)
+                    .unwrap();
+                let utc = local.with_timezone(&Utc);
+                db_guard.insert_screenshot(frame_number, utc, local, None, None, None).unwrap();
+                db_guard.insert_thumbnail(date, frame_number, b"thumb").unwrap();
+                fs::write(day_dir.join(format!("{:05}.png", frame_number)), "test").unwrap();
+            }
+        }
+
+        let summary = photographer.purge_frames_between(date, 9, 14)().unwrap();
+        assert_eq!(summary.files_removed, 2);
+        assert_eq!(summary.rows_removed, 2);
+        assert!(day_dir.join("00001.png").exists());
+        assert!(!day_dir.join("00002.png").exists());
+        assert!(!day_dir.join("00003.png").exists());
+
+        // Wrapping past midnight takes the start and the end of the day
+        let summary = photographer.purge_frames_between(date, 22, 6)().unwrap();
+        assert_eq!(summary.files_removed, 2);
+        assert_eq!(summary.rows_removed, 2);
+        assert!(!day_dir.join("00001.png").exists());
+        assert!(!day_dir.join("00004.png").exists());
+
+        let db_guard = photographer.db.lock().unwrap();
+        assert!(db_guard.get_thumbnail_frame_numbers(date).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_frames() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let photographer = Photographer::with_root_path(root.clone()).unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let day_dir = root.join("2024-03-15");
+        fs::create_dir_all(&day_dir).unwrap();
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            let local = date.and_hms_opt(12, 0, 0).unwrap().and_local_timezone(Local).unwrap();
+            for frame_number in 1..=4 {
+                let utc = local.with_timezone(&Utc);
+                db_guard.insert_screenshot(frame_number, utc, local, None, None, None).unwrap();
+                db_guard.insert_thumbnail(date, frame_number, b"thumb").unwrap();
+                fs::write(day_dir.join(format!("{:05}.png", frame_number)), "test").unwrap();
+            }
+        }
+        // Frame 4's row is gone but its file is still there
+        let (start, end) = local_day_bounds(date);
+        photographer.db.lock().unwrap().delete_frames_between(start, end, &[4]).unwrap();
+
+        assert!(photographer.delete_frame(date, 2)().unwrap());
+        assert!(!day_dir.join("00002.png").exists());
+        assert!(!photographer.delete_frame(date, 2)().unwrap());
+
+        assert_eq!(photographer.delete_frames(date, vec![1, 1, 4, 9])().unwrap(), 2);
+        assert!(!day_dir.join("00001.png").exists());
+        assert!(!day_dir.join("00004.png").exists());
+        assert!(day_dir.join("00003.png").exists());
+
+        let db_guard = photographer.db.lock().unwrap();
+        assert_eq!(db_guard.get_thumbnail_frame_numbers(date).unwrap(), vec![3]);
+        assert_eq!(db_guard.get_capture_bounds().unwrap().2, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_delete_frame_removes_symlink_not_its_target() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let photographer = Photographer::with_root_path(root.clone()).unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let day_dir = root.join("2024-03-15");
+        fs::create_dir_all(&day_dir).unwrap();
+        let outside = root.join("notes.txt");
+        fs::write(&outside, "keep me").unwrap();
+        std::os::unix::fs::symlink(&outside, day_dir.join("00001.png")).unwrap();
+
+        assert!(photographer.delete_frame(date, 1)().unwrap());
+        assert!(fs::symlink_metadata(day_dir.join("00001.png")).is_err());
+        assert!(outside.exists());
+    }
+
+    #[test]
+    fn test_purge_frames_between_invalid_hours() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+
+        assert!(matches!(
+            photographer.purge_frames_between(date, 9, 24)(),
+            Err(Error::InvalidSetting { .. })
+        ));
+    }
+
+    #[test]
+    fn test_local_day_bounds() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let (start, end) = local_day_bounds(date);
+
+        assert!(start < end);
+        assert_eq!(start.with_timezone(&Local).date_naive(), date);
+        assert_eq!(end.with_timezone(&Local).date_naive(), date.succ_opt().unwrap());
+    }
+
+    #[test]
+    fn test_keep_original() {
+        let temp_dir = TempDir::new().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let original = original_path(temp_dir.path(), date, 7);
+        assert!(original.ends_with(".cache/originals/2024-03-15/00007.png"));
+
+        keep_original(&original, b"png").unwrap();
+        assert_eq!(fs::read(&original).unwrap(), b"png");
+    }
+
+    #[test]
+    fn test_masked_original() {
+        let display = FrameDisplay { id: 1, width: 100, height: 80, scale_factor: 1.0 };
+        let region = Rect { x: 0, y: 0, width: 100, height: 80 };
+        let image_data = test_png(100, 80);
+        let capture = |settings: &TimelapseConfig| {
+            let screenshot = CapturedScreenshot {
+                image_data: image_data.clone(),
+                app_name: "App".to_string(),
+                window_title: "Window".to_string(),
+            };
+            FrameCapture::new(screenshot, Local::now(), display, region, None, settings)
+        };
+
+        // Kept as captured when no mask covers the screen
+        let unmasked = TimelapseConfig::default();
+        let original = masked_original(&capture(&unmasked), &unmasked, "00001.png").unwrap();
+        assert_eq!(original, image_data);
+        let off_screen = TimelapseConfig {
+            privacy_masks: vec![PrivacyMask {
+                rect: Rect { x: 200, y: 200, width: 10, height: 10 },
+                display_id: None,
+            }],
+            ..TimelapseConfig::default()
+        };
+        let original = masked_original(&capture(&off_screen), &off_screen, "00001.png").unwrap();
+        assert_eq!(original, image_data);
+
+        let masked = TimelapseConfig {
+            privacy_masks: vec![PrivacyMask {
+                rect: Rect { x: 10, y: 10, width: 20, height: 20 },
+                display_id: None,
+            }],
+            ..TimelapseConfig::default()
+        };
+        assert!(masked_original(&capture(&masked), &masked, "00001.png").is_ok());
+    }
+
+    #[test]
+    fn test_reprocess_frame() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let photographer = Photographer::with_root_path(root.clone()).unwrap();
+        assert!(!photographer.get_config().keep_originals);
+        photographer.set_keep_originals(true).unwrap();
+        assert!(photographer.get_config().keep_originals);
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let day_dir = root.join("2024-03-15");
+        fs::create_dir_all(&day_dir).unwrap();
+        fs::write(day_dir.join("00001.png"), "frame").unwrap();
+
+        assert!(!photographer.reprocess_frame(date, 1)().unwrap());
+        assert_eq!(fs::read(day_dir.join("00001.png")).unwrap(), b"frame");
+
+        // An original without its frame can't be reprocessed
+        keep_original(&original_path(&root, date, 2), b"png").unwrap();
+        assert!(matches!(
+            photographer.reprocess_frame(date, 2)(),
+            Err(Error::FrameNotFound { frame_number: 2, .. })
+        ));
+
+        // With an original, the frame is rendered again in the current format
+        keep_original(&original_path(&root, date, 1), &test_png(100, 80)).unwrap();
+        photographer
+            .update_config(|config| {
+                config.output_format = OutputFormat::WebP { lossless: true, quality: 80 }
+            })
+            .unwrap();
+        assert!(photographer.reprocess_frame(date, 1)().unwrap());
+        assert!(!day_dir.join("00001.png").exists());
+        assert!(day_dir.join("00001.webp").is_file());
+        assert!(original_path(&root, date, 1).is_file());
+    }
+
+    #[test]
+    fn test_set_black_threshold() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        assert_eq!(photographer.get_config().black_threshold, DEFAULT_BLACK_THRESHOLD);
+
+        assert_eq!(photographer.set_black_threshold(0.05).unwrap(), 0.05);
+        assert_eq!(photographer.get_config().black_threshold, 0.05);
+
+        // Out-of-range values are clamped
+        assert_eq!(photographer.set_black_threshold(-1.0).unwrap(), 0.0);
+        assert_eq!(photographer.set_black_threshold(2.5).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_set_black_threshold_rejects_nan() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        assert!(photographer.set_black_threshold(f64::NAN).is_err());
+        assert_eq!(photographer.get_config().black_threshold, DEFAULT_BLACK_THRESHOLD);
+    }
+
+    #[test]
+    fn test_signature_difference() {
+        let black = vec![0.0; 16];
+        let white = vec![1.0; 16];
+        assert_eq!(signature_difference(&black, &black), 0.0);
+        assert_eq!(signature_difference(&black, &white), 1.0);
+
+        // A single changed pixel out of 16 is a small difference
+        let mut nearly_black = black.clone();
+        nearly_black[3] = 0.5;
+        assert!((signature_difference(&black, &nearly_black) - 0.5 / 16.0).abs() < 1e-9);
+
+        // Mismatched or empty signatures never count as duplicates
+        assert_eq!(signature_difference(&black, &white[..8]), 1.0);
+        assert_eq!(signature_difference(&[], &[]), 1.0);
+    }
+
+    #[test]
+    fn test_score_change() {
+        let solid = |color: &str| {
+            let mut fill = magick_rust::PixelWand::new();
+            fill.set_color(color).unwrap();
+            let wand = MagickWand::new();
+            wand.new_image(64, 48, &fill).unwrap();
+            wand
+        };
+        let previous_frame = Mutex::new(None);
+
+        // The first frame has nothing to be compared with
+        assert_eq!(score_change(solid("gray"), &previous_frame), None);
+        assert!(previous_frame.lock().unwrap().is_some());
+
+        assert_eq!(score_change(solid("gray"), &previous_frame), Some(0.0));
+        assert!(score_change(solid("white"), &previous_frame).unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_unrendered_canvas_withheld_when_masked_or_cropped() {
+        let display = FrameDisplay { id: 1, width: 1000, height: 800, scale_factor: 1.0 };
+        let full_screen = Rect { x: 0, y: 0, width: 1000, height: 800 };
+        let capture = |region: Rect, settings: &TimelapseConfig| {
+            let screenshot = CapturedScreenshot {
+                image_data: b"not an image".to_vec(),
+                app_name: "App".to_string(),
+                window_title: "Window".to_string(),
+            };
+            FrameCapture::new(screenshot, Local::now(), display, region, None, settings)
+        };
+        let render_error = || Error::UnableToResizeScreenshot {
+            path: "00001.png".to_string(),
+            reason: "Failed to read image".to_string(),
+        };
+
+        let masked = TimelapseConfig {
+            privacy_masks: vec![PrivacyMask {
+                rect: Rect { x: 10, y: 10, width: 100, height: 100 },
+                display_id: None,
+            }],
+            ..TimelapseConfig::default()
+        };
+        let masked_capture = capture(full_screen, &masked);
+        let result = unrendered_canvas(&masked_capture, "00001.png", &masked, render_error());
+        assert!(matches!(result, Err(Error::UnableToResizeScreenshot { .. })));
+
+        let cropped = TimelapseConfig::default();
+        let region = Rect { x: 0, y: 20, width: 1000, height: 780 };
+        let result =
+            unrendered_canvas(&capture(region, &cropped), "00001.png", &cropped, render_error());
+        assert!(matches!(result, Err(Error::UnableToResizeScreenshot { .. })));
+    }
+
+    #[test]
+    fn test_set_similarity_threshold() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        assert_eq!(
+            photographer.get_config().similarity_threshold,
+            DEFAULT_SIMILARITY_THRESHOLD
+        );
+        assert_eq!(photographer.set_similarity_threshold(0.02).unwrap(), 0.02);
+        assert_eq!(photographer.set_similarity_threshold(-0.5).unwrap(), 0.0);
+        assert!(photographer.set_similarity_threshold(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_set_output_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        // Defaults to the native resolution
+        assert_eq!(photographer.get_config().output_size, None);
+
+        photographer.set_output_size(Some((1920, 1080))).unwrap();
+        assert_eq!(photographer.get_config().output_size, Some((1920, 1080)));
+
+        photographer.set_output_size(None).unwrap();
+        assert_eq!(photographer.get_config().output_size, None);
+    }
+
+    #[test]
+    fn test_frame_target_dimensions() {
+        assert_eq!(FrameTarget::Native.dimensions(3600.0, 2248.0), (3600.0, 2248.0));
+        assert_eq!(FrameTarget::Size(1800, 1124).dimensions(3600.0, 2248.0), (1800.0, 1124.0));
+        assert_eq!(FrameTarget::AspectRatio(16, 9).dimensions(3600.0, 2248.0), (3600.0, 2026.0));
+        assert_eq!(FrameTarget::AspectRatio(1, 1).dimensions(3600.0, 2248.0), (3600.0, 3600.0));
+        assert_eq!(FrameTarget::AspectRatio(9, 16).dimensions(3600.0, 2248.0), (2026.0, 3600.0));
+
+        // The aspect ratio wins over a fixed output size
+        let config = TimelapseConfig {
+            output_size: Some((1280, 720)),
+            aspect_ratio: Some((1, 1)),
+            ..TimelapseConfig::default()
+        };
+        assert_eq!(FrameTarget::from_config(&config), FrameTarget::AspectRatio(1, 1));
+    }
+
+    #[test]
+    fn test_set_aspect_ratio() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        photographer.set_aspect_ratio(Some((16, 9))).unwrap();
+        assert_eq!(photographer.get_config().aspect_ratio, Some((16, 9)));
+
+        assert!(photographer.set_aspect_ratio(Some((0, 9))).is_err());
+        assert!(photographer.set_aspect_ratio(Some((16, 0))).is_err());
+        assert_eq!(photographer.get_config().aspect_ratio, Some((16, 9)));
+
+        photographer.set_aspect_ratio(None).unwrap();
+        assert_eq!(photographer.get_config().aspect_ratio, None);
+    }
+
+    #[test]
+    fn test_set_output_size_rejects_invalid_dimensions() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        assert!(photographer.set_output_size(Some((0, 1080))).is_err());
+        assert!(photographer.set_output_size(Some((1920, 0))).is_err());
+        assert!(photographer.set_output_size(Some((100_000, 1080))).is_err());
+        assert_eq!(photographer.get_config().output_size, None);
+    }
+
+    #[test]
+    fn test_is_app_blocked() {
+        let blocked_apps = vec!["1Password".to_string(), "Safari".to_string()];
+
+        assert!(is_app_blocked("1Password", &blocked_apps));
+        assert!(is_app_blocked("1password", &blocked_apps));
+        assert!(is_app_blocked("SAFARI", &blocked_apps));
+        assert!(is_app_blocked(" Safari ", &blocked_apps));
+        assert!(!is_app_blocked("Code", &blocked_apps));
+        assert!(!is_app_blocked("Safari Technology Preview", &blocked_apps));
+        assert!(!is_app_blocked("Code", &[]));
+    }
+
+    #[test]
+    fn test_set_blocked_apps() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        assert!(photographer.get_config().blocked_apps.is_empty());
+
+        photographer
+            .set_blocked_apps(vec![
+                " Bitwarden ".to_string(),
+                "".to_string(),
+                "Banking".to_string(),
+            ])
+            .unwrap();
+        assert_eq!(
+            photographer.get_config().blocked_apps,
+            vec!["Bitwarden".to_string(), "Banking".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_list_capture_days() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let photographer = Photographer::with_root_path(root.to_path_buf()).unwrap();
+
+        let older = DirLayout::Flat.day_dir(root, NaiveDate::from_ymd_opt(2024, 3, 14).unwrap());
+        let newer = DirLayout::Monthly.day_dir(root, NaiveDate::from_ymd_opt(2024, 3, 15).unwrap());
+        let newer_flat = DirLayout::Flat.day_dir(root, NaiveDate::from_ymd_opt(2024, 3, 15).unwrap());
+        for dir in [&older, &newer, &newer_flat] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(older.join("00001.png"), "test").unwrap();
+        fs::write(newer.join("00001.png"), "test").unwrap();
+        fs::write(newer.join("00002.png"), "test").unwrap();
+        fs::write(newer_flat.join("00003.png"), "test").unwrap();
+
+        // None of these are capture days
+        fs::create_dir_all(root.join(".cache").join("video")).unwrap();
+        fs::create_dir_all(root.join("2024-02-30")).unwrap();
+        fs::create_dir_all(root.join("2024").join("13").join("01")).unwrap();
+        fs::create_dir_all(root.join("exports")).unwrap();
+
+        photographer
+            .label_day(NaiveDate::from_ymd_opt(2024, 3, 15).unwrap(), "client demo")
+            .unwrap();
+
+        let days = photographer.list_capture_days().unwrap();
+        assert_eq!(
+            days,
+            vec![
+                DaySummary {
+                    date: "2024-03-15".to_string(),
+                    frame_count: 3,
+                    label: Some("client demo".to_string()),
+                },
+                DaySummary { date: "2024-03-14".to_string(), frame_count: 1, label: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_label_day() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+
+        photographer.label_day(date, "  client demo ").unwrap();
+        assert_eq!(
+            photographer.get_day_labels().unwrap(),
+            vec![DayLabel { date: "2024-03-15".to_string(), label: "client demo".to_string() }]
+        );
+
+        // A blank label removes it
+        photographer.label_day(date, "   ").unwrap();
+        assert!(photographer.get_day_labels().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_evict_old_screenshots() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let photographer = Photographer::with_root_path(root.to_path_buf()).unwrap();
+
+        let today = Local::now().date_naive();
+        let days_ago = |days: u64| today - chrono::Days::new(days);
+        for date in [today, days_ago(2), days_ago(3), days_ago(10)] {
+            let day_dir = DirLayout::Flat.day_dir(root, date);
+            fs::create_dir_all(&day_dir).unwrap();
+            fs::write(day_dir.join("00001.png"), "test").unwrap();
+
+            let (start, _) = local_day_bounds(date);
+            let db_guard = photographer.db.lock().unwrap();
+            db_guard
+                .insert_screenshot(1, start, start.with_timezone(&Local), None, None, None)
+                .unwrap();
+        }
+
+        // Retention defaults to keeping everything
+        let report = photographer.evict_old_screenshots()().unwrap();
+        assert_eq!(report.days_removed, 0);
+        assert_eq!(photographer.list_capture_days().unwrap().len(), 4);
+
+        // Originals of a retained day stay, and those of an expired day go even
+        // when the day's frames are already gone
+        keep_original(&original_path(root, days_ago(2), 1), b"png").unwrap();
+        keep_original(&original_path(root, days_ago(20), 1), b"png").unwrap();
+
+        // Keep today and the two days before it
+        photographer.set_retention_days(3).unwrap();
+        let report = photographer.evict_old_screenshots()().unwrap();
+        assert_eq!(report.days_removed, 3);
+        assert_eq!(report.files_removed, 3);
+        assert_eq!(report.rows_removed, 2);
+        assert!(original_path(root, days_ago(2), 1).is_file());
+        assert!(!original_path(root, days_ago(20), 1).exists());
+
+        // The oldest kept day's first frame sits exactly on the cutoff
+        let (oldest_start, oldest_end) = local_day_bounds(days_ago(2));
+        let db_guard = photographer.db.lock().unwrap();
+        assert_eq!(db_guard.get_frame_metas_between(oldest_start, oldest_end).unwrap().len(), 1);
+        drop(db_guard);
+
+        let remaining: Vec<String> = photographer
+            .list_capture_days()
+            .unwrap()
+            .into_iter()
+            .map(|day| day.date)
+            .collect();
+        assert_eq!(
+            remaining,
+            vec![
+                today.format("%Y-%m-%d").to_string(),
+                days_ago(2).format("%Y-%m-%d").to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_frame_image() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let photographer = Photographer::with_root_path(root.to_path_buf()).unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let day_dir = DirLayout::Flat.day_dir(root, date);
+        fs::create_dir_all(&day_dir).unwrap();
+        fs::write(day_dir.join("00001.png"), b"png bytes").unwrap();
+        fs::write(day_dir.join("00002.webp"), b"webp bytes").unwrap();
+
+        let image = photographer.get_frame_image(date, 1).unwrap();
+        assert_eq!(image.mime_type, "image/png");
+        assert_eq!(image.data_base64, "cG5nIGJ5dGVz");
+
+        let image = photographer.get_frame_image(date, 2).unwrap();
+        assert_eq!(image.mime_type, "image/webp");
+
+        assert!(matches!(
+            photographer.get_frame_image(date, 3),
+            Err(Error::FrameNotFound { frame_number: 3, .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_get_frame_image_rejects_links_outside_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let photographer = Photographer::with_root_path(root.to_path_buf()).unwrap();
+
+        let secret = outside_dir.path().join("secret.png");
+        fs::write(&secret, "secret").unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let day_dir = DirLayout::Flat.day_dir(root, date);
+        fs::create_dir_all(&day_dir).unwrap();
+        std::os::unix::fs::symlink(&secret, day_dir.join("00001.png")).unwrap();
+
+        assert!(matches!(
+            photographer.get_frame_image(date, 1),
+            Err(Error::PathOutsideTimelapseRoot { .. })
+        ));
+    }
+
+    #[test]
+    fn test_analyze_frame_missing() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        assert!(matches!(
+            photographer.analyze_frame(date, 1),
+            Err(Error::FrameNotFound { frame_number: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_day_frame_paths_spans_layouts_and_formats() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        assert!(matches!(day_frame_paths(root, date), Err(Error::NoCapturesForDay { .. })));
+
+        let flat = DirLayout::Flat.day_dir(root, date);
+        let monthly = DirLayout::Monthly.day_dir(root, date);
+        fs::create_dir_all(&flat).unwrap();
+        fs::create_dir_all(&monthly).unwrap();
+        fs::write(flat.join("00001.png"), "test").unwrap();
+        fs::write(monthly.join("00002.webp"), "test").unwrap();
+        fs::write(flat.join("000010.png"), "test").unwrap();
+        fs::write(flat.join("00003.png"), "test").unwrap();
+        fs::write(flat.join("notes.txt"), "test").unwrap();
+
+        assert_eq!(
+            day_frame_paths(root, date).unwrap(),
+            [
+                flat.join("00001.png"),
+                monthly.join("00002.webp"),
+                flat.join("00003.png"),
+                flat.join("000010.png"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_video_encoding() {
+        assert_eq!(VideoEncoding::new(None, None).unwrap(), VideoEncoding::default());
+
+        let encoding = VideoEncoding::new(Some("libx265".to_string()), Some(28)).unwrap();
+        assert_eq!(encoding.codec, "libx265");
+        assert_eq!(encoding.crf, 28);
+        assert_eq!(VideoEncoding::new(None, Some(0)).unwrap().crf, 0);
+
+        for codec in ["libvpx", "libx264 -vf", "-y", ""] {
+            assert!(matches!(
+                VideoEncoding::new(Some(codec.to_string()), None),
+                Err(Error::InvalidSetting { .. })
+            ));
+        }
+        assert!(matches!(
+            VideoEncoding::new(None, Some(52)),
+            Err(Error::InvalidSetting { .. })
+        ));
+    }
+
+    #[test]
+    fn test_compile_args() {
+        let temp_dir = TempDir::new().unwrap();
+        let list_path = temp_dir.path().join("2024-03-15.txt");
+        let output_path = temp_dir.path().join("2024-03-15.mov");
+
+        let args = compile_args(&list_path, &output_path, 30, &VideoEncoding::default());
+        assert_eq!(args[..4], ["-f", "concat", "-safe", "0"]);
+        let codec = args.iter().position(|arg| arg == "-c:v").unwrap();
+        assert_eq!(args[codec + 1], "libx264");
+        assert_eq!(args[codec + 2..codec + 4], ["-crf", "23"]);
+        assert!(!args.contains(&"hvc1".to_string()));
+        assert_eq!(args.last().unwrap(), &output_path.to_string_lossy());
+
+        let hevc = VideoEncoding::new(Some("libx265".to_string()), Some(30)).unwrap();
+        let args = compile_args(&list_path, &output_path, 30, &hevc);
+        assert!(args.windows(2).any(|pair| pair == ["-tag:v", "hvc1"]));
+    }
+
+    #[test]
+    fn test_concat_list() {
+        assert_eq!(concat_list(&[], 30), "ffconcat version 1.0\n");
+
+        let frames = [
+            PathBuf::from("/tl/2024-03-15/00003.png"),
+            PathBuf::from("/tl/it's/00009.png"),
+        ];
+        assert_eq!(
+            concat_list(&frames, 4),
+            "ffconcat version 1.0\n\
+             file '/tl/2024-03-15/00003.png'\nduration 0.25\n\
+             file '/tl/it'\\''s/00009.png'\nduration 0.25\n\
+             file '/tl/it'\\''s/00009.png'\n"
+        );
+    }
+
+    #[test]
+    fn test_get_keyframes() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        let local = Local::now();
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            let scores = [(1, None), (2, Some(0.3)), (3, Some(0.01)), (4, Some(0.2))];
+            for (frame_number, change_score) in scores {
+                let record = ScreenshotRecord {
+                    frame_number,
+                    created_at: local.with_timezone(&Utc),
+                    local_time: local,
+                    app_name: None,
+                    window_title: None,
+                    display: None,
+                    thumbnail: None,
+                    change_score,
+                    stage_timings: Vec::new(),
+                };
+                db_guard.insert_record(&record).unwrap();
+            }
+        }
+
+        let date = local.date_naive();
+        assert_eq!(photographer.get_keyframes(date, 0.1).unwrap(), vec![2, 4]);
+        assert_eq!(photographer.get_keyframes(date, 0.0).unwrap(), vec![2, 3, 4]);
+        assert!(photographer.get_keyframes(date, 0.5).unwrap().is_empty());
+        assert!(matches!(
+            photographer.get_keyframes(date, 1.5),
+            Err(Error::InvalidSetting { .. })
+        ));
+    }
+
+    #[test]
+    fn test_command_line() {
+        let args = ["-i".to_string(), "/My Frames/*.png".to_string(), "-y".to_string()];
+        assert_eq!(command_line("ffmpeg", &args), "ffmpeg -i '/My Frames/*.png' -y");
+        assert_eq!(command_line("ffmpeg", &["it's".to_string()]), "ffmpeg 'it'\\''s'");
+    }
+
+    #[test]
+    fn test_compile_day_video_missing_day() {
+        let temp_dir = TempDir::new().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+
+        let result =
+            compile_day_video(temp_dir.path(), date, DEFAULT_VIDEO_FPS, &VideoEncoding::default());
+        assert!(matches!(result, Err(Error::NoCapturesForDay { .. })));
+        assert_eq!(
+            day_video_path(temp_dir.path(), date),
+            temp_dir.path().join("2024-03-15.mov")
+        );
+    }
+
+    #[test]
+    fn test_estimate_video() {
+        let temp_dir = TempDir::new().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        assert!(matches!(
+            estimate_video(temp_dir.path(), date, 30),
+            Err(Error::NoCapturesForDay { .. })
+        ));
+
+        let day_dir = temp_dir.path().join("2024-03-15");
+        fs::create_dir_all(&day_dir).unwrap();
+        for frame_number in 1..=60 {
+            fs::write(day_dir.join(format!("{:05}.png", frame_number)), vec![0u8; 1000]).unwrap();
+        }
+        fs::write(day_dir.join("notes.txt"), vec![0u8; 1000]).unwrap();
+        // Frames in the other layout and another format count too
+        let monthly = DirLayout::Monthly.day_dir(temp_dir.path(), date);
+        fs::create_dir_all(&monthly).unwrap();
+        fs::write(monthly.join("00061.webp"), vec![0u8; 1000]).unwrap();
+
+        let estimate = estimate_video(temp_dir.path(), date, 30).unwrap();
+        assert_eq!(estimate.frame_count, 61);
+        assert_eq!(estimate.duration_secs, 61.0 / 30.0);
+        assert_eq!(estimate.estimated_bytes, (61_000.0 * VIDEO_SIZE_FACTOR) as u64);
+
+        assert!(estimate_video(temp_dir.path(), date, 0).is_err());
+    }
+
+    #[test]
+    fn test_generate_day_gif_validation() {
+        let temp_dir = TempDir::new().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+
+        assert!(matches!(
+            generate_day_gif(temp_dir.path(), date, 0, 1),
+            Err(Error::InvalidSetting { .. })
+        ));
+        assert!(matches!(
+            generate_day_gif(temp_dir.path(), date, 480, 1),
+            Err(Error::NoCapturesForDay { .. })
+        ));
+        assert_eq!(day_gif_path(temp_dir.path(), date), temp_dir.path().join("2024-03-15.gif"));
+    }
+
+    #[test]
+    fn test_gif_filter_graph() {
+        let graph = gif_filter_graph(480, 10);
+        assert!(graph.starts_with("select='not(mod(n\\,10))',scale='min(480\\,iw)':-1"));
+        assert!(graph.ends_with("split[a][b];[a]palettegen[p];[b][p]paletteuse"));
+
+        // A skip of 0 keeps every frame rather than dividing by zero
+        assert!(gif_filter_graph(480, 0).starts_with("select='not(mod(n\\,1))'"));
+    }
+
+    #[test]
+    fn test_config_snapshot_survives_poisoned_lock() {
+        let rect = Rect { x: 0, y: 0, width: 100, height: 100 };
+        let config = Arc::new(Mutex::new(TimelapseConfig {
+            privacy_masks: vec![PrivacyMask { rect, display_id: None }],
+            blocked_apps: vec!["Bitwarden".to_string()],
+            ..TimelapseConfig::default()
+        }));
+        let poisoned = Arc::clone(&config);
+        let _ = std::thread::spawn(move || {
+            let _config = poisoned.lock().unwrap();
+            panic!("poison the config lock");
+        })
+        .join();
+        assert!(config.is_poisoned());
+
+        // Captures keep hiding what the user chose to hide
+        let snapshot = config_snapshot(&config);
+        assert_eq!(snapshot.privacy_masks.len(), 1);
+        assert_eq!(snapshot.blocked_apps, vec!["Bitwarden".to_string()]);
+    }
+
+    #[test]
+    fn test_set_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(photographer.get_config(), TimelapseConfig::default());
+
+        let config = TimelapseConfig {
+            black_threshold: 0.2,
+            output_size: Some((1280, 720)),
+            schedule: vec![(9, 17)],
+            resize_filter: ResizeFilter::Lanczos,
+            retention_days: 30,
+            ..TimelapseConfig::default()
+        };
+        photographer.set_config(config.clone()).unwrap();
+        assert_eq!(photographer.get_config(), config);
+        assert!(temp_dir.path().join("config.json").exists());
+
+        // The saved config is picked up by the next photographer
+        let reopened = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(reopened.get_config(), config);
+    }
 
-    // Resize the image maintaining aspect ratio
-    wand.resize_image(new_width, new_height, magick_rust::FilterType::Box)
-        .map_err(|e| Error::UnableToResizeScreenshot {
-            path: file_path.to_string(),
-            reason: format!("Failed to resize image: {:?}", e),
-        })?;
+    #[test]
+    fn test_load_config_missing_file() {
+        let temp_dir = TempDir::new().unwrap();
 
-    // Create a new black canvas of target size
-    let canvas = MagickWand::new();
-    canvas
-        .new_image(
-            target_width as usize,
-            target_height as usize,
-            &magick_rust::PixelWand::new(),
-        )
-        .map_err(|e| Error::UnableToResizeScreenshot {
-            path: file_path.to_string(),
-            reason: format!("Failed to create canvas: {:?}", e),
-        })?;
+        assert_eq!(load_config(temp_dir.path()), TimelapseConfig::default());
+    }
 
-    // Calculate position to center the resized image
-    let x_offset = ((target_width - new_width as f64) / 2.0) as isize;
-    let y_offset = ((target_height - new_height as f64) / 2.0) as isize;
+    #[test]
+    fn test_load_config_corrupt_file() {
+        let temp_dir = TempDir::new().unwrap();
 
-    // Composite the resized image onto the black canvas
-    canvas
-        .compose_images(
-            &wand,
-            magick_rust::CompositeOperator::Over,
-            true,
-            x_offset,
-            y_offset,
-        )
-        .map_err(|e| Error::UnableToResizeScreenshot {
-            path: file_path.to_string(),
-            reason: format!("Failed to composite image: {:?}", e),
-        })?;
+        fs::write(config_path(temp_dir.path()), "{ not json").unwrap();
+        assert_eq!(load_config(temp_dir.path()), TimelapseConfig::default());
 
-    // Write the final image
-    canvas
-        .write_image(file_path)
-        .map_err(|e| Error::UnableToResizeScreenshot {
-            path: file_path.to_string(),
-            reason: format!("Failed to write image: {:?}", e),
-        })?;
+        // Well-formed but out of range
+        fs::write(config_path(temp_dir.path()), r#"{"black_threshold": 7.0}"#).unwrap();
+        assert_eq!(load_config(temp_dir.path()), TimelapseConfig::default());
+    }
+
+    #[test]
+    fn test_load_config_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = TimelapseConfig {
+            similarity_threshold: 0.01,
+            blocked_apps: vec!["1Password".to_string()],
+            dir_layout: DirLayout::Monthly,
+            output_format: OutputFormat::WebP { lossless: false, quality: 80 },
+            battery_interval_secs: Some(30),
+            schedule: vec![(22, 6)],
+            auto_compile: true,
+            ..TimelapseConfig::default()
+        };
+
+        save_config(temp_dir.path(), &config).unwrap();
+        assert_eq!(load_config(temp_dir.path()), config);
+
+        // Settings added after the file was written take their defaults
+        fs::write(config_path(temp_dir.path()), r#"{"retention_days": 14}"#).unwrap();
+        let loaded = load_config(temp_dir.path());
+        assert_eq!(loaded.retention_days, 14);
+        assert_eq!(loaded.black_threshold, DEFAULT_BLACK_THRESHOLD);
+    }
+
+    #[test]
+    fn test_replace_saved_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("Timelapse");
+
+        // Invalid settings aren't saved
+        let invalid = TimelapseConfig { black_threshold: 1.5, ..TimelapseConfig::default() };
+        assert!(matches!(
+            replace_saved_config(&root, &invalid),
+            Err(Error::InvalidSetting { .. })
+        ));
+        assert!(!config_path(&root).exists());
+
+        // Saved without opening the database
+        let config = TimelapseConfig { retention_days: 30, ..TimelapseConfig::default() };
+        replace_saved_config(&root, &config).unwrap();
+        assert_eq!(load_config(&root), config);
+        assert!(!root.join("screenshots.db").exists());
+    }
+
+    #[test]
+    fn test_setters_persist_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        photographer.set_black_threshold(0.05).unwrap();
+        photographer.set_blocked_apps(vec!["Messages".to_string()]).unwrap();
+        photographer.set_schedule(vec![(9, 17)]).unwrap();
+        photographer.set_retention_days(7).unwrap();
+
+        let reopened = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        let config = reopened.get_config();
+        assert_eq!(config.black_threshold, 0.05);
+        assert_eq!(config.blocked_apps, vec!["Messages".to_string()]);
+        assert_eq!(config.schedule, vec![(9, 17)]);
+        assert_eq!(config.retention_days, 7);
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        type Setter = fn(&Photographer) -> Result<(), Error>;
+        type Check = fn(&TimelapseConfig) -> bool;
+        // Each setting's setter, values it refuses, how to put it back to its
+        // default, and whether the setter's change is in a config
+        let cases: Vec<(&str, Setter, Vec<Setter>, Setter, Check)> = vec![
+            (
+                "schedule",
+                |p| p.set_schedule(vec![(22, 6), (9, 17)]),
+                vec![|p| p.set_schedule(vec![(9, 24)])],
+                |p| p.clear_schedule(),
+                |c| c.schedule == vec![(22, 6), (9, 17)],
+            ),
+            (
+                "interval schedule",
+                |p| p.set_interval_schedule(vec![(9, 17, 1), (18, 23, 60)]),
+                vec![
+                    |p| p.set_interval_schedule(vec![(9, 24, 1)]),
+                    |p| p.set_interval_schedule(vec![(9, 17, 0)]),
+                    |p| p.set_interval_schedule(vec![(9, 17, MAX_BATTERY_INTERVAL_SECS + 1)]),
+                ],
+                |p| p.set_interval_schedule(Vec::new()),
+                |c| c.interval_schedule == vec![(9, 17, 1), (18, 23, 60)],
+            ),
+            (
+                "auto compile",
+                |p| p.set_auto_compile(true),
+                vec![],
+                |p| p.set_auto_compile(false),
+                |c| c.auto_compile,
+            ),
+            (
+                "capture cursor",
+                |p| p.set_capture_cursor(true),
+                vec![],
+                |p| p.set_capture_cursor(false),
+                |c| c.capture_cursor,
+            ),
+            (
+                "resize filter",
+                |p| p.set_resize_filter(ResizeFilter::Lanczos),
+                vec![],
+                |p| p.set_resize_filter(ResizeFilter::Box),
+                |c| c.resize_filter == ResizeFilter::Lanczos,
+            ),
+            (
+                "privacy masks",
+                |p| {
+                    let rect = Rect { x: 1600, y: 800, width: 320, height: 280 };
+                    p.set_privacy_masks(vec![PrivacyMask { rect, display_id: Some(2) }])
+                },
+                vec![|p| {
+                    let rect = Rect { x: 1600, y: 800, width: 0, height: 280 };
+                    p.set_privacy_masks(vec![PrivacyMask { rect, display_id: None }])
+                }],
+                |p| p.clear_privacy_masks(),
+                |c| c.privacy_masks.len() == 1 && c.privacy_masks[0].display_id == Some(2),
+            ),
+            (
+                "logical resolution",
+                |p| p.set_capture_logical_resolution(true),
+                vec![],
+                |p| p.set_capture_logical_resolution(false),
+                |c| c.capture_logical_resolution,
+            ),
+            (
+                "idle pause",
+                |p| p.set_idle_pause(true, 300),
+                vec![|p| p.set_idle_pause(true, 0)],
+                // The threshold is ignored when turning it off
+                |p| p.set_idle_pause(false, 0),
+                |c| c.idle_pause_secs == Some(300),
+            ),
+            (
+                "black backoff",
+                |p| p.set_black_backoff(2),
+                vec![
+                    |p| p.set_black_backoff(0),
+                    |p| p.set_black_backoff(MAX_BLACK_BACKOFF_SECS + 1),
+                ],
+                |p| p.set_black_backoff(DEFAULT_BLACK_BACKOFF_SECS),
+                |c| c.black_backoff_secs == 2,
+            ),
+            (
+                "png compression level",
+                |p| p.set_png_compression_level(9),
+                vec![|p| p.set_png_compression_level(10)],
+                |p| p.set_png_compression_level(DEFAULT_PNG_COMPRESSION_LEVEL),
+                |c| c.png_compression_level == 9,
+            ),
+            (
+                "capture crop",
+                |p| p.set_capture_crop(EdgeCrop { top: 25, bottom: 0, left: 0, right: 0 }),
+                vec![],
+                |p| p.set_capture_crop(EdgeCrop::default()),
+                |c| c.capture_crop == EdgeCrop { top: 25, bottom: 0, left: 0, right: 0 },
+            ),
+            (
+                "min free space",
+                |p| p.set_min_free_space(0),
+                vec![],
+                |p| p.set_min_free_space(DEFAULT_MIN_FREE_BYTES),
+                |c| c.min_free_bytes == 0,
+            ),
+            (
+                "filename width",
+                |p| p.set_filename_width(8),
+                vec![
+                    |p| p.set_filename_width(0),
+                    |p| p.set_filename_width(4),
+                    |p| p.set_filename_width(11),
+                ],
+                |p| p.set_filename_width(DEFAULT_FILENAME_WIDTH),
+                |c| c.filename_width == 8,
+            ),
+        ];
+
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        for (name, set, refused, _, check) in &cases {
+            assert!(!check(&photographer.get_config()), "{} starts changed", name);
+            for refuse in refused {
+                let result = refuse(&photographer);
+                assert!(matches!(result, Err(Error::InvalidSetting { .. })), "{} accepted", name);
+            }
+            assert!(!check(&photographer.get_config()), "{} changed by a refused value", name);
+
+            set(&photographer).unwrap();
+            assert!(check(&photographer.get_config()), "{} not changed", name);
+        }
+
+        // Every change is saved with the rest of the settings
+        let reloaded = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        for (name, _, _, reset, check) in &cases {
+            assert!(check(&reloaded.get_config()), "{} not saved", name);
+            reset(&reloaded).unwrap();
+        }
+        assert_eq!(reloaded.get_config(), TimelapseConfig::default());
+    }
+
+    #[test]
+    fn test_update_config_reports_unsaved_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        // A directory in the way of config.json makes every save fail
+        let config_path = config_path(temp_dir.path());
+        let _ = fs::remove_file(&config_path);
+        fs::create_dir_all(config_path.join("blocker")).unwrap();
+
+        assert!(photographer.set_blocked_apps(vec!["Bitwarden".to_string()]).is_err());
+        assert!(photographer.get_config().blocked_apps.is_empty());
+    }
+
+    #[test]
+    fn test_is_idle_paused() {
+        let mut config = TimelapseConfig::default();
+        assert!(!config.is_idle_paused(3600.0));
+
+        config.idle_pause_secs = Some(300);
+        assert!(!config.is_idle_paused(299.0));
+        assert!(config.is_idle_paused(300.0));
+    }
+
+    #[test]
+    fn test_is_low_on_space() {
+        let mut config = TimelapseConfig { min_free_bytes: 1000, ..TimelapseConfig::default() };
+        assert!(config.is_low_on_space(999));
+        assert!(!config.is_low_on_space(1000));
+
+        // A minimum of 0 never waits
+        config.min_free_bytes = 0;
+        assert!(!config.is_low_on_space(0));
+    }
+
+    #[test]
+    fn test_black_backoff() {
+        let config = TimelapseConfig { black_backoff_secs: 2, ..TimelapseConfig::default() };
+        assert_eq!(config.black_backoff(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_set_config_rejects_invalid() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        let invalid = [
+            TimelapseConfig { black_threshold: 1.5, ..TimelapseConfig::default() },
+            TimelapseConfig { similarity_threshold: f64::NAN, ..TimelapseConfig::default() },
+            TimelapseConfig { black_sample_target: 0, ..TimelapseConfig::default() },
+            TimelapseConfig { output_size: Some((0, 720)), ..TimelapseConfig::default() },
+            TimelapseConfig { battery_interval_secs: Some(0), ..TimelapseConfig::default() },
+            TimelapseConfig { schedule: vec![(9, 24)], ..TimelapseConfig::default() },
+            TimelapseConfig { interval_schedule: vec![(9, 17, 0)], ..TimelapseConfig::default() },
+        ];
+        for config in invalid {
+            assert!(matches!(
+                photographer.set_config(config),
+                Err(Error::InvalidSetting { .. })
+            ));
+        }
+
+        // Nothing was applied or saved
+        assert_eq!(photographer.get_config(), TimelapseConfig::default());
+        assert!(!temp_dir.path().join("config.json").exists());
+    }
+
+    #[test]
+    fn test_frame_capture_logical_size() {
+        let logical_size = |capture_logical_resolution| {
+            let settings =
+                TimelapseConfig { capture_logical_resolution, ..TimelapseConfig::default() };
+            let display = FrameDisplay { id: 1, width: 1440, height: 900, scale_factor: 2.0 };
+            let screenshot = CapturedScreenshot {
+                image_data: Vec::new(),
+                app_name: "App".to_string(),
+                window_title: "Window".to_string(),
+            };
+            let region = Rect { x: 0, y: 0, width: 1440, height: 900 };
+            FrameCapture::new(screenshot, Local::now(), display, region, None, &settings)
+                .logical_size
+        };
+
+        assert_eq!(logical_size(false), None);
+        assert_eq!(logical_size(true), Some((1440, 900)));
+    }
+
+    #[test]
+    fn test_privacy_mask_without_display_id() {
+        // Masks saved before they had a display id apply to every screen
+        let saved: PrivacyMask =
+            serde_json::from_str(r#"{"x": 10, "y": 20, "width": 30, "height": 40}"#).unwrap();
+        assert_eq!(saved.rect, Rect { x: 10, y: 20, width: 30, height: 40 });
+        assert_eq!(saved.display_id, None);
+        assert!(saved.applies_to(1));
+    }
+
+    #[test]
+    fn test_resize_filter_from_str() {
+        assert_eq!("box".parse::<ResizeFilter>().unwrap(), ResizeFilter::Box);
+        assert_eq!("Lanczos".parse::<ResizeFilter>().unwrap(), ResizeFilter::Lanczos);
+        assert_eq!(" catrom ".parse::<ResizeFilter>().unwrap(), ResizeFilter::Catrom);
+        assert_eq!("TRIANGLE".parse::<ResizeFilter>().unwrap(), ResizeFilter::Triangle);
+        assert!(matches!(
+            "bicubic".parse::<ResizeFilter>(),
+            Err(Error::InvalidSetting { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cursor_fraction() {
+        let screen = (1920, 0, 1440, 900);
+
+        assert_eq!(cursor_fraction((1920.0, 0.0), screen), Some((0.0, 0.0)));
+        assert_eq!(cursor_fraction((2640.0, 450.0), screen), Some((0.5, 0.5)));
+        // On the neighbouring screen
+        assert_eq!(cursor_fraction((100.0, 450.0), screen), None);
+        assert_eq!(cursor_fraction((3360.0, 450.0), screen), None);
+        assert_eq!(cursor_fraction((0.0, 0.0), (0, 0, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_rect_fraction_of() {
+        let mask = Rect { x: 960, y: 0, width: 480, height: 270 };
+        let screen = Rect { x: 0, y: 0, width: 1920, height: 1080 };
+        assert_eq!(mask.fraction_of(screen), Some((0.5, 0.0, 0.25, 0.25)));
+        assert_eq!(mask.fraction_of(Rect { width: 0, ..screen }), None);
+
+        // Only the part inside a cropped region is kept, relative to that region
+        let region = Rect { x: 1200, y: 0, width: 480, height: 540 };
+        assert_eq!(mask.fraction_of(region), Some((0.0, 0.0, 0.5, 0.5)));
+        assert_eq!(mask.fraction_of(Rect { x: 0, y: 540, width: 960, height: 540 }), None);
+    }
+
+    #[test]
+    fn test_window_region() {
+        let screen = (1920, 0, 1920, 1080);
+        assert_eq!(
+            window_region((2020, 100, 800, 600), screen),
+            Some(Rect { x: 100, y: 100, width: 800, height: 600 })
+        );
+        // Clamped where the window hangs off the screen's edges
+        assert_eq!(
+            window_region((1820, -50, 400, 300), screen),
+            Some(Rect { x: 0, y: 0, width: 300, height: 250 })
+        );
+        assert_eq!(window_region((2020, 100, 0, 600), screen), None);
+        assert_eq!(window_region((0, 100, 800, 600), screen), None);
+    }
+
+    #[test]
+    fn test_edge_crop_apply() {
+        assert_eq!(
+            EdgeCrop::default().apply(1920, 1080),
+            Rect { x: 0, y: 0, width: 1920, height: 1080 }
+        );
+
+        let menu_bar_and_dock = EdgeCrop { top: 25, bottom: 70, left: 0, right: 0 };
+        assert_eq!(
+            menu_bar_and_dock.apply(1440, 900),
+            Rect { x: 0, y: 25, width: 1440, height: 805 }
+        );
+
+        // Crops past the screen's size leave a single pixel
+        let too_large = EdgeCrop { top: 500, bottom: 500, left: 2000, right: 10 };
+        assert_eq!(too_large.apply(1920, 900), Rect { x: 1919, y: 500, width: 1, height: 1 });
+    }
+
+    #[test]
+    fn test_crop_pixels() {
+        assert_eq!(crop_pixels((0.25, 0.5, 0.5, 0.5), 3840, 2160), (960, 1080, 1920, 1080));
+        // Never empty or past the image's edges
+        assert_eq!(crop_pixels((0.999, 0.0, 0.0001, 1.5), 100, 100), (99, 0, 1, 100));
+    }
+
+    #[test]
+    fn test_set_capture_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(photographer.get_config().capture_mode, CaptureMode::FocusedScreen);
+
+        photographer.set_capture_mode(CaptureMode::ActiveWindow).unwrap();
+        assert_eq!(photographer.get_config().capture_mode, CaptureMode::ActiveWindow);
+    }
+
+    #[test]
+    fn test_set_capture_display() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        // A display that isn't connected is refused
+        assert!(photographer.set_capture_display(Some(u32::MAX)).is_err());
+        assert_eq!(photographer.get_config().capture_mode, CaptureMode::FocusedScreen);
+
+        // The chosen display is saved with the rest of the settings
+        photographer.set_capture_mode(CaptureMode::FixedDisplay(2)).unwrap();
+        let reloaded = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(reloaded.get_config().capture_mode, CaptureMode::FixedDisplay(2));
+
+        photographer.set_capture_display(None).unwrap();
+        assert_eq!(photographer.get_config().capture_mode, CaptureMode::FocusedScreen);
+    }
+
+    #[test]
+    fn test_displays_changed() {
+        let display = |id, x| DisplaySummary {
+            id,
+            x,
+            y: 0,
+            width: 1920,
+            height: 1080,
+            scale_factor: 1.0,
+            is_primary: id == 1,
+        };
+        let docked = sorted_display_ids(&[display(2, 1920), display(1, 0)]);
+        assert_eq!(docked, vec![1, 2]);
+
+        assert!(!displays_changed(None, &docked));
+        assert!(!displays_changed(Some(&[1, 2]), &docked));
+        assert!(displays_changed(Some(&[1]), &docked));
+        assert!(displays_changed(Some(&[1, 2]), &sorted_display_ids(&[display(1, 0)])));
+    }
+
+    #[test]
+    fn test_check_capture_display() {
+        assert!(check_capture_display(CaptureMode::FixedDisplay(2), &[1, 2]).is_ok());
+        assert!(check_capture_display(CaptureMode::FocusedScreen, &[]).is_ok());
+        assert!(matches!(
+            check_capture_display(CaptureMode::FixedDisplay(3), &[1, 2]),
+            Err(Error::InvalidSetting { .. })
+        ));
+    }
+
+    #[test]
+    fn test_missing_fixed_display() {
+        assert_eq!(missing_fixed_display(CaptureMode::FixedDisplay(2), &[1, 2]), None);
+        assert_eq!(missing_fixed_display(CaptureMode::FixedDisplay(3), &[1, 2]), Some(3));
+        assert_eq!(missing_fixed_display(CaptureMode::FixedDisplay(3), &[]), Some(3));
+        assert_eq!(missing_fixed_display(CaptureMode::FocusedScreen, &[]), None);
+        assert_eq!(missing_fixed_display(CaptureMode::ActiveWindow, &[1]), None);
+    }
+
+    #[test]
+    fn test_frame_capture_masks_by_display() {
+        let rect = Rect { x: 0, y: 0, width: 500, height: 400 };
+        let settings = TimelapseConfig {
+            privacy_masks: vec![
+                PrivacyMask { rect, display_id: None },
+                PrivacyMask { rect, display_id: Some(1) },
+                PrivacyMask { rect, display_id: Some(2) },
+            ],
+            ..TimelapseConfig::default()
+        };
+        let masks_on = |id: u32| {
+            let display = FrameDisplay { id, width: 1000, height: 800, scale_factor: 1.0 };
+            let screenshot = CapturedScreenshot {
+                image_data: Vec::new(),
+                app_name: "App".to_string(),
+                window_title: "Window".to_string(),
+            };
+            let region = Rect { x: 0, y: 0, width: 1000, height: 800 };
+            FrameCapture::new(screenshot, Local::now(), display, region, None, &settings)
+                .masks
+                .len()
+        };
+
+        assert_eq!(masks_on(1), 2);
+        assert_eq!(masks_on(2), 2);
+        assert_eq!(masks_on(3), 1);
+    }
+
+    #[test]
+    fn test_png_quality() {
+        assert_eq!(png_quality(DEFAULT_PNG_COMPRESSION_LEVEL), 75);
+        assert_eq!(png_quality(0), 5);
+        assert_eq!(png_quality(9), 95);
+    }
+
+    #[test]
+    fn test_set_timestamp_overlay() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        let config = photographer.get_config();
+        assert!(!config.timestamp_overlay);
+        assert_eq!(config.timestamp_format, DEFAULT_TIMESTAMP_FORMAT);
+        assert_eq!(config.timestamp_corner, OverlayCorner::BottomRight);
+
+        photographer
+            .set_timestamp_overlay(true, "%H:%M".to_string(), OverlayCorner::TopLeft)
+            .unwrap();
+        let config = photographer.get_config();
+        assert!(config.timestamp_overlay);
+        assert_eq!(config.timestamp_format, "%H:%M");
+        assert_eq!(config.timestamp_corner, OverlayCorner::TopLeft);
+
+        for format in ["", "%Q"] {
+            let corner = OverlayCorner::TopLeft;
+            assert!(photographer.set_timestamp_overlay(true, format.to_string(), corner).is_err());
+        }
+        assert_eq!(photographer.get_config().timestamp_format, "%H:%M");
+    }
+
+    #[test]
+    fn test_thumbnails() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let photographer = Photographer::with_root_path(root.to_path_buf()).unwrap();
+        photographer.set_generate_thumbnails(true).unwrap();
+        assert!(photographer.get_config().generate_thumbnails);
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        assert!(matches!(
+            photographer.get_thumbnail(date, 1),
+            Err(Error::ThumbnailNotFound { frame_number: 1, .. })
+        ));
+
+        photographer.db.lock().unwrap().insert_thumbnail(date, 1, b"jpeg bytes").unwrap();
+        let thumbnail = photographer.get_thumbnail(date, 1).unwrap();
+        assert_eq!(thumbnail.data_base64, "anBlZyBieXRlcw==");
+        assert_eq!(thumbnail.mime_type, "image/jpeg");
+
+        // Frames that already have thumbnails are left alone
+        let day_dir = DirLayout::Flat.day_dir(root, date);
+        fs::create_dir_all(&day_dir).unwrap();
+        fs::write(day_dir.join("00001.png"), "frame").unwrap();
+        fs::write(day_dir.join("notes.txt"), "not a frame").unwrap();
+        assert_eq!(photographer.backfill_thumbnails()().unwrap(), 0);
+
+        // Frames without one get a thumbnail
+        fs::write(day_dir.join("00002.png"), test_png(64, 48)).unwrap();
+        assert_eq!(photographer.backfill_thumbnails()().unwrap(), 1);
+        assert!(photographer.get_thumbnail(date, 2).is_ok());
+        assert_eq!(photographer.backfill_thumbnails()().unwrap(), 0);
+
+        // A poisoned database lock is an error rather than a silent skip
+        let db = Arc::clone(&photographer.db);
+        let _ = std::thread::spawn(move || {
+            let _db_guard = db.lock().unwrap();
+            panic!("poison the database lock");
+        })
+        .join();
+        fs::write(day_dir.join("00003.png"), test_png(64, 48)).unwrap();
+        assert!(matches!(
+            photographer.backfill_thumbnails()(),
+            Err(Error::DatabaseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_is_within_schedule() {
+        assert!(is_within_schedule(&[], 3));
+
+        let work_hours = [(9, 17)];
+        assert!(!is_within_schedule(&work_hours, 8));
+        assert!(is_within_schedule(&work_hours, 9));
+        assert!(is_within_schedule(&work_hours, 16));
+        assert!(!is_within_schedule(&work_hours, 17));
+
+        let overnight = [(22, 6)];
+        assert!(is_within_schedule(&overnight, 23));
+        assert!(is_within_schedule(&overnight, 0));
+        assert!(is_within_schedule(&overnight, 5));
+        assert!(!is_within_schedule(&overnight, 6));
+        assert!(!is_within_schedule(&overnight, 21));
+
+        let split = [(9, 12), (13, 18)];
+        assert!(is_within_schedule(&split, 10));
+        assert!(!is_within_schedule(&split, 12));
+        assert!(is_within_schedule(&split, 13));
+
+        assert!(is_within_schedule(&[(7, 7)], 2));
+    }
+
+    #[test]
+    fn test_scheduled_interval_secs() {
+        assert_eq!(scheduled_interval_secs(&[], 10), CAPTURE_INTERVAL_SECS);
+
+        let schedule = [(9, 17, 1), (18, 2, 60), (10, 12, 5)];
+        assert_eq!(scheduled_interval_secs(&schedule, 8), CAPTURE_INTERVAL_SECS);
+        assert_eq!(scheduled_interval_secs(&schedule, 9), 1);
+        assert_eq!(scheduled_interval_secs(&schedule, 17), CAPTURE_INTERVAL_SECS);
+        assert_eq!(scheduled_interval_secs(&schedule, 20), 60);
+        assert_eq!(scheduled_interval_secs(&schedule, 1), 60);
+        assert_eq!(scheduled_interval_secs(&schedule, 2), CAPTURE_INTERVAL_SECS);
+        // Overlapping ranges use the first match
+        assert_eq!(scheduled_interval_secs(&schedule, 11), 1);
+    }
+
+    #[test]
+    fn test_day_to_compile() {
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        let auto_compile = TimelapseConfig { auto_compile: true, ..TimelapseConfig::default() };
+
+        let mut current_day = monday;
+        assert_eq!(day_to_compile(&mut current_day, monday, &auto_compile), None);
+        assert_eq!(day_to_compile(&mut current_day, tuesday, &auto_compile), Some(monday));
+        assert_eq!(current_day, tuesday);
+
+        // Without auto compile the day still rolls over
+        let mut current_day = monday;
+        let manual = TimelapseConfig::default();
+        assert_eq!(day_to_compile(&mut current_day, tuesday, &manual), None);
+        assert_eq!(current_day, tuesday);
+    }
+
+    #[test]
+    fn test_until_next_minute() {
+        let now = Local.with_ymd_and_hms(2024, 3, 15, 10, 30, 45).unwrap();
+        assert_eq!(until_next_minute(now), Duration::from_secs(15));
+
+        let now = Local.with_ymd_and_hms(2024, 3, 15, 10, 30, 0).unwrap();
+        assert_eq!(until_next_minute(now), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_optimize_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            let now = Utc::now();
+            for frame_number in 1..=200 {
+                let local_time = now.with_timezone(&Local);
+                db_guard
+                    .insert_screenshot(frame_number, now, local_time, None, None, None)
+                    .unwrap();
+            }
+            db_guard
+                .delete_screenshots_between(DateTime::UNIX_EPOCH, now + chrono::Duration::days(1))
+                .unwrap();
+        }
+
+        let report = photographer.optimize_database()().unwrap();
+        assert!(report.bytes_before > 0);
+        assert!(report.bytes_after <= report.bytes_before);
+    }
+
+    #[test]
+    fn test_verify_integrity() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let photographer = Photographer::with_root_path(root.to_path_buf()).unwrap();
+
+        let date = Local::now().date_naive();
+        let day_dir = DirLayout::Flat.day_dir(root, date);
+        fs::create_dir_all(&day_dir).unwrap();
+        fs::write(day_dir.join("00001.png"), "frame").unwrap();
+        fs::write(day_dir.join("00003.png"), "frame").unwrap();
+        fs::write(day_dir.join("notes.txt"), "not a frame").unwrap();
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            let now = Utc::now();
+            for frame_number in [1, 2] {
+                let local_time = now.with_timezone(&Local);
+                db_guard
+                    .insert_screenshot(frame_number, now, local_time, None, None, None)
+                    .unwrap();
+            }
+        }
+
+        let report = photographer.verify_integrity(date, false)().unwrap();
+        assert_eq!(report.orphaned_rows, vec![2]);
+        assert_eq!(report.untracked_files, vec!["00003.png".to_string()]);
+        assert!(!report.fixed);
+
+        let report = photographer.verify_integrity(date, true)().unwrap();
+        assert!(report.fixed);
+        let report = photographer.verify_integrity(date, false)().unwrap();
+        assert_eq!(report, IntegrityReport::default());
+    }
+
+    #[test]
+    fn test_rebuild_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let photographer = Photographer::with_root_path(root.to_path_buf()).unwrap();
+
+        let flat_day = DirLayout::Flat.day_dir(root, NaiveDate::from_ymd_opt(2024, 3, 14).unwrap());
+        let monthly_day =
+            DirLayout::Monthly.day_dir(root, NaiveDate::from_ymd_opt(2024, 3, 15).unwrap());
+        fs::create_dir_all(&flat_day).unwrap();
+        fs::create_dir_all(&monthly_day).unwrap();
+        fs::write(flat_day.join("00001.png"), "frame").unwrap();
+        fs::write(flat_day.join("00002.webp"), "frame").unwrap();
+        fs::write(flat_day.join(".00003.png.partial"), "partial").unwrap();
+        fs::write(flat_day.join("notes.txt"), "not a frame").unwrap();
+        fs::write(monthly_day.join("00001.png"), "frame").unwrap();
+
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            let now = Utc::now();
+            db_guard
+                .insert_screenshot(99, now, now.with_timezone(&Local), None, None, None)
+                .unwrap();
+        }
+
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let progress_sink = Arc::clone(&progress);
+        let rebuild = photographer.rebuild_database(move |update| {
+            progress_sink.lock().unwrap().push(update);
+        });
+        assert_eq!(rebuild().unwrap(), 3);
+
+        let progress = progress.lock().unwrap();
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress[1].days_scanned, 2);
+        assert_eq!(progress[1].total_days, 2);
+        assert_eq!(progress[1].frames_found, 3);
+
+        let db_guard = photographer.db.lock().unwrap();
+        assert!(db_guard.get_screenshot_by_frame(99).unwrap().is_none());
+        assert!(db_guard.get_screenshot_by_frame(2).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_get_total_storage() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let photographer = Photographer::with_root_path(root.to_path_buf()).unwrap();
+        let database_bytes = fs::metadata(root.join("screenshots.db")).unwrap().len();
+
+        let flat_day = DirLayout::Flat.day_dir(root, NaiveDate::from_ymd_opt(2024, 3, 14).unwrap());
+        let monthly_day =
+            DirLayout::Monthly.day_dir(root, NaiveDate::from_ymd_opt(2024, 3, 15).unwrap());
+        fs::create_dir_all(&flat_day).unwrap();
+        fs::create_dir_all(&monthly_day).unwrap();
+        fs::write(flat_day.join("00001.png"), vec![0u8; 100]).unwrap();
+        fs::write(monthly_day.join("00001.png"), vec![0u8; 50]).unwrap();
+
+        let cache_dir = root.join(".cache").join("2024-03-14_fps30");
+        fs::create_dir_all(&cache_dir).unwrap();
+        fs::write(cache_dir.join("frame000001.jpg"), vec![0u8; 30]).unwrap();
+
+        fs::write(root.join("2024-03-14.mov"), vec![0u8; 7]).unwrap();
+
+        let log_dir = root.join(LOG_DIR_NAME);
+        fs::create_dir_all(&log_dir).unwrap();
+        fs::write(log_dir.join("timelapse.2024-03-14.log"), vec![0u8; 1000]).unwrap();
+
+        let breakdown = photographer.get_total_storage().unwrap();
+        assert_eq!(breakdown.screenshots_bytes, 150);
+        assert_eq!(breakdown.cache_bytes, 30);
+        assert_eq!(breakdown.database_bytes, database_bytes);
+        assert_eq!(breakdown.total_bytes, 150 + 30 + database_bytes + 7);
+    }
+
+    #[test]
+    fn test_write_file_atomically() {
+        let temp_dir = TempDir::new().unwrap();
+        let frame_path = temp_dir.path().join("00001.png");
+
+        write_file_atomically(&frame_path, b"frame data").unwrap();
+
+        assert_eq!(fs::read(&frame_path).unwrap(), b"frame data");
+        assert!(!partial_frame_path(&frame_path).exists());
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_write_with_retry_exhausts_attempts() {
+        let temp_dir = TempDir::new().unwrap();
+        // The parent directory doesn't exist, so every write fails
+        let frame_path = temp_dir.path().join("missing").join("00001.png");
+
+        let mut calls = 0;
+        let result = write_with_retry(3, Duration::ZERO, || {
+            calls += 1;
+            write_file_atomically(&frame_path, b"frame data")
+        })
+        .await;
+
+        assert!(matches!(result, Err(Error::IoError(_))));
+        assert_eq!(calls, 3);
+        assert!(!frame_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_write_with_retry_recovers() {
+        let temp_dir = TempDir::new().unwrap();
+        let frame_path = temp_dir.path().join("00001.png");
+
+        let mut calls = 0;
+        write_with_retry(3, Duration::ZERO, || {
+            calls += 1;
+            if calls < 2 {
+                return Err(Error::IoError(std::io::Error::other("disk full")));
+            }
+            write_file_atomically(&frame_path, b"frame data")
+        })
+        .await
+        .unwrap();
 
-    Ok(())
-}
+        assert_eq!(calls, 2);
+        assert_eq!(fs::read(&frame_path).unwrap(), b"frame data");
+    }
 
-async fn is_image_all_black(file_path: &str) -> Result<bool, Error> {
-    let wand = MagickWand::new();
+    #[test]
+    fn test_remove_partial_frames() {
+        let temp_dir = TempDir::new().unwrap();
+        let day_dir = temp_dir.path();
 
-    // Read the image
-    wand.read_image(file_path)
-        .map_err(|e| Error::UnableToCheckIfImageIsBlack {
-            reason: format!("Failed to read image: {:?}", e),
-        })?;
+        fs::write(day_dir.join("00001.png"), "test").unwrap();
+        fs::write(partial_frame_path(&day_dir.join("00002.png")), "trunc").unwrap();
 
-    let width = wand.get_image_width();
-    let height = wand.get_image_height();
+        // Partial frames don't affect numbering
+        assert_eq!(
+            next_filename(day_dir, &OutputFormat::Png, DEFAULT_FILENAME_WIDTH).unwrap(),
+            "00002.png"
+        );
 
-    // Sample pixels to check if image is all black
-    // We'll check a grid of pixels across the image
-    let sample_size = 10; // Check every 10th pixel
-    let mut total_brightness = 0.0;
-    let mut pixel_count = 0;
+        assert_eq!(remove_partial_frames(day_dir).unwrap(), 1);
+        assert!(day_dir.join("00001.png").exists());
+        assert_eq!(fs::read_dir(day_dir).unwrap().count(), 1);
 
-    for y in (0..height).step_by(sample_size) {
-        for x in (0..width).step_by(sample_size) {
-            match wand.get_image_pixel_color(x as isize, y as isize) {
-                Some(pixel) => {
-                    // Get RGB values and calculate brightness
-                    let red = pixel.get_red();
-                    let green = pixel.get_green();
-                    let blue = pixel.get_blue();
+        // A day that hasn't been created yet has nothing to clean up
+        assert_eq!(remove_partial_frames(&day_dir.join("missing")).unwrap(), 0);
+    }
 
-                    // Calculate luminance (brightness)
-                    let brightness = 0.299 * red + 0.587 * green + 0.114 * blue;
-                    total_brightness += brightness;
-                    pixel_count += 1;
-                }
-                None => {
-                    // Skip pixels that can't be read
-                    continue;
-                }
-            }
-        }
+    #[tokio::test]
+    async fn test_stop_and_join() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        // Stopping a photographer that was never started returns immediately
+        photographer.stop_and_join().await;
+        assert!(!photographer.running.load(Ordering::SeqCst));
+
+        photographer.start();
+        photographer.stop_and_join().await;
+        assert!(!photographer.running.load(Ordering::SeqCst));
+        assert!(photographer.task.lock().unwrap().is_none());
     }
 
-    if pixel_count == 0 {
-        return Err(Error::UnableToCheckIfImageIsBlack {
-            reason: "No pixels could be sampled".to_string(),
+    #[tokio::test]
+    async fn test_sleep_while_running_wakes_on_stop() {
+        let running = Arc::new(AtomicBool::new(true));
+        let sleeper = tokio::spawn({
+            let running = Arc::clone(&running);
+            async move { sleep_while_running(Duration::from_secs(60), &running).await }
         });
+
+        sleep(Duration::from_millis(50)).await;
+        running.store(false, Ordering::SeqCst);
+        assert!(timeout(STOP_CHECK_INTERVAL * 4, sleeper).await.is_ok());
+
+        // Finishes on time while still running
+        let started = Instant::now();
+        sleep_while_running(Duration::from_millis(20), &AtomicBool::new(true)).await;
+        assert!(started.elapsed() >= Duration::from_millis(20));
     }
 
-    let mean_brightness = total_brightness / pixel_count as f64;
+    #[tokio::test]
+    async fn test_stop_interrupts_error_backoff() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
 
-    // Consider image "all black" if mean brightness is very close to 0
-    // Using a small threshold to account for potential compression artifacts
-    // PixelWand values are typically in the range 0.0 to 1.0
-    Ok(mean_brightness < 0.01)
-}
+        // Captures fail without a screen to capture, so the loop is soon backing off
+        photographer.start();
+        sleep(Duration::from_millis(100)).await;
+        photographer.stop();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use tempfile::TempDir;
+        let task = photographer.task.lock().unwrap().take().unwrap();
+        assert!(timeout(STOP_CHECK_INTERVAL * 4, task).await.is_ok());
+    }
 
     #[test]
-    fn test_photographer_new() {
-        let photographer = Photographer::new();
-        assert!(photographer.is_ok());
+    fn test_settings_snapshot_reflects_setters() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
 
-        let photographer = photographer.unwrap();
-        assert!(!photographer.running.load(Ordering::SeqCst));
-        assert_eq!(photographer.get_error_logs().len(), 0);
+        photographer.set_output_size(Some((1280, 720))).unwrap();
+        photographer.set_blocked_apps(vec!["Safari".to_string()]).unwrap();
+        photographer.set_battery_saver(true, 10).unwrap();
+
+        let settings = photographer.get_config();
+        assert_eq!(settings.output_size, Some((1280, 720)));
+        assert_eq!(settings.blocked_apps, vec!["Safari".to_string()]);
+        assert_eq!(settings.battery_interval_secs, Some(10));
+        assert_eq!(settings.black_threshold, DEFAULT_BLACK_THRESHOLD);
     }
 
     #[test]
-    fn test_photographer_start_stop() {
-        let photographer = Photographer::new().unwrap();
+    fn test_sample_step() {
+        // A 4K frame is sampled on a coarse grid
+        let step = sample_step(3840, 2160, 1000);
+        assert_eq!(step, 91);
+        let samples = 3840_usize.div_ceil(step) * 2160_usize.div_ceil(step);
+        assert!((900..=1100).contains(&samples));
 
-        // Initially should not be running
-        assert!(!photographer.running.load(Ordering::SeqCst));
+        // Small images never drop below sampling every pixel
+        assert_eq!(sample_step(10, 10, 1000), 1);
+        assert_eq!(sample_step(1, 1, 1000), 1);
+        assert_eq!(sample_step(0, 0, 1000), 1);
+        assert_eq!(sample_step(1920, 1080, 0), 1440);
+    }
 
-        // Start the photographer
-        let running_handle = photographer.start();
-        assert!(running_handle.load(Ordering::SeqCst));
+    #[test]
+    fn test_set_black_sample_target() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
 
-        // Stop the photographer
-        photographer.stop();
-        assert!(!photographer.running.load(Ordering::SeqCst));
+        assert_eq!(photographer.get_config().black_sample_target, DEFAULT_BLACK_SAMPLE_TARGET);
+
+        photographer.set_black_sample_target(5000).unwrap();
+        assert_eq!(photographer.get_config().black_sample_target, 5000);
+
+        assert!(photographer.set_black_sample_target(0).is_err());
+        assert_eq!(photographer.get_config().black_sample_target, 5000);
     }
 
     #[test]
-    fn test_photographer_error_logs() {
-        let photographer = Photographer::new().unwrap();
+    fn test_set_battery_saver() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
 
-        // Initially should have no error logs
-        assert_eq!(photographer.get_error_logs().len(), 0);
+        assert_eq!(photographer.get_config().battery_interval_secs, None);
 
-        // Add an error log manually (simulating what would happen during operation)
-        {
-            let mut logs = photographer.error_logs.lock().unwrap();
-            logs.push(ErrorLogEntry {
-                timestamp: Utc::now(),
-                error_message: "Test error".to_string(),
-            });
-        }
+        photographer.set_battery_saver(true, 30).unwrap();
+        assert_eq!(photographer.get_config().battery_interval_secs, Some(30));
 
-        // Verify we can retrieve the error log
-        let logs = photographer.get_error_logs();
-        assert_eq!(logs.len(), 1);
-        assert_eq!(logs[0].error_message, "Test error");
+        // The interval is ignored when disabling
+        photographer.set_battery_saver(false, 0).unwrap();
+        assert_eq!(photographer.get_config().battery_interval_secs, None);
 
-        // Clear error logs
-        photographer.clear_error_logs();
-        assert_eq!(photographer.get_error_logs().len(), 0);
+        assert!(photographer.set_battery_saver(true, 0).is_err());
+        assert!(photographer.set_battery_saver(true, MAX_BATTERY_INTERVAL_SECS + 1).is_err());
+        assert_eq!(photographer.get_config().battery_interval_secs, None);
     }
 
     #[test]
-    fn test_photographer_error_logs_limit() {
-        let photographer = Photographer::new().unwrap();
+    fn test_stage_timer_records_laps_in_order() {
+        let mut timer = StageTimer::start();
+        timer.lap("render");
+        timer.lap("write");
 
-        // Add more than 10000 error logs
-        {
-            let mut logs = photographer.error_logs.lock().unwrap();
-            for i in 0..10002 {
-                logs.push(ErrorLogEntry {
-                    timestamp: Utc::now(),
-                    error_message: format!("Error {}", i),
-                });
+        let stages: Vec<&str> = timer.stages.iter().map(|(stage, _)| *stage).collect();
+        assert_eq!(stages, vec!["render", "write"]);
+        let total: Duration = timer.stages.iter().map(|(_, duration)| *duration).sum();
+        assert!(total <= timer.started.elapsed());
+    }
 
-                // Simulate the limiting behavior from the actual code
-                if logs.len() > 10000 {
-                    logs.remove(0);
-                }
-            }
-        }
+    #[test]
+    fn test_summarize_stage_timings() {
+        assert!(summarize_stage_timings(Vec::new()).is_empty());
 
-        // Should be limited to 10000
-        let logs = photographer.get_error_logs();
-        assert_eq!(logs.len(), 10000);
-        // First error should be "Error 2" (0 and 1 should have been removed)
-        assert_eq!(logs[0].error_message, "Error 2");
+        let mut timings: Vec<(String, f64)> =
+            (1..=20).map(|ms| ("encode".to_string(), ms as f64)).collect();
+        timings.push(("render".to_string(), 30.0));
+        let summaries = summarize_stage_timings(timings);
+
+        assert_eq!(
+            summaries,
+            vec![
+                StageTimingSummary {
+                    stage: "encode".to_string(),
+                    frames: 20,
+                    average_ms: 10.5,
+                    p95_ms: 19.0,
+                },
+                StageTimingSummary {
+                    stage: "render".to_string(),
+                    frames: 1,
+                    average_ms: 30.0,
+                    p95_ms: 30.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_backoff() {
+        assert_eq!(error_backoff(1), Duration::from_secs(2));
+        assert_eq!(error_backoff(2), Duration::from_secs(4));
+        assert_eq!(error_backoff(3), Duration::from_secs(8));
+        assert_eq!(error_backoff(5), Duration::from_secs(32));
+        assert_eq!(error_backoff(6), Duration::from_secs(60));
+        assert_eq!(error_backoff(u32::MAX), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_remaining_interval() {
+        let interval = Duration::from_secs(1);
+        let tick_started = Instant::now();
+        let at = |millis| tick_started + Duration::from_millis(millis);
+
+        assert_eq!(remaining_interval(interval, tick_started, at(0)), Some(interval));
+        assert_eq!(
+            remaining_interval(interval, tick_started, at(300)),
+            Some(Duration::from_millis(700))
+        );
+        // Ticks that overran don't sleep at all
+        assert_eq!(remaining_interval(interval, tick_started, at(1000)), None);
+        assert_eq!(remaining_interval(interval, tick_started, at(2500)), None);
+        // A clock reading from before the tick counts as no time elapsed
+        assert_eq!(remaining_interval(interval, at(100), tick_started), Some(interval));
+    }
+
+    #[test]
+    fn test_parse_power_source() {
+        let on_battery = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1234)\t85%; discharging; 5:12 remaining present: true\n";
+        let on_ac = "Now drawing from 'AC Power'\n -InternalBattery-0 (id=1234)\t100%; charged; 0:00 remaining present: true\n";
+
+        assert_eq!(parse_power_source(on_battery), Some(true));
+        assert_eq!(parse_power_source(on_ac), Some(false));
+        assert_eq!(parse_power_source("Now drawing from 'UPS Power'"), None);
+        assert_eq!(parse_power_source(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_capture_interval_without_battery_saver() {
+        assert_eq!(capture_interval_secs(CAPTURE_INTERVAL_SECS, None).await, CAPTURE_INTERVAL_SECS);
+        assert_eq!(capture_interval_secs(60, None).await, 60);
     }
 
     #[test]
@@ -524,7 +7853,9 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let timelapse_root = temp_dir.path().to_path_buf();
 
-        let result = Photographer::create_day_dir_if_needed(&timelapse_root);
+        let today = Local::now().date_naive();
+        let result =
+            Photographer::create_day_dir_if_needed(&timelapse_root, DirLayout::Flat, today);
         assert!(result.is_ok());
 
         let day_dir = result.unwrap();
@@ -538,12 +7869,82 @@ mod tests {
         assert_eq!(&dir_name[7..8], "-");
     }
 
+    #[test]
+    fn test_create_day_dir_if_needed_monthly() {
+        let temp_dir = TempDir::new().unwrap();
+        let timelapse_root = temp_dir.path().to_path_buf();
+
+        let today = Local::now().date_naive();
+        let day_dir =
+            Photographer::create_day_dir_if_needed(&timelapse_root, DirLayout::Monthly, today)
+                .unwrap();
+        assert!(day_dir.is_dir());
+
+        // Verify the directory structure (YYYY/MM/DD)
+        let expected = timelapse_root
+            .join(today.format("%Y").to_string())
+            .join(today.format("%m").to_string())
+            .join(today.format("%d").to_string());
+        assert_eq!(day_dir, expected);
+    }
+
+    #[test]
+    fn test_existing_day_dirs_checks_both_layouts() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+
+        assert!(existing_day_dirs(root, date).is_empty());
+
+        fs::create_dir_all(root.join("2024").join("03").join("15")).unwrap();
+        assert_eq!(
+            existing_day_dirs(root, date),
+            vec![root.join("2024").join("03").join("15")]
+        );
+
+        fs::create_dir_all(root.join("2024-03-15")).unwrap();
+        assert_eq!(existing_day_dirs(root, date).len(), 2);
+    }
+
+    #[test]
+    fn test_next_frame_filename_uses_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let day_dir = DirLayout::Flat.day_dir(temp_dir.path(), date);
+        fs::create_dir_all(&day_dir).unwrap();
+        let settings = TimelapseConfig::default();
+        let next = || {
+            next_frame_filename(&photographer.db, &day_dir, date, &OutputFormat::Png, &settings)
+                .unwrap()
+        };
+
+        // Rows for the day before and after don't count
+        {
+            let db_guard = photographer.db.lock().unwrap();
+            let (before, after) = (date.pred_opt().unwrap(), date.succ_opt().unwrap());
+            for (frame_number, day) in [(90, before), (41, date), (95, after)] {
+                let (start, _) = local_day_bounds(day);
+                let at = start + chrono::Duration::hours(12);
+                let local = at.with_timezone(&Local);
+                db_guard.insert_screenshot(frame_number, at, local, None, None, None).unwrap();
+            }
+        }
+        // The day's row counts even though its file is gone
+        assert_eq!(next(), "00042.png");
+
+        // Frames the writer hasn't recorded yet count too
+        fs::write(day_dir.join("00042.png"), "test").unwrap();
+        fs::write(day_dir.join("00050.png"), "test").unwrap();
+        assert_eq!(next(), "00051.png");
+    }
+
     #[test]
     fn test_next_filename_empty_dir() {
         let temp_dir = TempDir::new().unwrap();
         let day_dir = temp_dir.path().to_path_buf();
 
-        let result = next_filename(&day_dir);
+        let result = next_filename(&day_dir, &OutputFormat::Png, DEFAULT_FILENAME_WIDTH);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "00001.png");
     }
@@ -558,7 +7959,7 @@ mod tests {
         fs::write(day_dir.join("00002.png"), "test").unwrap();
         fs::write(day_dir.join("00003.jpg"), "test").unwrap();
 
-        let result = next_filename(&day_dir);
+        let result = next_filename(&day_dir, &OutputFormat::Png, DEFAULT_FILENAME_WIDTH);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "00004.png");
     }
@@ -573,12 +7974,221 @@ mod tests {
         fs::write(day_dir.join("00005.png"), "test").unwrap();
         fs::write(day_dir.join("00010.png"), "test").unwrap();
 
-        let result = next_filename(&day_dir);
+        let result = next_filename(&day_dir, &OutputFormat::Png, DEFAULT_FILENAME_WIDTH);
         assert!(result.is_ok());
         // Should be max + 1 = 11
         assert_eq!(result.unwrap(), "00011.png");
     }
 
+    #[test]
+    fn test_next_filename_width() {
+        let temp_dir = TempDir::new().unwrap();
+        let day_dir = temp_dir.path().to_path_buf();
+
+        assert_eq!(next_filename(&day_dir, &OutputFormat::Png, 6).unwrap(), "000001.png");
+
+        // A day with frames keeps their width, whatever the setting
+        fs::write(day_dir.join("000041.png"), "test").unwrap();
+        fs::write(day_dir.join("000042.png"), "test").unwrap();
+        assert_eq!(next_filename(&day_dir, &OutputFormat::Png, 6).unwrap(), "000043.png");
+        assert_eq!(next_filename(&day_dir, &OutputFormat::Png, 5).unwrap(), "000043.png");
+
+        // A day that already mixes widths continues with its latest frame's
+        fs::write(day_dir.join("00043.png"), "test").unwrap();
+        assert_eq!(next_filename(&day_dir, &OutputFormat::Png, 6).unwrap(), "00044.png");
+
+        // Numbers wider than the padding aren't cut short
+        assert_eq!(frame_filename(123_456, &OutputFormat::Png, 5), "123456.png");
+    }
+
+    #[test]
+    fn test_next_filename_webp() {
+        let temp_dir = TempDir::new().unwrap();
+        let day_dir = temp_dir.path().to_path_buf();
+        let webp = OutputFormat::WebP { lossless: true, quality: 100 };
+
+        assert_eq!(next_filename(&day_dir, &webp, DEFAULT_FILENAME_WIDTH).unwrap(), "00001.webp");
+
+        // Numbering continues from frames written in another format
+        fs::write(day_dir.join("00001.png"), "test").unwrap();
+        fs::write(day_dir.join("00002.png"), "test").unwrap();
+        assert_eq!(next_filename(&day_dir, &webp, DEFAULT_FILENAME_WIDTH).unwrap(), "00003.webp");
+
+        fs::write(day_dir.join("00003.webp"), "test").unwrap();
+        assert_eq!(
+            next_filename(&day_dir, &OutputFormat::Png, DEFAULT_FILENAME_WIDTH).unwrap(),
+            "00004.png"
+        );
+    }
+
+    #[test]
+    fn test_capture_health_record() {
+        let start = Utc::now();
+        let mut health = CaptureHealth::default();
+        assert_eq!(health.as_of(start).secs_since_last_frame, None);
+
+        let saved = ScreenshotOutcome::Saved { frame_number: 1, path: PathBuf::from("00001.png") };
+        health.record(&Ok(saved), start);
+        health.record(&Err(Error::UnableToFindHomeDir), start + chrono::Duration::seconds(5));
+        health.record(&Err(Error::UnableToFindHomeDir), start + chrono::Duration::seconds(10));
+
+        let report = health.as_of(start + chrono::Duration::seconds(300));
+        assert_eq!(report.last_frame_at, Some(start));
+        assert_eq!(report.secs_since_last_frame, Some(300));
+        assert_eq!(report.consecutive_errors, 2);
+        assert_eq!(report.last_tick, Some(TickResult::Error));
+
+        health.record(&Ok(ScreenshotOutcome::Black), start + chrono::Duration::seconds(15));
+        assert_eq!(health.consecutive_errors, 0);
+        assert_eq!(health.last_tick, Some(TickResult::Black));
+        assert_eq!(health.last_frame_at, Some(start));
+    }
+
+    #[test]
+    fn test_capture_health_counts_black_frame_runs() {
+        let now = Utc::now();
+        let mut health = CaptureHealth::default();
+
+        for _ in 0..3 {
+            health.record(&Ok(ScreenshotOutcome::Black), now);
+        }
+        // Ticks that didn't look at the screen don't break the run
+        health.record(&Ok(ScreenshotOutcome::Skipped), now);
+        health.record(&Err(Error::UnableToFindHomeDir), now);
+        health.record(&Ok(ScreenshotOutcome::Black), now);
+        assert_eq!(health.consecutive_black_frames, 4);
+
+        health.record(&Ok(ScreenshotOutcome::Duplicate), now);
+        assert_eq!(health.consecutive_black_frames, 0);
+    }
+
+    #[test]
+    fn test_is_user_away() {
+        assert!(!is_user_away(None));
+        assert!(!is_user_away(Some(5.0)));
+        assert!(is_user_away(Some(AWAY_IDLE_SECS)));
+        assert!(is_user_away(Some(3600.0)));
+    }
+
+    #[test]
+    fn test_avif_output_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let day_dir = temp_dir.path().to_path_buf();
+        let avif = OutputFormat::Avif { quality: 50 };
+
+        assert_eq!(avif.extension(), "avif");
+        assert_eq!(avif.mime_type(), "image/avif");
+        fs::write(day_dir.join("00001.png"), "test").unwrap();
+        assert_eq!(next_filename(&day_dir, &avif, DEFAULT_FILENAME_WIDTH).unwrap(), "00002.avif");
+        assert_eq!(mime_type_for(&day_dir.join("00002.avif")), "image/avif");
+
+        let photographer = Photographer::with_root_path(temp_dir.path().join("root")).unwrap();
+        let result = photographer.set_output_format(avif);
+        if avif.is_supported() {
+            assert!(result.is_ok());
+            assert_eq!(photographer.get_config().output_format, avif);
+        } else {
+            assert!(matches!(result, Err(Error::UnsupportedOutputFormat { .. })));
+            assert_eq!(photographer.get_config().output_format, OutputFormat::Png);
+        }
+
+        assert!(matches!(
+            photographer.set_output_format(OutputFormat::Avif { quality: 101 }),
+            Err(Error::InvalidSetting { .. })
+        ));
+    }
+
+    #[test]
+    fn test_set_output_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        assert_eq!(photographer.get_config().output_format, OutputFormat::Png);
+
+        let webp = OutputFormat::WebP { lossless: false, quality: 80 };
+        let result = photographer.set_output_format(webp);
+        if webp.is_supported() {
+            assert!(result.is_ok());
+            assert_eq!(photographer.get_config().output_format, webp);
+        } else {
+            assert!(matches!(result, Err(Error::UnsupportedOutputFormat { .. })));
+            assert_eq!(photographer.get_config().output_format, OutputFormat::Png);
+        }
+
+        let too_high = OutputFormat::WebP { lossless: false, quality: 101 };
+        assert!(matches!(
+            photographer.set_output_format(too_high),
+            Err(Error::InvalidSetting { .. })
+        ));
+    }
+
+    #[test]
+    fn test_set_display_output_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let photographer = Photographer::with_root_path(temp_dir.path().to_path_buf()).unwrap();
+
+        let config = photographer.get_config();
+        assert_eq!(config.output_format_for(1), &OutputFormat::Png);
+
+        let too_high = OutputFormat::Avif { quality: 101 };
+        assert!(matches!(
+            photographer.set_display_output_format(1, Some(too_high)),
+            Err(Error::InvalidSetting { .. })
+        ));
+
+        let webp = OutputFormat::WebP { lossless: false, quality: 80 };
+        if webp.is_supported() {
+            photographer.set_display_output_format(1, Some(webp)).unwrap();
+            let config = photographer.get_config();
+            assert_eq!(config.output_format_for(1), &webp);
+            assert_eq!(config.output_format_for(2), &OutputFormat::Png);
+        }
+
+        photographer.set_display_output_format(1, None).unwrap();
+        assert!(photographer.get_config().display_output_formats.is_empty());
+    }
+
+    #[test]
+    fn test_display_output_formats_compile_together() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let day_dir = DirLayout::Flat.day_dir(root, date);
+        fs::create_dir_all(&day_dir).unwrap();
+
+        let mut config = TimelapseConfig::default();
+        let webp = OutputFormat::WebP { lossless: false, quality: 80 };
+        config.display_output_formats.insert(2, webp);
+        // Focus moves between displays 1 and 2 over the day
+        for (frame_number, display_id) in [(1, 1), (2, 2), (3, 1), (4, 2)] {
+            let format = config.output_format_for(display_id);
+            fs::write(day_dir.join(frame_filename(frame_number, format, 5)), "test").unwrap();
+        }
+
+        let list = concat_list(&day_frame_paths(root, date).unwrap(), 30);
+        let listed: Vec<&str> = list.lines().filter(|line| line.starts_with("file")).collect();
+        assert_eq!(listed.len(), 5);
+        for name in ["00001.png", "00002.webp", "00003.png", "00004.webp"] {
+            assert!(list.contains(name), "{} missing from {}", name, list);
+        }
+    }
+
+    #[test]
+    fn test_output_format_for_falls_back_to_global() {
+        let webp = OutputFormat::WebP { lossless: true, quality: 100 };
+        let mut config = TimelapseConfig {
+            output_format: webp,
+            ..TimelapseConfig::default()
+        };
+        config.display_output_formats.insert(2, OutputFormat::Png);
+
+        assert_eq!(config.output_format_for(1), &webp);
+        assert_eq!(config.output_format_for(2), &OutputFormat::Png);
+
+        config.display_output_formats.insert(3, OutputFormat::Avif { quality: 101 });
+        assert!(matches!(config.validate(), Err(Error::InvalidSetting { .. })));
+    }
+
     #[test]
     fn test_next_filename_ignores_non_numeric() {
         let temp_dir = TempDir::new().unwrap();
@@ -589,7 +8199,7 @@ mod tests {
         fs::write(day_dir.join("test.png"), "test").unwrap();
         fs::write(day_dir.join("image.jpg"), "test").unwrap();
 
-        let result = next_filename(&day_dir);
+        let result = next_filename(&day_dir, &OutputFormat::Png, DEFAULT_FILENAME_WIDTH);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "00002.png");
     }
@@ -616,12 +8226,16 @@ mod tests {
 
     #[test]
     fn test_window_overlaps_screen_edge_case() {
-        // Window partially on screen, but center is inside
+        // Window partially on screen, with its center exactly at the edge
         let window = (175, 175, 50, 50);
         let screen = (0, 0, 200, 200);
 
-        // Center is at (200, 200), which is exactly at the edge
-        assert!(!window_overlaps_screen(window, screen));
+        assert!(window_overlaps_screen(window, screen));
+        assert_eq!(overlap_area(window, screen), 25 * 25);
+
+        // Touching the edge without overlapping doesn't count
+        assert!(!window_overlaps_screen((200, 0, 50, 50), screen));
+        assert_eq!(overlap_area((200, 0, 50, 50), screen), 0);
     }
 
     #[test]
@@ -635,6 +8249,56 @@ mod tests {
         assert!(window_overlaps_screen(window, screen2));
     }
 
+    #[test]
+    fn test_window_overlaps_screen_straddling_monitors() {
+        // Center at x=1910 is on the first screen, but most of the window isn't
+        let window = (1620, 100, 580, 500);
+        let screen1 = (0, 0, 1920, 1080);
+        let screen2 = (1920, 0, 1920, 1080);
+
+        assert!(window_overlaps_screen(window, screen1));
+        assert!(window_overlaps_screen(window, screen2));
+        assert_eq!(overlap_area(window, screen1), 300 * 500);
+        assert_eq!(overlap_area(window, screen2), 280 * 500);
+        assert_eq!(best_overlap_screen(window, &[screen1, screen2]), Some(0));
+
+        let window = (1700, 100, 580, 500);
+        assert_eq!(overlap_area(window, screen1), 220 * 500);
+        assert_eq!(overlap_area(window, screen2), 360 * 500);
+        assert_eq!(best_overlap_screen(window, &[screen1, screen2]), Some(1));
+    }
+
+    #[test]
+    fn test_overlap_area_negative_coordinates() {
+        // A monitor to the left of the primary has negative x
+        let window = (-300, 0, 400, 200);
+        let left = (-1440, 0, 1440, 900);
+        let primary = (0, 0, 1920, 1080);
+
+        assert_eq!(overlap_area(window, left), 300 * 200);
+        assert_eq!(overlap_area(window, primary), 100 * 200);
+        assert_eq!(best_overlap_screen(window, &[primary, left]), Some(1));
+    }
+
+    #[test]
+    fn test_best_overlap_screen_straddling_window() {
+        let screens = [(0, 0, 1920, 1080), (1920, 0, 1920, 1080)];
+
+        // 700px on the first screen, 300px on the second
+        assert_eq!(best_overlap_screen((1220, 100, 1000, 500), &screens), Some(0));
+        // 300px on the first screen, 700px on the second
+        assert_eq!(best_overlap_screen((1620, 100, 1000, 500), &screens), Some(1));
+    }
+
+    #[test]
+    fn test_best_overlap_screen_outside_all_screens() {
+        // The window is still on a display that has since been unplugged
+        let screens = [(0, 0, 1920, 1080)];
+
+        assert_eq!(best_overlap_screen((2000, 100, 500, 500), &screens), None);
+        assert_eq!(best_overlap_screen((0, 0, 100, 100), &[]), None);
+    }
+
     #[test]
     fn test_error_display() {
         let error = Error::UnableToFindHomeDir;
@@ -4,10 +4,20 @@ import { readFile } from "@tauri-apps/plugin-fs";
 import React from "react";
 
 import "./App.css";
+import { folderDate, sortFoldersByDate } from "./dayFolders";
 import { useFiles, useFolders, useVideos } from "./hooks/useFolders";
 
 type ViewMode = "images" | "videos";
 
+// Frames per second pulled out of a video for scrubbing
+const VIDEO_EXTRACTION_FPS = 30;
+
+interface VideoFrames {
+  cache_folder: string;
+  frame_count: number;
+  frame_pattern: string;
+}
+
 export function App(): React.ReactNode {
   const { folders, foldersError, refreshFolders } = useFolders();
   const { videos, videosError, refreshVideos } = useVideos();
@@ -42,14 +52,13 @@ export function App(): React.ReactNode {
   React.useEffect(() => {
     if (viewMode === "images" && folders.length > 0 && !selectedFolder) {
       const todayFolder = folders.find(
-        (folder) => folder === currentDateFolder,
+        (folder) => folderDate(folder) === currentDateFolder,
       );
       if (todayFolder) {
         setSelectedFolder(todayFolder);
       } else {
         // If today's folder doesn't exist, select the most recent one
-        const sortedFolders = [...folders].sort().reverse();
-        setSelectedFolder(sortedFolders[0]);
+        setSelectedFolder(sortFoldersByDate(folders)[0]);
       }
     }
   }, [folders, selectedFolder, currentDateFolder, viewMode]);
@@ -90,12 +99,16 @@ export function App(): React.ReactNode {
         console.log("Extracting frames from video:", selectedVideo);
 
         // Call Tauri command to extract frames (uses cache if available)
-        const cacheFolder = await invoke<string>("extract_video_frames", {
+        const frames = await invoke<VideoFrames>("extract_video_frames", {
           videoFilename: selectedVideo,
+          fps: VIDEO_EXTRACTION_FPS,
         });
 
-        console.log("Frames extracted to cache folder:", cacheFolder);
-        setVideoCacheFolder(cacheFolder);
+        console.log(
+          `Extracted ${frames.frame_count} frames to cache folder:`,
+          frames.cache_folder,
+        );
+        setVideoCacheFolder(frames.cache_folder);
         setIsExtractingFrames(false);
       } catch (error) {
         console.error("Error extracting frames:", error);
@@ -368,14 +381,11 @@ export function App(): React.ReactNode {
                 className="bg-gray-700 text-white px-3 py-1 rounded border border-gray-600 focus:outline-none focus:ring-2 focus:ring-blue-500"
               >
                 <option value="">Select a date…</option>
-                {[...folders]
-                  .sort()
-                  .reverse()
-                  .map((folder) => (
-                    <option key={folder} value={folder}>
-                      {folder} {folder === currentDateFolder ? "(Today)" : ""}
-                    </option>
-                  ))}
+                {sortFoldersByDate(folders).map((folder) => (
+                  <option key={folder} value={folder}>
+                    {folder} {folderDate(folder) === currentDateFolder ? "(Today)" : ""}
+                  </option>
+                ))}
               </select>
               {selectedFolder && files.length > 0 && (
                 <>
@@ -0,0 +1,13 @@
+// Day folders are YYYY-MM-DD in the flat layout and YYYY/MM/DD in the monthly one
+export const YEAR_FOLDER = /^\d{4}$/;
+export const MONTH_OR_DAY_FOLDER = /^\d{2}$/;
+
+/** The YYYY-MM-DD date a day folder path holds, in either layout */
+export function folderDate(folder: string): string {
+  return folder.split("/").join("-");
+}
+
+/** Day folder paths ordered by the date they hold, most recent first */
+export function sortFoldersByDate(folders: Array<string>): Array<string> {
+  return [...folders].sort((a, b) => folderDate(b).localeCompare(folderDate(a)));
+}
@@ -65,6 +65,36 @@ describe('useFolders', () => {
     });
   });
 
+  it('should list monthly layout days as YYYY/MM/DD paths', async () => {
+    type Entry = { name: string; isDirectory: boolean; isFile: boolean };
+    const listings: Record<string, Array<Entry>> = {
+      Timelapse: [
+        { name: '2025-01-14', isDirectory: true, isFile: false },
+        { name: '2025', isDirectory: true, isFile: false },
+        { name: '.cache', isDirectory: true, isFile: false },
+      ],
+      'Timelapse/2025': [
+        { name: '01', isDirectory: true, isFile: false },
+        { name: 'notes', isDirectory: true, isFile: false },
+      ],
+      'Timelapse/2025/01': [
+        { name: '15', isDirectory: true, isFile: false },
+        { name: '16', isDirectory: true, isFile: false },
+        { name: 'notes.txt', isDirectory: false, isFile: true },
+      ],
+    };
+    vi.mocked(readDir).mockImplementation(async (path) => listings[String(path)] as any);
+
+    const { result } = renderHook(() => useFolders());
+
+    await waitFor(() => {
+      expect(result.current.folders).toEqual(['2025-01-14', '2025/01/15', '2025/01/16']);
+    });
+    expect(readDir).toHaveBeenCalledWith('Timelapse/2025/01', {
+      baseDir: BaseDirectory.Home,
+    });
+  });
+
   it('should refresh folders when refreshFolders is called', async () => {
     const initialFolders = [
       { name: 'folder1', isDirectory: true, isFile: false },
@@ -2,6 +2,8 @@ import { BaseDirectory } from "@tauri-apps/api/path";
 import { readDir } from "@tauri-apps/plugin-fs";
 import React from "react";
 
+import { MONTH_OR_DAY_FOLDER, YEAR_FOLDER } from "../dayFolders";
+
 function ensureError(val: unknown): Error {
   if (val instanceof Error) {
     return val;
@@ -9,6 +11,31 @@ function ensureError(val: unknown): Error {
   return new Error(String(val));
 }
 
+async function subfolders(path: string): Promise<Array<string>> {
+  const entries = await readDir(path, {
+    baseDir: BaseDirectory.Home,
+  });
+  return entries
+    .filter((entry) => entry.isDirectory)
+    .filter((entry) => !entry.name.startsWith(".")) // Exclude hidden folders like .cache
+    .map((entry) => entry.name);
+}
+
+// Day folders of the monthly layout under `Timelapse/<year>`, as YYYY/MM/DD paths
+async function monthlyDayFolders(year: string): Promise<Array<string>> {
+  const days: Array<string> = [];
+  const months = (await subfolders(`Timelapse/${year}`)).filter((name) =>
+    MONTH_OR_DAY_FOLDER.test(name),
+  );
+  for (const month of months) {
+    const monthDays = (await subfolders(`Timelapse/${year}/${month}`)).filter(
+      (name) => MONTH_OR_DAY_FOLDER.test(name),
+    );
+    days.push(...monthDays.map((day) => `${year}/${month}/${day}`));
+  }
+  return days;
+}
+
 export function useFolders(): {
   folders: Array<string>;
   foldersError: Error | null;
@@ -20,13 +47,14 @@ export function useFolders(): {
   const loadFolders = React.useCallback(async (): Promise<void> => {
     try {
       setFoldersError(null);
-      const entries = await readDir("Timelapse", {
-        baseDir: BaseDirectory.Home,
-      });
-      const folderList = entries
-        .filter((entry) => entry.isDirectory)
-        .filter((entry) => !entry.name.startsWith(".")) // Exclude hidden folders like .cache
-        .map((entry) => entry.name);
+      const folderList: Array<string> = [];
+      for (const name of await subfolders("Timelapse")) {
+        if (YEAR_FOLDER.test(name)) {
+          folderList.push(...(await monthlyDayFolders(name)));
+        } else {
+          folderList.push(name);
+        }
+      }
       setFolders(folderList);
     } catch (error) {
       setFoldersError(ensureError(error));